@@ -1,9 +1,10 @@
 use clap::{Parser, Subcommand};
-use sensei_common::{AskRequest, AskResponse};
+use sensei_common::{AskRequest, AskResponse, Classification};
 use serde_json::json;
 use std::error::Error;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 
 // HTTP / UDS handling
 use http_body_util::{BodyExt, Full};
@@ -21,6 +22,10 @@ struct Cli {
     #[arg(short, long)]
     url: Option<String>,
 
+    /// API key sent to the server (falls back to SENSEI_API_KEY)
+    #[arg(long)]
+    api_key: Option<String>,
+
     /// Shortcut to ask a question
     #[arg(short, long)]
     ask: Option<String>,
@@ -41,6 +46,41 @@ enum Commands {
     Add {
         #[arg(value_name = "FILE")]
         path: PathBuf,
+        /// Classification level: unclassified, confidential, secret or top_secret
+        #[arg(long, default_value = "unclassified")]
+        classification: String,
+        /// Tag the document (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+    /// Manage the knowledge base
+    #[command(subcommand)]
+    Kb(KbCommands),
+}
+
+#[derive(Subcommand, Debug)]
+enum KbCommands {
+    /// List documents, newest first
+    Ls {
+        /// Only chunks of this source (file path)
+        #[arg(long)]
+        source: Option<String>,
+        /// Only documents with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+        #[arg(long, default_value_t = 0)]
+        offset: i64,
+    },
+    /// Print a document
+    Show { id: i64 },
+    /// Delete a document, or every chunk of a source
+    Rm {
+        #[arg(required_unless_present = "source")]
+        id: Option<i64>,
+        #[arg(long, conflicts_with = "id")]
+        source: Option<String>,
     },
 }
 
@@ -56,6 +96,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     };
 
     let target_url = cli.url.unwrap_or(default_url);
+    let _ = API_KEY.set(cli.api_key.or_else(|| std::env::var("SENSEI_API_KEY").ok()));
 
     // 1. Check --ask flag
     if let Some(prompt) = cli.ask {
@@ -66,7 +107,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
     if let Some(cmd) = cli.command {
         match cmd {
             Commands::Ask { prompt } => return print_ask(&target_url, &prompt).await,
-            Commands::Add { path } => return handle_add(&target_url, path).await,
+            Commands::Add {
+                path,
+                classification,
+                tags,
+            } => return handle_add(&target_url, path, &classification, tags).await,
+            Commands::Kb(command) => return handle_kb(&target_url, command).await,
         }
     }
 
@@ -84,8 +130,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
 async fn print_ask(url: &str, prompt: &str) -> Result<(), Box<dyn Error>> {
     println!("Sending request to {}/v1/ask...", url);
     match send_ask_request(url, prompt).await {
-        Ok(content) => {
-            println!("\n🥋 Sensei says:\n{}", content);
+        Ok(response) => {
+            println!("\n🥋 Sensei says:\n{}", render_answer(&response));
         }
         Err(e) => {
             eprintln!("❌ Error: {}", e);
@@ -94,15 +140,20 @@ async fn print_ask(url: &str, prompt: &str) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-// Generic sender that switches between UDS (Hyper) and TCP (Reqwest)
-pub async fn send_ask_request(
+/// API key attached to every request, set once from the CLI.
+static API_KEY: OnceLock<Option<String>> = OnceLock::new();
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Send a request to the server over UDS (Hyper) or TCP (Reqwest).
+/// Returns the status code and the raw body.
+pub async fn send_request(
     base_url: &str,
-    prompt: &str,
-) -> Result<String, Box<dyn Error + Send + Sync>> {
-    let req_body = AskRequest {
-        prompt: prompt.to_string(),
-    };
-    let json_body = serde_json::to_string(&req_body)?;
+    method: &str,
+    path: &str,
+    body: Option<String>,
+) -> Result<(u16, Bytes), BoxError> {
+    let api_key = API_KEY.get().cloned().flatten();
 
     if base_url.starts_with("unix://") {
         #[cfg(unix)]
@@ -119,22 +170,20 @@ pub async fn send_ask_request(
                 }
             });
 
-            let req = Request::builder()
-                .method("POST")
-                .uri("http://localhost/v1/ask") // UDS ignores host, path matters
+            let mut req = Request::builder()
+                .method(method)
+                .uri(format!("http://localhost{}", path)) // UDS ignores host, path matters
                 .header("Host", "localhost")
-                .header("Content-Type", "application/json")
-                .body(Full::new(Bytes::from(json_body)))?;
-
-            let res = sender.send_request(req).await?;
-
-            if !res.status().is_success() {
-                return Err(format!("Server Error: {}", res.status()).into());
+                .header("Content-Type", "application/json");
+            if let Some(key) = &api_key {
+                req = req.header("Authorization", format!("Bearer {}", key));
             }
+            let req = req.body(Full::new(Bytes::from(body.unwrap_or_default())))?;
 
+            let res = sender.send_request(req).await?;
+            let status = res.status().as_u16();
             let body_bytes = res.collect().await?.to_bytes();
-            let result: AskResponse = serde_json::from_slice(&body_bytes)?;
-            Ok(result.content)
+            Ok((status, body_bytes))
         }
         #[cfg(not(unix))]
         {
@@ -143,71 +192,197 @@ pub async fn send_ask_request(
     } else {
         // Standard HTTP via Reqwest
         let client = reqwest::Client::new();
-        let url = format!("{}/v1/ask", base_url.trim_end_matches('/'));
-        let res = client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .body(json_body)
-            .send()
-            .await?;
-
-        if !res.status().is_success() {
-            return Err(format!("Server Error: {}", res.status()).into());
+        let url = format!("{}{}", base_url.trim_end_matches('/'), path);
+        let mut req = client
+            .request(reqwest::Method::from_bytes(method.as_bytes())?, &url)
+            .header("Content-Type", "application/json");
+        if let Some(key) = &api_key {
+            req = req.bearer_auth(key);
         }
-        let result: AskResponse = res.json().await?;
-        Ok(result.content)
+        if let Some(body) = body {
+            req = req.body(body);
+        }
+
+        let res = req.send().await?;
+        let status = res.status().as_u16();
+        Ok((status, res.bytes().await?))
     }
 }
 
-async fn handle_add(base_url: &str, path: PathBuf) -> Result<(), Box<dyn Error>> {
-    let content = fs::read_to_string(&path)?;
-    // Simplified JSON construction
-    let json_body = serde_json::to_string(&json!({ "content": content }))?;
+/// Turn a non-2xx response into an error carrying the server message.
+fn check_status(status: u16, body: &[u8]) -> Result<(), BoxError> {
+    if (200..300).contains(&status) {
+        return Ok(());
+    }
+    let message = serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(str::to_string))
+        .unwrap_or_default();
+    Err(format!("Server Error: {} {}", status, message)
+        .trim_end()
+        .into())
+}
 
-    if base_url.starts_with("unix://") {
-        #[cfg(unix)]
-        {
-            let socket_path = base_url.trim_start_matches("unix://");
-            let stream = UnixStream::connect(socket_path).await?;
-            let io = TokioIo::new(stream);
-            let (mut sender, conn) = hyper::client::conn::http1::handshake(io).await?;
-            tokio::task::spawn(async move { if (conn.await).is_err() {} });
+pub async fn send_ask_request(base_url: &str, prompt: &str) -> Result<AskResponse, BoxError> {
+    let req_body = AskRequest {
+        prompt: prompt.to_string(),
+    };
+    let json_body = serde_json::to_string(&req_body)?;
 
-            let req = Request::builder()
-                .method("POST")
-                .uri("http://localhost/v1/knowledge/add")
-                .header("Host", "localhost")
-                .header("Content-Type", "application/json")
-                .body(Full::new(Bytes::from(json_body)))?;
+    let (status, body) = send_request(base_url, "POST", "/v1/ask", Some(json_body)).await?;
+    check_status(status, &body)?;
+    Ok(serde_json::from_slice(&body)?)
+}
 
-            let res = sender.send_request(req).await?;
+/// The answer followed by the knowledge it cites, one source per line.
+pub fn render_answer(response: &AskResponse) -> String {
+    let mut out = response.content.trim_end().to_string();
+    if !response.sources.is_empty() {
+        out.push_str("\n\nSources:");
+        for citation in &response.sources {
+            out.push_str(&format!("\n{}\n    {}", citation, citation.snippet));
+        }
+    }
+    out
+}
 
-            if res.status().is_success() {
-                println!("✅ Document added.");
+async fn handle_add(
+    base_url: &str,
+    path: PathBuf,
+    classification: &str,
+    tags: Vec<String>,
+) -> Result<(), Box<dyn Error>> {
+    let content = fs::read_to_string(&path)?;
+    let classification: Classification = classification.parse()?;
+    // The absolute path identifies the document, so re-adding it updates the stored version.
+    let source = fs::canonicalize(&path).unwrap_or(path);
+    let json_body = serde_json::to_string(&json!({
+        "content": content,
+        "classification": classification,
+        "source": source.to_string_lossy(),
+        "tags": tags
+    }))?;
+
+    let result = match send_request(base_url, "POST", "/v1/knowledge/add", Some(json_body)).await {
+        Ok((status, body)) => check_status(status, &body).map(|_| body),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(body) => {
+            let report: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+            if report["relabeled"].as_u64().is_some_and(|n| n > 0) {
+                println!("✅ Document unchanged, classification and tags updated.");
+            } else if report["unchanged"].as_bool() == Some(true) {
+                println!("✅ Document unchanged, nothing to update.");
             } else {
-                eprintln!("❌ Failed: {}", res.status());
+                println!("✅ Document added ({} chunks).", report["chunks"]);
             }
-            Ok(())
-        }
-        #[cfg(not(unix))]
-        {
-            Err("Unix sockets not supported".into())
         }
-    } else {
-        let client = reqwest::Client::new();
-        let url = format!("{}/v1/knowledge/add", base_url.trim_end_matches('/'));
-        let res = client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .body(json_body)
-            .send()
+        Err(e) => eprintln!("❌ Failed: {}", e),
+    }
+    Ok(())
+}
+
+async fn handle_kb(base_url: &str, command: KbCommands) -> Result<(), Box<dyn Error>> {
+    match command {
+        KbCommands::Ls {
+            source,
+            tag,
+            limit,
+            offset,
+        } => {
+            let mut query = url::form_urlencoded::Serializer::new(String::new());
+            query.append_pair("limit", &limit.to_string());
+            query.append_pair("offset", &offset.to_string());
+            if let Some(source) = &source {
+                query.append_pair("source", source);
+            }
+            if let Some(tag) = &tag {
+                query.append_pair("tag", tag);
+            }
+            let page = kb_request(
+                base_url,
+                "GET",
+                &format!("/v1/knowledge?{}", query.finish()),
+            )
             .await?;
 
-        if res.status().is_success() {
-            println!("✅ Document added.");
-        } else {
-            eprintln!("❌ Failed: {}", res.status());
+            let documents = page["documents"].as_array().cloned().unwrap_or_default();
+            for doc in &documents {
+                let meta = &doc["metadata"];
+                let origin = match (meta["source"].as_str(), meta["chunk_index"].as_u64()) {
+                    (Some(source), Some(chunk)) => format!("{}#{}", source, chunk),
+                    (Some(source), None) => source.to_string(),
+                    _ => "(inline)".to_string(),
+                };
+                let tags = meta["tags"]
+                    .as_array()
+                    .map(|tags| {
+                        tags.iter()
+                            .filter_map(|t| t.as_str())
+                            .collect::<Vec<_>>()
+                            .join(",")
+                    })
+                    .unwrap_or_default();
+                let snippet: String = doc["snippet"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .replace('\n', " ")
+                    .chars()
+                    .take(60)
+                    .collect();
+                println!(
+                    "{:>6}  {:<12}  {}  [{}]  {}",
+                    doc["id"],
+                    meta["classification"].as_str().unwrap_or("unclassified"),
+                    origin,
+                    tags,
+                    snippet
+                );
+            }
+            println!(
+                "-- {} of {} documents",
+                documents.len(),
+                page["total"].as_i64().unwrap_or(0)
+            );
         }
-        Ok(())
+        KbCommands::Show { id } => {
+            let doc = kb_request(base_url, "GET", &format!("/v1/knowledge/{}", id)).await?;
+            println!("# Document {}", id);
+            println!("{}", serde_json::to_string_pretty(&doc["metadata"])?);
+            println!("\n{}", doc["content"].as_str().unwrap_or_default());
+        }
+        KbCommands::Rm { id, source } => match (id, source) {
+            (_, Some(source)) => {
+                let query = url::form_urlencoded::Serializer::new(String::new())
+                    .append_pair("source", &source)
+                    .finish();
+                let result =
+                    kb_request(base_url, "DELETE", &format!("/v1/knowledge?{}", query)).await?;
+                println!("🗑️  Deleted {} chunks of {}", result["deleted"], source);
+            }
+            (Some(id), None) => {
+                kb_request(base_url, "DELETE", &format!("/v1/knowledge/{}", id)).await?;
+                println!("🗑️  Deleted document {}", id);
+            }
+            (None, None) => unreachable!("clap requires an id or --source"),
+        },
+    }
+    Ok(())
+}
+
+/// Call a knowledge endpoint and parse its JSON answer (`null` for empty bodies).
+async fn kb_request(
+    base_url: &str,
+    method: &str,
+    path: &str,
+) -> Result<serde_json::Value, Box<dyn Error>> {
+    let (status, body) = send_request(base_url, method, path, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    check_status(status, &body).map_err(|e| e.to_string())?;
+    if body.is_empty() {
+        return Ok(serde_json::Value::Null);
     }
+    Ok(serde_json::from_slice(&body)?)
 }
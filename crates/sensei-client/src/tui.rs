@@ -51,20 +51,15 @@ pub async fn run_tui(base_url: String) -> anyhow::Result<()> {
                 .unwrap_or_else(|| Duration::from_secs(0));
 
             if event::poll(timeout).expect("poll failed") {
-                match event::read().expect("read failed") {
-                    Event::Key(key) => {
-                        if key.kind == KeyEventKind::Press
-                            && tx_input.send(AppEvent::Input(key)).is_err()
-                        {
-                            return;
-                        }
-                    }
-                    Event::Mouse(mouse) => {
-                        if tx_input.send(AppEvent::Mouse(mouse)).is_err() {
-                            return;
-                        }
+                let sent = match event::read().expect("read failed") {
+                    Event::Key(key) if key.kind == KeyEventKind::Press => {
+                        tx_input.send(AppEvent::Input(key))
                     }
-                    _ => {}
+                    Event::Mouse(mouse) => tx_input.send(AppEvent::Mouse(mouse)),
+                    _ => Ok(()),
+                };
+                if sent.is_err() {
+                    return;
                 }
             }
 
@@ -133,6 +128,8 @@ pub async fn run_tui(base_url: String) -> anyhow::Result<()> {
                         style = style.fg(Color::Yellow);
                     } else if m.starts_with("Error:") {
                         style = style.fg(Color::Red);
+                    } else if m == "Sources:" || is_citation(m) {
+                        style = style.fg(Color::DarkGray);
                     } else if m.starts_with("# ") || m.starts_with("## ") {
                         style = style.fg(Color::Blue).add_modifier(Modifier::BOLD);
                     } else if m.contains("🦈 SENSEI") {
@@ -302,7 +299,7 @@ pub async fn run_tui(base_url: String) -> anyhow::Result<()> {
                                     // Use our new UDS-capable sender
                                     let res = crate::send_ask_request(&base_url, &input).await;
                                     let msg = match res {
-                                        Ok(content) => content,
+                                        Ok(response) => crate::render_answer(&response),
                                         Err(e) => format!("Error: {}", e),
                                     };
                                     tx.send(AppEvent::ServerResponse(msg)).unwrap();
@@ -343,3 +340,8 @@ pub async fn run_tui(base_url: String) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// A source line rendered by `render_answer`, e.g. `[1] runbook.md <sensei://knowledge/4> ...`.
+fn is_citation(line: &str) -> bool {
+    line.starts_with('[') && line.contains("<sensei://knowledge/")
+}
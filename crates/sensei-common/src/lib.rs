@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
 
 /// Represents a health check response.
 #[derive(Serialize, Deserialize, Debug)]
@@ -17,6 +18,87 @@ pub struct AskRequest {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AskResponse {
     pub content: String,
+    /// Knowledge the answer was given, in the order it is cited (`[1]`, `[2]`, ...).
+    #[serde(default)]
+    pub sources: Vec<Citation>,
+}
+
+/// URI scheme of knowledge base documents, shared by the HTTP API and MCP resources.
+pub const KNOWLEDGE_URI_PREFIX: &str = "sensei://knowledge/";
+
+pub fn knowledge_uri(id: i64) -> String {
+    format!("{}{}", KNOWLEDGE_URI_PREFIX, id)
+}
+
+/// A knowledge base chunk retrieved for an answer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Citation {
+    /// Number the answer cites it by, e.g. `[1]`.
+    pub index: usize,
+    /// `sensei://knowledge/{id}`
+    pub uri: String,
+    /// Where the document came from, e.g. a file path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Retrieval score, higher is more relevant.
+    pub score: f64,
+    pub snippet: String,
+    /// Label of the document the chunk belongs to.
+    #[serde(default)]
+    pub classification: Classification,
+}
+
+impl fmt::Display for Citation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] ", self.index)?;
+        if let Some(source) = &self.source {
+            write!(f, "{} ", source)?;
+        }
+        write!(f, "<{}> (score {:.4})", self.uri, self.score)
+    }
+}
+
+/// A single frame emitted by the streaming ask endpoint (`/v1/ask/stream`).
+/// Serialized as the SSE `data` payload; the SSE event name mirrors `type`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AskStreamEvent {
+    /// The router picked a category for the query.
+    Routing {
+        category: AgentCategory,
+        query: String,
+    },
+    /// RAG retrieval finished with `documents` hits.
+    Rag {
+        documents: usize,
+        #[serde(default)]
+        sources: Vec<Citation>,
+    },
+    /// An agent handed the task over to another one (A2A).
+    Delegation {
+        from: AgentCategory,
+        to: AgentCategory,
+    },
+    /// A text delta of the answer being generated.
+    Token { content: String },
+    /// The final assembled answer, as persisted in the session.
+    Done { content: String },
+    /// The request failed before an answer could be produced.
+    Error { message: String },
+}
+
+impl AskStreamEvent {
+    /// SSE event name for this frame.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AskStreamEvent::Routing { .. } => "routing",
+            AskStreamEvent::Rag { .. } => "rag",
+            AskStreamEvent::Delegation { .. } => "delegation",
+            AskStreamEvent::Token { .. } => "token",
+            AskStreamEvent::Done { .. } => "done",
+            AskStreamEvent::Error { .. } => "error",
+        }
+    }
 }
 
 /// Generic Agent Category (Wrapper around String).
@@ -59,3 +141,64 @@ impl<'de> Deserialize<'de> for AgentCategory {
         Ok(AgentCategory::new(&s))
     }
 }
+
+/// Mandatory access control level of a document or an agent clearance.
+/// Levels are ordered: an agent may read documents at or below its clearance ("no read up").
+#[derive(
+    Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Classification {
+    #[default]
+    Unclassified,
+    Confidential,
+    Secret,
+    TopSecret,
+}
+
+impl Classification {
+    pub const ALL: [Classification; 4] = [
+        Classification::Unclassified,
+        Classification::Confidential,
+        Classification::Secret,
+        Classification::TopSecret,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Classification::Unclassified => "unclassified",
+            Classification::Confidential => "confidential",
+            Classification::Secret => "secret",
+            Classification::TopSecret => "top_secret",
+        }
+    }
+
+    /// Whether a subject cleared at `self` may read data labelled `label`.
+    pub fn dominates(&self, label: Classification) -> bool {
+        *self >= label
+    }
+}
+
+impl fmt::Display for Classification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str().replace('_', " ").to_uppercase())
+    }
+}
+
+impl FromStr for Classification {
+    type Err = String;
+
+    /// Accepts `top_secret`, `top-secret`, `Top Secret`, ...
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_lowercase().replace(['-', ' '], "_");
+        Classification::ALL
+            .into_iter()
+            .find(|c| c.as_str() == normalized)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown classification '{}'. Expected one of: unclassified, confidential, secret, top_secret",
+                    s
+                )
+            })
+    }
+}
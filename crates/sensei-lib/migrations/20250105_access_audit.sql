@@ -0,0 +1,10 @@
+-- Documents carry their classification in `documents.metadata` ({"classification": "secret"}).
+-- Reads denied by the "no read up" rule are recorded here.
+CREATE TABLE IF NOT EXISTS access_audit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    subject TEXT NOT NULL,
+    clearance TEXT NOT NULL,
+    document_id INTEGER NOT NULL,
+    classification TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,14 @@
+-- Record the embedding model behind every vector so vectors of different models are never compared.
+-- Everything embedded so far came from gemini-embedding-001.
+ALTER TABLE documents ADD COLUMN embedding_model TEXT;
+UPDATE documents SET embedding_model = 'gemini-embedding-001';
+
+ALTER TABLE router_cache ADD COLUMN embedding_model TEXT;
+UPDATE router_cache SET embedding_model = 'gemini-embedding-001';
+
+-- Dimension of each vec0 table, which is fixed when the table is created.
+CREATE TABLE IF NOT EXISTS vector_tables (
+    name TEXT PRIMARY KEY NOT NULL,
+    dimension INTEGER NOT NULL
+);
+INSERT OR IGNORE INTO vector_tables (name, dimension) VALUES ('vec_items', 3072), ('vec_router_cache', 3072);
@@ -0,0 +1,2 @@
+-- Identity of the caller that opened each session; NULL for sessions opened without one.
+ALTER TABLE sessions ADD COLUMN owner TEXT;
@@ -0,0 +1,3 @@
+-- Highest classification of the knowledge and history an answer was built from.
+-- History is only replayed to agents cleared for it ("no read up").
+ALTER TABLE messages ADD COLUMN classification TEXT NOT NULL DEFAULT 'unclassified';
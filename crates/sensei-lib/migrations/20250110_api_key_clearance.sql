@@ -0,0 +1,3 @@
+-- Highest classification of knowledge base documents a key may read ("no read up").
+-- Keys with the admin scope read everything.
+ALTER TABLE api_keys ADD COLUMN clearance TEXT NOT NULL DEFAULT 'unclassified';
@@ -0,0 +1,6 @@
+-- Sessions and audit records name their key ("key:<name>"), so a name must identify
+-- exactly one key, revoked ones included. Older duplicates get their id appended.
+UPDATE api_keys SET name = name || '-' || substr(id, 1, 8)
+WHERE rowid NOT IN (SELECT MIN(rowid) FROM api_keys GROUP BY name);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_api_keys_name ON api_keys(name);
@@ -1,101 +1,103 @@
 use crate::agents::Agent;
+use crate::agents::react::{DEFAULT_MAX_STEPS, NoAction, ReactLoop, ReactOutcome};
 use crate::llm::Llm;
-use crate::tools::Tool;
+use crate::tools::{Tool, ToolRegistry};
 use async_trait::async_trait;
-use sensei_common::AgentCategory;
-use serde::Deserialize;
-use std::collections::HashMap;
+use sensei_common::{AgentCategory, Classification};
 use std::sync::Arc;
 
+const ROLE: &str = "You are an autonomous Action Agent.";
+
 pub struct ToolExecutorAgent {
     llm: Arc<dyn Llm>,
-    tools: HashMap<String, Box<dyn Tool>>,
+    tools: ToolRegistry,
     category: AgentCategory,
-}
-
-#[derive(Deserialize)]
-struct ToolCall {
-    tool_name: String,
-    argument: String,
+    role: String,
+    max_steps: usize,
+    clearance: Classification,
+    rag: bool,
 }
 
 impl ToolExecutorAgent {
     pub fn new(llm: Arc<dyn Llm>, category: AgentCategory) -> Self {
+        Self::with_registry(llm, category, ToolRegistry::new())
+    }
+
+    /// Agent drawing its tools from a (shared) registry.
+    pub fn with_registry(llm: Arc<dyn Llm>, category: AgentCategory, tools: ToolRegistry) -> Self {
         Self {
             llm,
-            tools: HashMap::new(),
+            tools,
             category,
+            role: ROLE.to_string(),
+            max_steps: DEFAULT_MAX_STEPS,
+            clearance: Classification::Unclassified,
+            rag: true,
         }
     }
 
-    pub fn register_tool(&mut self, tool: Box<dyn Tool>) {
-        self.tools.insert(tool.name().to_string(), tool);
-    }
-
-    async fn decide_tool(&self, query: &str) -> Option<ToolCall> {
-        let tools_list = self.tools.keys().cloned().collect::<Vec<_>>().join(", ");
-
-        let prompt = format!(
-            r###"            You are an autonomous Action Agent.
-            Available Tools: [{}] 
-            Task: Analyze the user request and decide which tool to execute.
-            User Request: "{}"
-            Rules:
-            - If the request matches a tool capability, output JSON: {{'tool_name': 'name', 'argument': 'value'}}
-            - Tool "nmap": argument must be a target (IP/Host) e.g. "127.0.0.1".
-            - Tool "system_diagnostic": argument must be one of: "uptime", "disk", "memory", "whoami", "date".
-            - If NO tool matches or arguments are ambiguous, return JSON: {{'tool_name': 'none', 'argument': 'reason'}}
-
-            Output strictly JSON.
-            "###,
-            tools_list, query
-        );
+    /// System prompt of the ReAct loop.
+    pub fn with_role(mut self, role: &str) -> Self {
+        self.role = role.to_string();
+        self
+    }
 
-        let response = self.llm.generate(&prompt).await.ok()?;
+    pub fn with_clearance(mut self, clearance: Classification) -> Self {
+        self.clearance = clearance;
+        self
+    }
 
-        // Robust JSON extraction
-        let start = response.find('{').unwrap_or(0);
-        let end = response.rfind('}').map(|i| i + 1).unwrap_or(response.len());
-        let json_str = &response[start..end];
+    pub fn with_rag(mut self, rag: bool) -> Self {
+        self.rag = rag;
+        self
+    }
 
-        serde_json::from_str::<ToolCall>(json_str).ok()
+    /// Maximum number of tool calls per request.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub fn register_tool(&mut self, tool: Box<dyn Tool>) {
+        self.tools.register(Arc::from(tool));
+    }
+
+    pub fn tools(&self) -> &ToolRegistry {
+        &self.tools
+    }
+
+    /// Run the ReAct loop and return the answer with its step trace.
+    pub async fn run(&self, input: &str) -> Result<ReactOutcome, NoAction> {
+        ReactLoop::new(self.llm.as_ref(), &self.tools, &self.role)
+            .max_steps(self.max_steps)
+            .run(input)
+            .await
     }
 }
 
 #[async_trait]
 impl Agent for ToolExecutorAgent {
     async fn process(&self, input: &str) -> String {
-        // 1. Decide which tool to call
-        if let Some(call) = self.decide_tool(input).await {
-            if call.tool_name == "none" {
-                return format!("I cannot perform this action: {}", call.argument);
+        match self.run(input).await {
+            Ok(outcome) => outcome.render(self.category.as_str()),
+            Err(NoAction::Declined(reason)) => format!("I cannot perform this action: {}", reason),
+            Err(NoAction::DecisionFailed) => {
+                "Error: Failed to process action request (LLM Decision Failed).".to_string()
             }
-
-            // 2. Execute the tool
-            if let Some(tool) = self.tools.get(&call.tool_name) {
-                match tool.execute(&call.argument).await {
-                    Ok(output) => {
-                        format!(
-                            "✅ Action executed successfully.\n\n**Tool Output:**\n```\n{}\n```",
-                            output
-                        )
-                    }
-                    Err(e) => format!("❌ Tool execution failed: {}", e),
-                }
-            } else {
-                format!(
-                    "Error: Tool '{}' selected by AI is not found in registry.",
-                    call.tool_name
-                )
-            }
-        } else {
-            "Error: Failed to process action request (LLM Decision Failed).".to_string()
         }
     }
 
     fn category(&self) -> AgentCategory {
         self.category.clone()
     }
+
+    fn clearance(&self) -> Classification {
+        self.clearance
+    }
+
+    fn uses_rag(&self) -> bool {
+        self.rag
+    }
 }
 
 #[cfg(test)]
@@ -1,23 +1,85 @@
 use crate::agents::Agent;
+use crate::agents::react::{DEFAULT_MAX_STEPS, NoAction, ReactLoop, ReactOutcome};
+use crate::errors::SenseiError;
 use crate::llm::Llm;
 use crate::mcp_client::McpClient;
+use crate::tools::{Tool, ToolRegistry};
 use async_trait::async_trait;
 use sensei_common::AgentCategory;
-use serde::Deserialize;
-use serde_json::Value;
-use std::sync::Arc;
+use serde_json::{Value, json};
+use std::sync::{Arc, RwLock, Weak};
+use tokio::sync::broadcast::error::RecvError;
 
-pub struct McpAgent {
+/// Sent by servers whose tool list changed; the agent then lists the tools again.
+const TOOLS_CHANGED: &str = "notifications/tools/list_changed";
+
+/// A tool exposed by a remote MCP server.
+pub struct McpTool {
     client: Arc<McpClient>,
-    llm: Arc<dyn Llm>,
-    server_name: String,
-    tools: Vec<Value>, // Cache of available tools descriptions
+    name: String,
+    description: String,
+    input_schema: Value,
+}
+
+impl McpTool {
+    /// Build from one entry of a `tools/list` result.
+    pub fn from_description(client: Arc<McpClient>, description: &Value) -> Option<Self> {
+        let name = description.get("name")?.as_str()?.to_string();
+        let input_schema = description
+            .get("inputSchema")
+            .or_else(|| description.get("input_schema"))
+            .cloned()
+            .unwrap_or_else(|| json!({ "type": "object", "properties": {} }));
+
+        Some(Self {
+            client,
+            name,
+            description: description
+                .get("description")
+                .and_then(|d| d.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            input_schema,
+        })
+    }
 }
 
-#[derive(Deserialize)]
-struct McpToolCall {
-    tool_name: String,
-    arguments: Value,
+#[async_trait]
+impl Tool for McpTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn input_schema(&self) -> Value {
+        self.input_schema.clone()
+    }
+
+    async fn execute(&self, args: &str) -> Result<String, SenseiError> {
+        let arguments = serde_json::from_str(args).unwrap_or_else(|_| json!({}));
+        self.call(&arguments).await
+    }
+
+    async fn call(&self, arguments: &Value) -> Result<String, SenseiError> {
+        self.client
+            .call_tool(&self.name, arguments.clone())
+            .await
+            .map_err(|e| SenseiError::Tool(e.to_string()))
+    }
+}
+
+// Swapped as a whole when the server's tool list changes
+type SharedTools = Arc<RwLock<Arc<ToolRegistry>>>;
+
+pub struct McpAgent {
+    llm: Arc<dyn Llm>,
+    client: Arc<McpClient>,
+    server_name: String,
+    tools: SharedTools,
+    max_steps: usize,
 }
 
 impl McpAgent {
@@ -28,7 +90,7 @@ impl McpAgent {
     ) -> anyhow::Result<Self> {
         // Auto-discovery of tools
         client.initialize().await?;
-        let tools = client.list_tools().await?;
+        let tools = discover_tools(&client).await?;
 
         println!(
             "🔌 Connected to MCP Server '{}'. Discovered {} tools.",
@@ -36,71 +98,103 @@ impl McpAgent {
             tools.len()
         );
 
+        let tools: SharedTools = Arc::new(RwLock::new(Arc::new(tools)));
+        watch_tools(&client, Arc::downgrade(&tools), server_name);
         Ok(Self {
-            client,
             llm,
+            client,
             server_name: server_name.to_string(),
             tools,
+            max_steps: DEFAULT_MAX_STEPS,
         })
     }
 
-    async fn decide_tool(&self, query: &str) -> Option<McpToolCall> {
-        // Construct a prompt that describes available MCP tools
-        let tools_desc = serde_json::to_string_pretty(&self.tools).unwrap_or_default();
-
-        let prompt = format!(
-            r#" 
-            You are an autonomous Agent controlling an MCP Server named '{}'.
-            
-            Available Tools (JSON Schema):
-            {} 
-            
-            Task: Analyze the user request and decide which tool to execute.
-            User Request: "{}" 
-            
-            Rules:
-            - If the request matches a tool, output strictly JSON: {{ "tool_name": "name", "arguments": {{ ... }} }}
-            - If NO tool matches, output strictly JSON: {{ "tool_name": "none", "arguments": {{}} }} 
-            
-            Output strictly JSON.
-            "#,
-            self.server_name, tools_desc, query
-        );
+    /// Maximum number of tool calls per request.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
 
-        let response = self.llm.generate(&prompt).await.ok()?;
+    /// The server's tools as of the last `tools/list`.
+    pub fn tools(&self) -> Arc<ToolRegistry> {
+        self.tools.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
 
-        let start = response.find('{').unwrap_or(0);
-        let end = response.rfind('}').map(|i| i + 1).unwrap_or(response.len());
-        let json_str = &response[start..end];
+    pub fn client(&self) -> &Arc<McpClient> {
+        &self.client
+    }
 
-        serde_json::from_str::<McpToolCall>(json_str).ok()
+    /// Run the ReAct loop and return the answer with its step trace.
+    pub async fn run(&self, input: &str) -> Result<ReactOutcome, NoAction> {
+        let role = format!(
+            "You are an autonomous Agent controlling an MCP Server named '{}'.",
+            self.server_name
+        );
+        let tools = self.tools();
+        ReactLoop::new(self.llm.as_ref(), &tools, &role)
+            .max_steps(self.max_steps)
+            .run(input)
+            .await
     }
 }
 
+async fn discover_tools(client: &Arc<McpClient>) -> anyhow::Result<ToolRegistry> {
+    let mut tools = ToolRegistry::new();
+    for description in &client.list_tools().await? {
+        if let Some(tool) = McpTool::from_description(client.clone(), description) {
+            tools.register(Arc::new(tool));
+        }
+    }
+    Ok(tools)
+}
+
+/// List the tools again whenever the server says they changed, until the agent is dropped.
+fn watch_tools(client: &Arc<McpClient>, tools: Weak<RwLock<Arc<ToolRegistry>>>, server_name: &str) {
+    let mut notifications = client.subscribe();
+    let client = Arc::downgrade(client);
+    let server_name = server_name.to_string();
+    tokio::spawn(async move {
+        loop {
+            match notifications.recv().await {
+                Ok(notification) if notification.method == TOOLS_CHANGED => {}
+                // Missed notifications may have included a change.
+                Err(RecvError::Lagged(_)) => {}
+                Ok(_) => continue,
+                Err(RecvError::Closed) => break,
+            }
+            let (Some(client), Some(tools)) = (client.upgrade(), tools.upgrade()) else {
+                break;
+            };
+            match discover_tools(&client).await {
+                Ok(registry) => {
+                    println!(
+                        "🔌 MCP Server '{}' changed its tools: {} available.",
+                        server_name,
+                        registry.len()
+                    );
+                    *tools.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(registry);
+                }
+                Err(e) => eprintln!(
+                    "MCP Server '{}' changed its tools but listing them failed: {}",
+                    server_name, e
+                ),
+            }
+        }
+    });
+}
+
 #[async_trait]
 impl Agent for McpAgent {
     async fn process(&self, input: &str) -> String {
-        // 1. LLM decides which tool to call
-        if let Some(call) = self.decide_tool(input).await {
-            if call.tool_name == "none" {
-                return format!(
-                    "I cannot process this request with the available tools on {}.",
-                    self.server_name
-                );
-            }
-
-            // 2. Call MCP Tool
-            match self.client.call_tool(&call.tool_name, call.arguments).await {
-                Ok(output) => {
-                    format!(
-                        "✅ Tool '{}' on {} executed successfully:\n\n{}",
-                        call.tool_name, self.server_name, output
-                    )
-                }
-                Err(e) => format!("❌ MCP Tool execution failed: {}", e),
+        match self.run(input).await {
+            Ok(outcome) => outcome.render(&self.server_name),
+            Err(NoAction::Declined(_)) => format!(
+                "I cannot process this request with the available tools on {}.",
+                self.server_name
+            ),
+            Err(NoAction::DecisionFailed) => {
+                "Error: Failed to decide on MCP tool execution.".to_string()
             }
-        } else {
-            "Error: Failed to decide on MCP tool execution.".to_string()
         }
     }
 
@@ -1,35 +1,180 @@
 pub mod action;
 pub mod mcp_agent;
+pub mod react;
 pub mod router;
 pub mod specialists;
+pub mod swarm;
 
+use crate::context::{ContextWindow, History};
+use crate::llm::ChatTurn;
 use async_recursion::async_recursion;
 use async_trait::async_trait;
 use regex::Regex;
-use sensei_common::AgentCategory;
+use sensei_common::{AgentCategory, AskStreamEvent, Classification};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::OnceLock;
 use tokio::sync::RwLock;
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+/// Channel used to push streaming events (tokens, delegations) to the caller.
+pub type EventSender = UnboundedSender<AskStreamEvent>;
 
 #[async_trait]
 pub trait Agent: Send + Sync {
     /// Process a user query and return a response.
     async fn process(&self, input: &str) -> String;
 
+    /// Process a user query with the prior turns of the conversation.
+    /// Agents that are not conversational ignore the history.
+    async fn process_with_history(&self, input: &str, _history: &[ChatTurn]) -> String {
+        self.process(input).await
+    }
+
+    /// Process a user query while streaming token deltas into `events`.
+    /// Returns the assembled response. Agents without native streaming emit it as one token.
+    async fn process_stream(
+        &self,
+        input: &str,
+        history: &[ChatTurn],
+        events: &EventSender,
+    ) -> String {
+        let response = self.process_with_history(input, history).await;
+        let _ = events.send(AskStreamEvent::Token {
+            content: response.clone(),
+        });
+        response
+    }
+
     /// Return the category/role of this agent.
     fn category(&self) -> AgentCategory;
+
+    /// Highest classification of knowledge this agent may receive ("no read up").
+    fn clearance(&self) -> Classification {
+        Classification::Unclassified
+    }
+
+    /// Whether queries for this agent are enriched with knowledge base context.
+    fn uses_rag(&self) -> bool {
+        true
+    }
+}
+
+/// Opens a line that hands the task over to another agent.
+const DELEGATION_MARKER: &str = "[DELEGATE:";
+
+fn delegation_regex() -> &'static Regex {
+    // Optimized Protocol v2: [DELEGATE: CATEGORY] Payload
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?m)^\[DELEGATE:\s*(\w+)\]\s*(?s)(.*)$").unwrap())
+}
+
+/// Whether a line starting with `text` delegates; `None` while it is too short to tell.
+fn delegates(line: &str) -> Option<bool> {
+    if line.len() >= DELEGATION_MARKER.len() {
+        Some(line.starts_with(DELEGATION_MARKER))
+    } else if DELEGATION_MARKER.starts_with(line) {
+        None
+    } else {
+        Some(false)
+    }
+}
+
+/// Forward the events of one turn. Each line is held back until it is known not to
+/// open a delegation; from a delegating line on, tokens are dropped: they are not the answer.
+/// Returns the text forwarded.
+async fn relay_turn(
+    mut turn: mpsc::UnboundedReceiver<AskStreamEvent>,
+    events: &EventSender,
+) -> String {
+    let mut forwarded = String::new();
+    // Start of the current line, while it could still be a delegation
+    let mut held = String::new();
+    let mut line_decided = false;
+    let mut delegating = false;
+    while let Some(event) = turn.recv().await {
+        let AskStreamEvent::Token { content } = event else {
+            let _ = events.send(event);
+            continue;
+        };
+        let mut out = String::new();
+        for c in content.chars() {
+            if delegating {
+                break;
+            }
+            if line_decided {
+                out.push(c);
+                line_decided = c != '\n';
+                continue;
+            }
+            held.push(c);
+            if c == '\n' {
+                out.push_str(&std::mem::take(&mut held));
+                continue;
+            }
+            match delegates(&held) {
+                Some(false) => {
+                    out.push_str(&std::mem::take(&mut held));
+                    line_decided = true;
+                }
+                Some(true) => delegating = true,
+                None => {}
+            }
+        }
+        if !out.is_empty() {
+            forwarded.push_str(&out);
+            let _ = events.send(AskStreamEvent::Token { content: out });
+        }
+    }
+    // A last line shorter than the marker
+    if !delegating && !held.is_empty() {
+        forwarded.push_str(&held);
+        let _ = events.send(AskStreamEvent::Token { content: held });
+    }
+    forwarded
 }
 
 pub struct Orchestrator {
     // RwLock allows concurrent reads and exclusive writes for Hot Reloading
     agents: RwLock<HashMap<AgentCategory, Arc<Box<dyn Agent>>>>,
+    context: Option<ContextWindow>,
 }
 
 impl Orchestrator {
     pub fn new() -> Self {
         Self {
             agents: RwLock::new(HashMap::new()),
+            context: None,
+        }
+    }
+
+    /// Replay session history to agents through the given window.
+    pub fn with_context(mut self, context: ContextWindow) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Load the conversation history of a session the agent serving `category` is cleared for
+    /// (empty without a context window).
+    pub async fn load_history(&self, session_id: &str, category: &AgentCategory) -> History {
+        let Some(ref context) = self.context else {
+            return History::default();
+        };
+
+        let clearance = self.clearance(category).await;
+        match context.load(session_id, clearance).await {
+            Ok(history) => history,
+            Err(e) => {
+                eprintln!("Failed to load history for session {}: {}", session_id, e);
+                History::default()
+            }
+        }
+    }
+
+    /// Drop what the context window caches about a deleted session.
+    pub async fn forget_session(&self, session_id: &str) {
+        if let Some(ref context) = self.context {
+            context.forget(session_id).await;
         }
     }
 
@@ -39,58 +184,159 @@ impl Orchestrator {
         self.agents.write().await.insert(cat, Arc::new(agent));
     }
 
+    /// Categories with a registered agent, sorted.
+    pub async fn categories(&self) -> Vec<AgentCategory> {
+        let mut categories: Vec<_> = self.agents.read().await.keys().cloned().collect();
+        categories.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        categories
+    }
+
+    /// Clearance of the agent serving `category` (unclassified when none is registered).
+    pub async fn clearance(&self, category: &AgentCategory) -> Classification {
+        self.agents
+            .read()
+            .await
+            .get(category)
+            .map(|agent| agent.clearance())
+            .unwrap_or_default()
+    }
+
+    /// Whether the agent serving `category` takes knowledge base context (true when none is registered).
+    pub async fn uses_rag(&self, category: &AgentCategory) -> bool {
+        self.agents
+            .read()
+            .await
+            .get(category)
+            .is_none_or(|agent| agent.uses_rag())
+    }
+
+    /// Unregister `removed` and register `agents` under one write lock, so a request
+    /// sees either the previous swarm or the new one.
+    pub async fn replace(&self, removed: &[AgentCategory], agents: Vec<Box<dyn Agent>>) {
+        let mut map = self.agents.write().await;
+        for category in removed {
+            map.remove(category);
+        }
+        for agent in agents {
+            map.insert(agent.category(), Arc::new(agent));
+        }
+    }
+
     pub async fn unregister(&self, category: &AgentCategory) {
         println!("DEBUG: Unregistering agent for category {:?}", category);
         self.agents.write().await.remove(category);
     }
 
     pub async fn dispatch(&self, category: AgentCategory, input: &str) -> String {
-        self.dispatch_loop(category, input, 3).await
+        self.dispatch_with_history(category, input, &[]).await
+    }
+
+    /// Dispatch a query within a conversation.
+    /// Only the entry agent sees the history; delegates receive the bare payload.
+    pub async fn dispatch_with_history(
+        &self,
+        category: AgentCategory,
+        input: &str,
+        history: &[ChatTurn],
+    ) -> String {
+        self.dispatch_loop(category, input, history, None, 3).await
+    }
+
+    /// Same as `dispatch_with_history`, but streams token deltas and delegation hops into `events`.
+    /// Delegation lines and what follows them are not streamed, so the tokens add up to the
+    /// returned string; text an agent wrote before delegating starts the answer.
+    pub async fn dispatch_stream(
+        &self,
+        category: AgentCategory,
+        input: &str,
+        history: &[ChatTurn],
+        events: &EventSender,
+    ) -> String {
+        self.dispatch_stream_loop(category, input, history, events, None, 3)
+            .await
+    }
+
+    async fn resolve(&self, category: &AgentCategory) -> Result<Arc<Box<dyn Agent>>, String> {
+        // Acquire read lock for agent lookup
+        let map = self.agents.read().await;
+        if let Some(agent) = map.get(category) {
+            Ok(agent.clone())
+        } else if let Some(casual) = map.get(&AgentCategory::new("casual")) {
+            // Default fallback category is "casual"
+            Ok(casual.clone())
+        } else {
+            Err(format!(
+                "No agent found for category {:?} and 'casual' fallback missing",
+                category
+            ))
+        }
     }
 
+    /// Refuse to hand a payload to `target` when it is not cleared for `ceiling`.
+    ///
+    /// The entry agent receives knowledge and history up to its clearance and may pass any
+    /// of it on, so every agent of the chain must be cleared at least as high ("no write down").
+    async fn check_hand_off(
+        &self,
+        target: &AgentCategory,
+        ceiling: Classification,
+    ) -> Result<(), String> {
+        match self.resolve(target).await {
+            Ok(agent) if !agent.clearance().dominates(ceiling) => {
+                println!(
+                    "🔒 Refused delegation to {:?}: cleared for {}, context up to {}",
+                    target,
+                    agent.clearance(),
+                    ceiling
+                );
+                Err(format!(
+                    "Error: Delegation to '{}' refused: it is cleared for {} but this request may carry {} context.",
+                    target.as_str(),
+                    agent.clearance(),
+                    ceiling
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// `ceiling` is the clearance of the entry agent, `None` until it is resolved.
     #[async_recursion]
-    async fn dispatch_loop(&self, category: AgentCategory, input: &str, depth: u8) -> String {
+    async fn dispatch_loop(
+        &self,
+        category: AgentCategory,
+        input: &str,
+        history: &[ChatTurn],
+        ceiling: Option<Classification>,
+        depth: u8,
+    ) -> String {
         // println!("DEBUG: Dispatching to {:?} (depth {})", category, depth);
         if depth == 0 {
             return "Error: Agent recursion limit reached (A2A loop detected).".to_string();
         }
 
-        // Acquire read lock for agent lookup
-        let agent = {
-            let map = self.agents.read().await;
-            if let Some(agent) = map.get(&category) {
-                // println!("DEBUG: Found agent for {:?}", category);
-                agent.clone()
-            } else {
-                // println!("DEBUG: Agent not found for {:?}. Fallback to Casual.", category);
-                // Default fallback category is "casual"
-                if let Some(casual) = map.get(&AgentCategory::new("casual")) {
-                    casual.clone()
-                } else {
-                    return format!(
-                        "No agent found for category {:?} and 'casual' fallback missing",
-                        category
-                    );
-                }
-            }
-        }; // Lock released here
-
-        let response = agent.process(input).await;
+        let agent = match self.resolve(&category).await {
+            Ok(agent) => agent,
+            Err(msg) => return msg,
+        };
+        let ceiling = ceiling.unwrap_or_else(|| agent.clearance());
 
-        // Optimized Protocol v2: [DELEGATE: CATEGORY] Payload
-        static RE: OnceLock<Regex> = OnceLock::new();
-        let re = RE.get_or_init(|| Regex::new(r"(?m)^\[DELEGATE:\s*(\w+)\]\s*(?s)(.*)$").unwrap());
+        let response = agent.process_with_history(input, history).await;
 
-        if let Some(caps) = re.captures(&response) {
+        if let Some(caps) = delegation_regex().captures(&response) {
             let target_cat_str = caps.get(1).map_or("", |m| m.as_str());
             let target_query = caps.get(2).map_or("", |m| m.as_str()).trim();
 
             // Dynamic Routing: Create category directly from string
             let target_cat = AgentCategory::new(target_cat_str);
 
-            let observation = self
-                .dispatch_loop(target_cat, target_query, depth - 1)
-                .await;
+            let observation = match self.check_hand_off(&target_cat, ceiling).await {
+                Ok(()) => {
+                    self.dispatch_loop(target_cat, target_query, &[], Some(ceiling), depth - 1)
+                        .await
+                }
+                Err(refusal) => refusal,
+            };
 
             let new_input = format!(
                 "{}
@@ -98,7 +344,82 @@ impl Orchestrator {
 [OBSERVATION from {}]\n{}",
                 input, target_cat_str, observation
             );
-            return self.dispatch_loop(category, &new_input, depth - 1).await;
+            return self
+                .dispatch_loop(category, &new_input, history, Some(ceiling), depth - 1)
+                .await;
+        }
+
+        response
+    }
+
+    #[async_recursion]
+    async fn dispatch_stream_loop(
+        &self,
+        category: AgentCategory,
+        input: &str,
+        history: &[ChatTurn],
+        events: &EventSender,
+        ceiling: Option<Classification>,
+        depth: u8,
+    ) -> String {
+        // Errors are the answer too: they are streamed like one.
+        let fail = |message: String| {
+            let _ = events.send(AskStreamEvent::Token {
+                content: message.clone(),
+            });
+            message
+        };
+        if depth == 0 {
+            return fail("Error: Agent recursion limit reached (A2A loop detected).".to_string());
+        }
+
+        let agent = match self.resolve(&category).await {
+            Ok(agent) => agent,
+            Err(msg) => return fail(msg),
+        };
+        let ceiling = ceiling.unwrap_or_else(|| agent.clearance());
+
+        let (turn_tx, turn_rx) = mpsc::unbounded_channel();
+        let process = async move {
+            // Dropping the sender ends the relay once the turn is complete.
+            agent.process_stream(input, history, &turn_tx).await
+        };
+        let (response, forwarded) = tokio::join!(process, relay_turn(turn_rx, events));
+
+        if let Some(caps) = delegation_regex().captures(&response) {
+            let target_cat_str = caps.get(1).map_or("", |m| m.as_str());
+            let target_query = caps.get(2).map_or("", |m| m.as_str()).trim();
+            let target_cat = AgentCategory::new(target_cat_str);
+
+            // The delegate works silently: only the delegating agent's final answer is streamed.
+            let observation = match self.check_hand_off(&target_cat, ceiling).await {
+                Ok(()) => {
+                    let _ = events.send(AskStreamEvent::Delegation {
+                        from: category.clone(),
+                        to: target_cat.clone(),
+                    });
+                    self.dispatch_loop(target_cat, target_query, &[], Some(ceiling), depth - 1)
+                        .await
+                }
+                Err(refusal) => refusal,
+            };
+
+            let new_input = format!(
+                "{}\n\n[OBSERVATION from {}]\n{}",
+                input, target_cat_str, observation
+            );
+            // Lines streamed before the delegation are already out: they stay part of the answer.
+            let answer = self
+                .dispatch_stream_loop(
+                    category,
+                    &new_input,
+                    history,
+                    events,
+                    Some(ceiling),
+                    depth - 1,
+                )
+                .await;
+            return forwarded + &answer;
         }
 
         response
@@ -0,0 +1,222 @@
+use crate::llm::Llm;
+use crate::tools::{ToolRegistry, select_tool};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Default number of tool calls an agent may make for one request.
+pub const DEFAULT_MAX_STEPS: usize = 5;
+
+/// Observations are clipped to keep the planning and synthesis prompts small.
+const MAX_OBSERVATION_CHARS: usize = 2000;
+
+/// One act → observe iteration.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReactStep {
+    pub tool_name: String,
+    pub arguments: Value,
+    pub observation: String,
+    pub success: bool,
+}
+
+/// Final answer of a ReAct run with the steps that led to it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReactOutcome {
+    pub answer: String,
+    pub steps: Vec<ReactStep>,
+}
+
+impl ReactOutcome {
+    /// The answer followed by the step trace, `source` naming where tools ran.
+    pub fn render(&self, source: &str) -> String {
+        let mut out = format!("{}\n\n---\n**Steps:**\n", self.answer.trim_end());
+        for (i, step) in self.steps.iter().enumerate() {
+            let status = if step.success {
+                format!(
+                    "✅ Tool '{}' on {} executed successfully",
+                    step.tool_name, source
+                )
+            } else {
+                format!("❌ Tool '{}' on {} failed", step.tool_name, source)
+            };
+            out.push_str(&format!(
+                "{}. {} with `{}`\n```\n{}\n```\n",
+                i + 1,
+                status,
+                step.arguments,
+                clip(&step.observation)
+            ));
+        }
+        out
+    }
+}
+
+/// Why a ReAct run ended without running any tool.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NoAction {
+    /// The LLM found no tool matching the request.
+    Declined(String),
+    /// The LLM answer could not be turned into a tool choice.
+    DecisionFailed,
+}
+
+/// Iterative plan → act → observe loop over the tools of a registry,
+/// closed by an LLM synthesis of all observations.
+pub struct ReactLoop<'a> {
+    llm: &'a dyn Llm,
+    tools: &'a ToolRegistry,
+    role: &'a str,
+    max_steps: usize,
+}
+
+impl<'a> ReactLoop<'a> {
+    pub fn new(llm: &'a dyn Llm, tools: &'a ToolRegistry, role: &'a str) -> Self {
+        Self {
+            llm,
+            tools,
+            role,
+            max_steps: DEFAULT_MAX_STEPS,
+        }
+    }
+
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+
+    pub async fn run(&self, request: &str) -> Result<ReactOutcome, NoAction> {
+        let mut steps: Vec<ReactStep> = Vec::new();
+
+        while steps.len() < self.max_steps {
+            // 1. Plan: pick the next tool given what was observed so far
+            let Some(call) = select_tool(
+                self.llm,
+                self.tools,
+                self.role,
+                &self.planning_request(request, &steps),
+            )
+            .await
+            else {
+                if steps.is_empty() {
+                    return Err(NoAction::DecisionFailed);
+                }
+                break;
+            };
+
+            if call.is_none() {
+                if steps.is_empty() {
+                    return Err(NoAction::Declined(call.reason()));
+                }
+                break;
+            }
+
+            // Repeating an identical call would not teach us anything new.
+            if steps
+                .iter()
+                .any(|s| s.tool_name == call.tool_name && s.arguments == call.arguments)
+            {
+                break;
+            }
+
+            // 2. Act & observe
+            let (observation, success) = match self.tools.get(&call.tool_name) {
+                Some(tool) => match tool.call(&call.arguments).await {
+                    Ok(output) => (output, true),
+                    Err(e) => (format!("Tool execution failed: {}", e), false),
+                },
+                None => (
+                    format!(
+                        "Error: Tool '{}' selected by AI is not found in registry.",
+                        call.tool_name
+                    ),
+                    false,
+                ),
+            };
+            println!(
+                "🔁 ReAct step {}: '{}' ({})",
+                steps.len() + 1,
+                call.tool_name,
+                if success { "ok" } else { "failed" }
+            );
+
+            steps.push(ReactStep {
+                tool_name: call.tool_name,
+                arguments: call.arguments,
+                observation,
+                success,
+            });
+        }
+
+        // 3. Synthesize an interpreted answer over all observations
+        let answer = self.synthesize(request, &steps).await;
+        Ok(ReactOutcome { answer, steps })
+    }
+
+    fn planning_request(&self, request: &str, steps: &[ReactStep]) -> String {
+        if steps.is_empty() {
+            return request.to_string();
+        }
+
+        format!(
+            "{}\n\nSteps already taken (do not repeat them):\n{}\n\n\
+             Pick the next tool only if more information is needed. \
+             If the observations already answer the request, choose no tool (\"none\").",
+            request,
+            transcript(steps)
+        )
+    }
+
+    async fn synthesize(&self, request: &str, steps: &[ReactStep]) -> String {
+        let prompt = format!(
+            r#"
+            {}
+
+            User Request: "{}"
+
+            You ran the following tools:
+            {}
+
+            Write the final answer for the user. Interpret the observations (what they show,
+            what it means, sensible next steps) instead of repeating raw output.
+            Only state what the observations support.
+            "#,
+            self.role,
+            request,
+            transcript(steps)
+        );
+
+        match self.llm.generate(&prompt).await {
+            Ok(answer) if !answer.trim().is_empty() => answer,
+            Ok(_) | Err(_) => {
+                // Without a synthesis, the last observation is the best answer we have.
+                let last = steps.last().map(|s| s.observation.as_str()).unwrap_or("");
+                format!("**Tool Output:**\n```\n{}\n```", last)
+            }
+        }
+    }
+}
+
+fn transcript(steps: &[ReactStep]) -> String {
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            format!(
+                "Step {}: {} {}\nObservation:\n{}",
+                i + 1,
+                s.tool_name,
+                s.arguments,
+                clip(&s.observation)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn clip(text: &str) -> String {
+    if text.chars().count() <= MAX_OBSERVATION_CHARS {
+        return text.trim_end().to_string();
+    }
+    let mut clipped: String = text.chars().take(MAX_OBSERVATION_CHARS).collect();
+    clipped.push_str("\n...[truncated]...");
+    clipped
+}
@@ -2,7 +2,7 @@ use crate::llm::Llm;
 use crate::memory::MemoryStore;
 use sensei_common::AgentCategory;
 use serde::Deserialize;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 #[derive(Debug)]
 pub struct RoutingDecision {
@@ -19,7 +19,8 @@ struct RouterResponse {
 pub struct RouterAgent {
     llm: Arc<dyn Llm>,
     memory: Option<MemoryStore>, // Option to allow testing/running without DB
-    system_prompt: String,
+    // Swapped when the configuration is reloaded
+    system_prompt: RwLock<String>,
 }
 
 impl RouterAgent {
@@ -27,10 +28,24 @@ impl RouterAgent {
         Self {
             llm,
             memory,
-            system_prompt: system_prompt.to_string(),
+            system_prompt: RwLock::new(system_prompt.to_string()),
         }
     }
 
+    pub fn system_prompt(&self) -> String {
+        self.system_prompt
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn set_system_prompt(&self, system_prompt: &str) {
+        *self
+            .system_prompt
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = system_prompt.to_string();
+    }
+
     fn check_fast_path(&self, input: &str) -> Option<RoutingDecision> {
         let input_lower = input.to_lowercase();
 
@@ -115,7 +130,7 @@ impl RouterAgent {
     }
 
     async fn classify_with_llm(&self, input: &str, embedding: Option<Vec<f32>>) -> RoutingDecision {
-        let prompt = format!("{}\n\nQuery: \"{}\"", self.system_prompt, input);
+        let prompt = format!("{}\n\nQuery: \"{}\"", self.system_prompt(), input);
 
         match self.llm.generate(&prompt).await {
             Ok(json_str) => {
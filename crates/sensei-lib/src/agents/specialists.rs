@@ -1,7 +1,8 @@
-use crate::agents::Agent;
-use crate::llm::Llm;
+use crate::agents::{Agent, EventSender};
+use crate::llm::{ChatTurn, Llm, flatten_turns};
 use async_trait::async_trait;
-use sensei_common::AgentCategory;
+use futures::StreamExt;
+use sensei_common::{AgentCategory, AskStreamEvent, Classification};
 use std::sync::Arc;
 
 pub struct SpecializedAgent {
@@ -9,6 +10,8 @@ pub struct SpecializedAgent {
     category: AgentCategory,
     system_prompt: String,
     master_prompt: Option<String>,
+    clearance: Classification,
+    rag: bool,
 }
 
 impl SpecializedAgent {
@@ -23,41 +26,123 @@ impl SpecializedAgent {
             category,
             system_prompt: system_prompt.to_string(),
             master_prompt,
+            clearance: Classification::Unclassified,
+            rag: true,
         }
     }
-}
 
-#[async_trait]
-impl Agent for SpecializedAgent {
-    async fn process(&self, input: &str) -> String {
+    pub fn with_clearance(mut self, clearance: Classification) -> Self {
+        self.clearance = clearance;
+        self
+    }
+
+    pub fn with_rag(mut self, rag: bool) -> Self {
+        self.rag = rag;
+        self
+    }
+
+    /// Build the conversation sent to the LLM, returning whether raw (unfiltered) generation applies.
+    fn build_turns(&self, input: &str, history: &[ChatTurn]) -> (Vec<ChatTurn>, bool) {
         let is_raw_mode = input.contains("--raw");
 
         // Select prompt based on mode
-        let sys_prompt = if is_raw_mode && self.master_prompt.is_some() {
-            self.master_prompt.as_ref().unwrap()
-        } else {
-            &self.system_prompt
+        let sys_prompt = match &self.master_prompt {
+            Some(master) if is_raw_mode => master,
+            _ => &self.system_prompt,
         };
 
-        let full_prompt = format!("{}\n\nUser Query: {}", sys_prompt, input);
+        let mut turns = Vec::with_capacity(history.len() + 2);
+        turns.push(ChatTurn::system(sys_prompt.as_str()));
+        turns.extend_from_slice(history);
+        turns.push(ChatTurn::user(input));
 
-        // Use raw generation (bypass filters) if --raw is requested AND allowed for this agent (Red)
-        let result = if is_raw_mode && self.category == AgentCategory::new("red") {
-            self.llm.generate_raw(&full_prompt).await
+        // Use raw generation (bypass filters) if --raw is requested AND this agent has a master prompt
+        let use_raw = is_raw_mode && self.master_prompt.is_some();
+        (turns, use_raw)
+    }
+
+    fn error_message(&self, e: impl std::fmt::Display) -> String {
+        eprintln!("Agent {:?} Error: {}", self.category, e);
+        "I encountered an error processing your request.".to_string()
+    }
+}
+
+#[async_trait]
+impl Agent for SpecializedAgent {
+    async fn process(&self, input: &str) -> String {
+        self.process_with_history(input, &[]).await
+    }
+
+    async fn process_with_history(&self, input: &str, history: &[ChatTurn]) -> String {
+        let (turns, use_raw) = self.build_turns(input, history);
+
+        let result = if use_raw {
+            self.llm.generate_raw(&flatten_turns(&turns)).await
         } else {
-            self.llm.generate(&full_prompt).await
+            self.llm.chat(&turns).await
         };
 
         match result {
             Ok(response) => response,
-            Err(e) => {
-                eprintln!("Agent {:?} Error: {}", self.category, e);
-                "I encountered an error processing your request.".to_string()
+            Err(e) => self.error_message(e),
+        }
+    }
+
+    async fn process_stream(
+        &self,
+        input: &str,
+        history: &[ChatTurn],
+        events: &EventSender,
+    ) -> String {
+        let (turns, use_raw) = self.build_turns(input, history);
+
+        // Raw generation goes through a dedicated endpoint without streaming support.
+        if use_raw {
+            let response = match self.llm.generate_raw(&flatten_turns(&turns)).await {
+                Ok(response) => response,
+                Err(e) => self.error_message(e),
+            };
+            let _ = events.send(AskStreamEvent::Token {
+                content: response.clone(),
+            });
+            return response;
+        }
+
+        let mut stream = match self.llm.chat_stream(&turns).await {
+            Ok(stream) => stream,
+            Err(e) => return self.error_message(e),
+        };
+
+        let mut response = String::new();
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(delta) => {
+                    response.push_str(&delta);
+                    let _ = events.send(AskStreamEvent::Token { content: delta });
+                }
+                Err(e) => {
+                    // Keep what was already streamed, the client has seen it anyway.
+                    let message = self.error_message(e);
+                    if response.is_empty() {
+                        return message;
+                    }
+                    break;
+                }
             }
         }
+
+        response
     }
 
     fn category(&self) -> AgentCategory {
         self.category.clone()
     }
+
+    fn clearance(&self) -> Classification {
+        self.clearance
+    }
+
+    fn uses_rag(&self) -> bool {
+        self.rag
+    }
 }
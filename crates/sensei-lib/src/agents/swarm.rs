@@ -0,0 +1,56 @@
+use crate::agents::Agent;
+use crate::agents::action::ToolExecutorAgent;
+use crate::agents::specialists::SpecializedAgent;
+use crate::config::AgentDefinition;
+use crate::errors::SenseiError;
+use crate::llm::Llm;
+use crate::tools::ToolRegistry;
+use std::sync::Arc;
+
+/// Build the agent declared by `definition` on `llm` (the client for its tier or model).
+/// Agents with tools get them from `tools` and run at most `max_steps` tool calls per request.
+pub fn build_agent(
+    definition: &AgentDefinition,
+    llm: Arc<dyn Llm>,
+    tools: &ToolRegistry,
+    max_steps: usize,
+) -> Result<Box<dyn Agent>, SenseiError> {
+    if definition.tools.is_empty() {
+        return Ok(Box::new(
+            SpecializedAgent::new(
+                llm,
+                definition.category.clone(),
+                &definition.prompt,
+                definition.master_prompt.clone(),
+            )
+            .with_clearance(definition.clearance)
+            .with_rag(definition.rag),
+        ));
+    }
+
+    if let Some(unknown) = definition.tools.iter().find(|t| tools.get(t).is_none()) {
+        return Err(SenseiError::Config(format!(
+            "Agent '{}' uses unknown tool '{}' (available: {})",
+            definition.category.as_str(),
+            unknown,
+            tools.names().join(", ")
+        )));
+    }
+    let names: Vec<&str> = definition.tools.iter().map(String::as_str).collect();
+    Ok(Box::new(
+        ToolExecutorAgent::with_registry(llm, definition.category.clone(), tools.subset(&names))
+            .with_role(&definition.prompt)
+            .with_max_steps(max_steps)
+            .with_clearance(definition.clearance)
+            .with_rag(definition.rag),
+    ))
+}
+
+/// Category names for the router prompt, e.g. `ACTION, BLUE, CASUAL`.
+pub fn category_list(definitions: &[AgentDefinition]) -> String {
+    definitions
+        .iter()
+        .map(|d| d.category.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,149 @@
+use crate::errors::SenseiError;
+use chrono::NaiveDateTime;
+use sensei_common::Classification;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Prefix of every generated API key, so leaked keys are easy to recognize.
+pub const KEY_PREFIX: &str = "sensei_";
+
+/// What an API key is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Ask questions and browse sessions.
+    Ask,
+    /// Add and manage documents in the knowledge base.
+    Ingest,
+    /// Correct router decisions.
+    Feedback,
+    /// Everything, including key management.
+    Admin,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 4] = [Scope::Ask, Scope::Ingest, Scope::Feedback, Scope::Admin];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Ask => "ask",
+            Scope::Ingest => "ingest",
+            Scope::Feedback => "feedback",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = SenseiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Scope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s.trim().to_lowercase())
+            .ok_or_else(|| {
+                SenseiError::Config(format!(
+                    "Unknown scope '{}'. Expected one of: ask, ingest, feedback, admin",
+                    s
+                ))
+            })
+    }
+}
+
+/// Parse a comma separated scope list such as "ask,ingest".
+pub fn parse_scopes(list: &str) -> Result<Vec<Scope>, SenseiError> {
+    let mut scopes = Vec::new();
+    for scope in list.split(',').filter(|s| !s.trim().is_empty()) {
+        let scope = scope.parse()?;
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+    Ok(scopes)
+}
+
+pub fn format_scopes(scopes: &[Scope]) -> String {
+    scopes
+        .iter()
+        .map(Scope::as_str)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A stored API key. The key itself is never stored, only its hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    /// First characters of the key, to tell keys apart in listings.
+    pub prefix: String,
+    pub scopes: Vec<Scope>,
+    /// Highest classification of documents the key may read.
+    #[serde(default)]
+    pub clearance: Classification,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+impl ApiKey {
+    /// `admin` grants every scope.
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope) || self.scopes.contains(&Scope::Admin)
+    }
+
+    /// Clearance the key reads with: `admin` keys are cleared for everything.
+    pub fn effective_clearance(&self) -> Classification {
+        if self.scopes.contains(&Scope::Admin) {
+            Classification::TopSecret
+        } else {
+            self.clearance
+        }
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+}
+
+/// Identity of whoever sent a request, recorded on the messages it produces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caller(pub String);
+
+impl Caller {
+    pub fn api_key(key: &ApiKey) -> Self {
+        Self(format!("key:{}", key.name))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A fresh random API key (the plaintext handed to the user).
+pub fn generate_key() -> String {
+    format!(
+        "{}{}{}",
+        KEY_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Displayable start of a key, e.g. `sensei_1a2b3c4d`.
+pub fn key_prefix(key: &str) -> String {
+    key.chars().take(KEY_PREFIX.len() + 8).collect()
+}
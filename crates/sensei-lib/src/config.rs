@@ -1,19 +1,419 @@
 use crate::errors::SenseiError;
-use serde::Deserialize;
-use std::collections::HashMap;
+use sensei_common::{AgentCategory, Classification};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::Read;
 
 // --- Prompts Config (YAML) ---
 
-#[derive(Debug, Deserialize)]
+/// Entry holding the router prompt rather than an agent.
+pub const ROUTER_PROMPT: &str = "router";
+/// Entry holding the master prompt of the built-in red team agent.
+pub const MASTER_PROMPT: &str = "master";
+
+const DEFAULT_MASTER_PROMPT: &str = "SYSTEM: You are SENSEI.";
+const DEFAULT_TOOL_ROLE: &str = "You are an autonomous Action Agent.";
+/// Argument of agents that declare none.
+const DEFAULT_PROMPT_ARGUMENT: &str = "task";
+
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct PromptsConfig {
-    pub agents: HashMap<String, AgentConfig>,
+    pub agents: BTreeMap<String, AgentConfig>,
 }
 
-#[derive(Debug, Deserialize)]
+/// LLM tier of an agent: the fast model or the smart (reasoning) model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LlmTier {
+    #[default]
+    Fast,
+    Smart,
+}
+
+/// An argument of the MCP prompt an agent is published as.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptArgument {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+impl PromptArgument {
+    fn new(name: &str, description: &str, required: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            required,
+        }
+    }
+}
+
+/// An entry of `prompts.yaml`. Entries named after a built-in agent only override the fields they set.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct AgentConfig {
+    #[serde(default)]
+    pub prompt: String,
+    /// Category the router sends queries to (defaults to the entry name).
+    pub category: Option<String>,
+    pub tier: Option<LlmTier>,
+    /// Chat model, used instead of the tier's model.
+    pub model: Option<String>,
+    pub temperature: Option<f64>,
+    /// Tools the agent may run; an agent with tools answers with the ReAct loop.
+    pub tools: Option<Vec<String>>,
+    /// Enrich queries with knowledge base context (default: true).
+    pub rag: Option<bool>,
+    pub clearance: Option<Classification>,
+    /// Prompt used instead of `prompt` for `--raw` requests, which bypass the provider's safety filters.
+    pub master_prompt: Option<String>,
+    /// Summary of the agent for MCP clients.
+    pub description: Option<String>,
+    /// Arguments of the agent's MCP prompt (default: a required `task`).
+    pub arguments: Option<Vec<PromptArgument>>,
+    /// Request rendered from the arguments, e.g. `Assess {{target}}` (default: one line per argument).
+    pub template: Option<String>,
+}
+
+impl AgentConfig {
+    fn builtin(prompt: &str, tier: LlmTier, clearance: Classification) -> Self {
+        Self {
+            prompt: prompt.to_string(),
+            tier: Some(tier),
+            clearance: Some(clearance),
+            ..Self::default()
+        }
+    }
+
+    fn category(mut self, category: &str) -> Self {
+        self.category = Some(category.to_string());
+        self
+    }
+
+    fn tools(mut self, tools: &[&str]) -> Self {
+        self.tools = Some(tools.iter().map(|t| t.to_string()).collect());
+        self
+    }
+
+    fn describe(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Publish the agent with a required `target` and an optional `scope`.
+    fn targeted(mut self, target: &str, scope: &str, template: &str) -> Self {
+        self.arguments = Some(vec![
+            PromptArgument::new("target", target, true),
+            PromptArgument::new("scope", scope, false),
+        ]);
+        self.template = Some(template.to_string());
+        self
+    }
+
+    /// Apply the fields set in `other` on top of this entry.
+    fn merge(self, other: &AgentConfig) -> Self {
+        Self {
+            prompt: if other.prompt.is_empty() {
+                self.prompt
+            } else {
+                other.prompt.clone()
+            },
+            category: other.category.clone().or(self.category),
+            tier: other.tier.or(self.tier),
+            model: other.model.clone().or(self.model),
+            temperature: other.temperature.or(self.temperature),
+            tools: other.tools.clone().or(self.tools),
+            rag: other.rag.or(self.rag),
+            clearance: other.clearance.or(self.clearance),
+            master_prompt: other.master_prompt.clone().or(self.master_prompt),
+            description: other.description.clone().or(self.description),
+            arguments: other.arguments.clone().or(self.arguments),
+            template: other.template.clone().or(self.template),
+        }
+    }
+}
+
+/// An agent of the swarm with every default applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentDefinition {
+    pub category: AgentCategory,
     pub prompt: String,
+    pub tier: LlmTier,
+    pub model: Option<String>,
+    pub temperature: Option<f64>,
+    pub tools: Vec<String>,
+    pub rag: bool,
+    pub clearance: Classification,
+    pub master_prompt: Option<String>,
+    pub description: Option<String>,
+    pub arguments: Vec<PromptArgument>,
+    pub template: String,
+}
+
+impl AgentDefinition {
+    /// The persona followed by the template filled with `arguments`.
+    /// Lines naming an optional argument that was not given are left out.
+    pub fn render(&self, arguments: &HashMap<String, String>) -> Result<String, SenseiError> {
+        if let Some(unknown) = arguments
+            .keys()
+            .find(|name| !self.arguments.iter().any(|a| &a.name == *name))
+        {
+            return Err(SenseiError::InvalidInput(format!(
+                "Unknown argument '{}'",
+                unknown
+            )));
+        }
+        let given = |name: &str| arguments.get(name).filter(|v| !v.trim().is_empty());
+        if let Some(missing) = self
+            .arguments
+            .iter()
+            .find(|a| a.required && given(&a.name).is_none())
+        {
+            return Err(SenseiError::InvalidInput(format!(
+                "Missing required argument '{}'",
+                missing.name
+            )));
+        }
+
+        let request: Vec<String> = self
+            .template
+            .lines()
+            .filter_map(|line| fill(line, |name| given(name).map(String::as_str)))
+            .collect();
+        Ok(format!(
+            "{}\n\n{}",
+            self.prompt.trim_end(),
+            request.join("\n").trim()
+        ))
+    }
+}
+
+/// Replace the `{{name}}` placeholders of `text`; `None` when a value is missing.
+fn fill<'a>(text: &str, value: impl Fn(&str) -> Option<&'a str>) -> Option<String> {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        out.push_str(value(rest[start + 2..start + 2 + len].trim())?);
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+/// Names of the `{{name}}` placeholders of a template.
+fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        names.push(rest[start + 2..start + 2 + len].trim());
+        rest = &rest[start + 2 + len + 2..];
+    }
+    names
+}
+
+impl PromptsConfig {
+    /// Prompt of an entry, e.g. [`ROUTER_PROMPT`]. Empty prompts count as unset.
+    pub fn prompt(&self, key: &str) -> Option<&str> {
+        self.agents
+            .get(key)
+            .map(|agent| agent.prompt.as_str())
+            .filter(|prompt| !prompt.is_empty())
+    }
+
+    /// The agents Sensei ships with, keyed by their entry name.
+    fn builtin_agents(&self) -> BTreeMap<&'static str, AgentConfig> {
+        use Classification::{Secret, Unclassified};
+        use LlmTier::{Fast, Smart};
+
+        let master = self.prompt(MASTER_PROMPT).unwrap_or(DEFAULT_MASTER_PROMPT);
+        BTreeMap::from([
+            (
+                "red_team",
+                AgentConfig {
+                    master_prompt: Some(master.to_string()),
+                    ..AgentConfig::builtin("SYSTEM: You are a Red Team Operator.", Smart, Secret)
+                }
+                .category("red")
+                .describe("Offensive security: attack paths, exploitation and adversary emulation.")
+                .targeted(
+                    "Host, network, application or organization to assess",
+                    "Rules of engagement: what is in and out of bounds",
+                    "Plan an offensive assessment of {{target}}.\nScope: {{scope}}",
+                ),
+            ),
+            (
+                "blue_team",
+                AgentConfig::builtin("SYSTEM: You are a Blue Team Analyst.", Smart, Secret)
+                    .category("blue")
+                    .describe("Defensive security: hardening, detection and incident response.")
+                    .targeted(
+                        "System or environment to defend",
+                        "Threats, logs or incident to focus on",
+                        "Review the defenses of {{target}}.\nFocus: {{scope}}",
+                    ),
+            ),
+            (
+                "cloud",
+                AgentConfig::builtin("SYSTEM: You are a Cloud Security Architect.", Smart, Secret)
+                    .describe("Cloud security architecture, IAM and misconfiguration review.")
+                    .targeted(
+                        "Cloud account, service or architecture to review",
+                        "Providers, regions or services in scope",
+                        "Review the security of {{target}}.\nScope: {{scope}}",
+                    ),
+            ),
+            (
+                "crypto",
+                AgentConfig::builtin("SYSTEM: You are a Cryptographer.", Smart, Secret)
+                    .describe("Cryptography: protocols, primitives and their implementation."),
+            ),
+            (
+                "osint",
+                AgentConfig::builtin("SYSTEM: You are an Intelligence Officer.", Smart, Secret)
+                    .describe("Open source intelligence gathering and analysis.")
+                    .targeted(
+                        "Person, organization or domain to investigate",
+                        "Sources and questions to focus on",
+                        "Gather open source intelligence on {{target}}.\nScope: {{scope}}",
+                    ),
+            ),
+            (
+                "casual",
+                AgentConfig::builtin("SYSTEM: You are Sensei.", Fast, Unclassified)
+                    .describe("General conversation with Sensei."),
+            ),
+            (
+                "novice",
+                AgentConfig::builtin("SYSTEM: You are a Teacher.", Fast, Unclassified)
+                    .describe("Security concepts explained for beginners."),
+            ),
+            (
+                "action",
+                AgentConfig::builtin(DEFAULT_TOOL_ROLE, Fast, Unclassified)
+                    .tools(&["nmap"])
+                    .describe("Runs network scans with nmap."),
+            ),
+            (
+                "system",
+                AgentConfig::builtin(DEFAULT_TOOL_ROLE, Fast, Unclassified)
+                    .tools(&["system_diagnostic"])
+                    .describe("Checks the health of the host."),
+            ),
+        ])
+    }
+
+    /// The swarm: the built-in agents with their entries applied, plus one agent per other entry.
+    /// An entry overrides the built-in agent of the same name or category. Sorted by category.
+    pub fn agent_definitions(&self) -> Result<Vec<AgentDefinition>, SenseiError> {
+        let mut agents: BTreeMap<String, AgentConfig> = self
+            .builtin_agents()
+            .into_iter()
+            .map(|(key, agent)| (key.to_string(), agent))
+            .collect();
+
+        for (key, entry) in &self.agents {
+            if key == ROUTER_PROMPT || key == MASTER_PROMPT {
+                continue;
+            }
+            let category = entry.category.as_deref().unwrap_or(key);
+            let builtin = agents.keys().find(|name| {
+                *name == key || agents[*name].category.as_deref().unwrap_or(name) == category
+            });
+            match builtin.cloned() {
+                Some(name) => {
+                    let agent = agents.remove(&name).unwrap_or_default().merge(entry);
+                    agents.insert(name, agent);
+                }
+                None if entry.prompt.is_empty() => {
+                    return Err(SenseiError::Config(format!(
+                        "Agent '{}' needs a prompt",
+                        key
+                    )));
+                }
+                None => {
+                    agents.insert(key.clone(), entry.clone());
+                }
+            }
+        }
+
+        let mut definitions: Vec<AgentDefinition> = Vec::with_capacity(agents.len());
+        for (key, agent) in agents {
+            let category = AgentCategory::new(agent.category.as_deref().unwrap_or(&key));
+            if category.as_str().is_empty() || category.as_str() == ROUTER_PROMPT {
+                return Err(SenseiError::Config(format!(
+                    "Agent '{}' has an invalid category",
+                    key
+                )));
+            }
+            if definitions.iter().any(|d| d.category == category) {
+                return Err(SenseiError::Config(format!(
+                    "Agent '{}' reuses the category '{}'",
+                    key,
+                    category.as_str()
+                )));
+            }
+            let arguments = agent.arguments.unwrap_or_else(|| {
+                vec![PromptArgument::new(
+                    DEFAULT_PROMPT_ARGUMENT,
+                    "What to ask the agent",
+                    true,
+                )]
+            });
+            let template = agent.template.unwrap_or_else(|| {
+                arguments
+                    .iter()
+                    .map(|a| format!("{{{{{}}}}}", a.name))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            });
+            for (i, argument) in arguments.iter().enumerate() {
+                if argument.name.trim().is_empty()
+                    || arguments[..i].iter().any(|a| a.name == argument.name)
+                {
+                    return Err(SenseiError::Config(format!(
+                        "Agent '{}' has an empty or repeated argument '{}'",
+                        key, argument.name
+                    )));
+                }
+            }
+            if let Some(name) = placeholders(&template)
+                .into_iter()
+                .find(|name| !arguments.iter().any(|a| a.name == *name))
+            {
+                return Err(SenseiError::Config(format!(
+                    "Agent '{}' template uses the undeclared argument '{}'",
+                    key, name
+                )));
+            }
+            definitions.push(AgentDefinition {
+                category,
+                prompt: agent.prompt,
+                tier: agent.tier.unwrap_or_default(),
+                model: agent.model,
+                temperature: agent.temperature,
+                tools: agent.tools.unwrap_or_default(),
+                rag: agent.rag.unwrap_or(true),
+                clearance: agent.clearance.unwrap_or_default(),
+                master_prompt: agent.master_prompt,
+                description: agent.description,
+                arguments,
+                template,
+            });
+        }
+        definitions.sort_by(|a, b| a.category.as_str().cmp(b.category.as_str()));
+        Ok(definitions)
+    }
 }
 
 pub fn load_prompts(path: &str) -> Result<PromptsConfig, SenseiError> {
@@ -31,17 +431,54 @@ pub fn load_prompts(path: &str) -> Result<PromptsConfig, SenseiError> {
 
 // --- MCP Settings (JSON) ---
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct McpSettings {
     #[serde(rename = "mcpServers")]
     pub mcp_servers: HashMap<String, McpServerConfig>,
 }
 
-#[derive(Debug, Deserialize)]
+/// An MCP server, either spawned from `command` (stdio) or reached at `url` over HTTP.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct McpServerConfig {
-    pub command: String,
+    pub command: Option<String>,
+    #[serde(default)]
     pub args: Vec<String>,
     pub env: Option<HashMap<String, String>>,
+    pub url: Option<String>,
+    /// Sent with every HTTP request, e.g. `Authorization`.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub transport: RemoteTransport,
+    /// Ingest the server's resources into the knowledge base, at `classification`.
+    #[serde(default)]
+    pub index_resources: bool,
+    #[serde(default)]
+    pub classification: Classification,
+}
+
+/// HTTP flavour of a remote MCP server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteTransport {
+    /// One POST endpoint answering with JSON or an event stream (MCP 2025-03-26).
+    #[default]
+    #[serde(alias = "http")]
+    StreamableHttp,
+    /// Legacy HTTP+SSE: a GET event stream plus a POST endpoint it announces (MCP 2024-11-05).
+    Sse,
+}
+
+impl McpServerConfig {
+    fn validate(&self, name: &str) -> Result<(), SenseiError> {
+        match (&self.command, &self.url) {
+            (Some(_), None) | (None, Some(_)) => Ok(()),
+            _ => Err(SenseiError::Config(format!(
+                "MCP server '{}' needs exactly one of 'command' or 'url'",
+                name
+            ))),
+        }
+    }
 }
 
 pub fn load_mcp_settings(path: &str) -> Result<McpSettings, SenseiError> {
@@ -54,5 +491,8 @@ pub fn load_mcp_settings(path: &str) -> Result<McpSettings, SenseiError> {
 
     let settings: McpSettings = serde_json::from_str(&contents)
         .map_err(|e| SenseiError::Config(format!("Failed to parse MCP JSON: {}", e)))?;
+    for (name, server) in &settings.mcp_servers {
+        server.validate(name)?;
+    }
     Ok(settings)
 }
@@ -0,0 +1,250 @@
+use crate::errors::SenseiError;
+use crate::llm::{ChatTurn, Llm};
+use crate::memory::{MemoryStore, Message};
+use sensei_common::Classification;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Default token budget for the conversation history replayed to agents.
+pub const DEFAULT_CONTEXT_TOKENS: usize = 4000;
+
+/// Default number of session summaries kept in memory.
+pub const DEFAULT_SUMMARY_CACHE: usize = 256;
+
+/// Rough token estimate (~4 chars per token), good enough for budgeting.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// The turns of a session an agent may see.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct History {
+    pub turns: Vec<ChatTurn>,
+    /// Highest classification among the replayed turns; answers built on them inherit it.
+    pub classification: Classification,
+}
+
+/// Builds the conversation history of a session for agents.
+///
+/// Only turns the receiving agent is cleared for are replayed ("no read up").
+/// The most recent turns are kept verbatim as long as they fit in `max_tokens`.
+/// Older turns are dropped, or condensed into a single system turn when a summarizer is set.
+pub struct ContextWindow {
+    memory: MemoryStore,
+    max_tokens: usize,
+    summarizer: Option<Arc<dyn Llm>>,
+    summaries: Mutex<SummaryCache>,
+}
+
+/// Least recently used summaries, bounded to `capacity` entries.
+struct SummaryCache {
+    capacity: usize,
+    tick: u64,
+    // (session_id, clearance) -> (number of summarized messages, summary, last use)
+    entries: HashMap<(String, Classification), (usize, String, u64)>,
+}
+
+impl SummaryCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &(String, Classification), count: usize) -> Option<String> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(key)?;
+        if entry.0 != count {
+            return None;
+        }
+        entry.2 = self.tick;
+        Some(entry.1.clone())
+    }
+
+    fn insert(&mut self, key: (String, Classification), count: usize, summary: String) {
+        self.tick += 1;
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.2)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        if self.capacity > 0 {
+            self.entries.insert(key, (count, summary, self.tick));
+        }
+    }
+
+    fn remove(&mut self, session_id: &str) {
+        self.entries.retain(|(id, _), _| id != session_id);
+    }
+}
+
+impl ContextWindow {
+    pub fn new(memory: MemoryStore, max_tokens: usize) -> Self {
+        Self {
+            memory,
+            max_tokens,
+            summarizer: None,
+            summaries: Mutex::new(SummaryCache::new(DEFAULT_SUMMARY_CACHE)),
+        }
+    }
+
+    /// Keep at most `sessions` summaries in memory (one per session and clearance).
+    pub fn with_summary_cache(mut self, sessions: usize) -> Self {
+        self.summaries = Mutex::new(SummaryCache::new(sessions));
+        self
+    }
+
+    /// Drop the cached summary of a session, e.g. once it is deleted.
+    pub async fn forget(&self, session_id: &str) {
+        self.summaries.lock().await.remove(session_id);
+    }
+
+    /// Summarize turns that fall out of the window instead of dropping them.
+    pub fn with_summarizer(mut self, llm: Arc<dyn Llm>) -> Self {
+        self.summarizer = Some(llm);
+        self
+    }
+
+    /// Load the history of `session_id` an agent cleared at `clearance` may see, oldest turn first.
+    pub async fn load(
+        &self,
+        session_id: &str,
+        clearance: Classification,
+    ) -> Result<History, SenseiError> {
+        let messages = self.memory.get_messages(session_id).await?;
+        if messages.is_empty() {
+            // Deleted or empty session: nothing to summarize anymore.
+            self.forget(session_id).await;
+        }
+        let messages: Vec<Message> = messages
+            .into_iter()
+            .filter(|m| clearance.dominates(m.classification))
+            .collect();
+        let classification = messages
+            .iter()
+            .map(|m| m.classification)
+            .max()
+            .unwrap_or_default();
+
+        // Walk back from the newest message until the budget is spent.
+        let mut used = 0;
+        let mut split = messages.len();
+        for message in messages.iter().rev() {
+            let cost = estimate_tokens(&message.content);
+            if used + cost > self.max_tokens {
+                break;
+            }
+            used += cost;
+            split -= 1;
+        }
+
+        let (older, recent) = messages.split_at(split);
+        let mut turns = Vec::with_capacity(recent.len() + 1);
+
+        if !older.is_empty()
+            && let Some(summary) = self.summarize(session_id, clearance, older).await
+        {
+            turns.push(ChatTurn::system(format!(
+                "Summary of the earlier conversation:\n{}",
+                summary
+            )));
+        }
+
+        turns.extend(recent.iter().filter_map(to_turn));
+        Ok(History {
+            turns,
+            classification,
+        })
+    }
+
+    async fn summarize(
+        &self,
+        session_id: &str,
+        clearance: Classification,
+        older: &[Message],
+    ) -> Option<String> {
+        let llm = self.summarizer.as_ref()?;
+
+        // Summaries only change when more turns slide out of the window.
+        let key = (session_id.to_string(), clearance);
+        if let Some(summary) = self.summaries.lock().await.get(&key, older.len()) {
+            return Some(summary);
+        }
+
+        let transcript = older
+            .iter()
+            .map(|m| format!("{}: {}", m.role, m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let prompt = format!(
+            "Summarize the following conversation in a few sentences. Keep names, targets, \
+             findings and decisions; drop pleasantries.\n\n{}",
+            transcript
+        );
+
+        match llm.generate(&prompt).await {
+            Ok(summary) => {
+                let summary = summary.trim().to_string();
+                self.summaries
+                    .lock()
+                    .await
+                    .insert(key, older.len(), summary.clone());
+                Some(summary)
+            }
+            Err(e) => {
+                eprintln!("Context summarization failed: {}", e);
+                None
+            }
+        }
+    }
+}
+
+fn to_turn(message: &Message) -> Option<ChatTurn> {
+    match message.role.as_str() {
+        "user" => Some(ChatTurn::user(message.content.as_str())),
+        "assistant" => Some(ChatTurn::assistant(message.content.as_str())),
+        _ => None,
+    }
+}
+
+/// Maximum length (in chars) of generated session titles.
+const MAX_TITLE_CHARS: usize = 60;
+
+/// Ask the LLM for a short title summarizing the first exchange of a session.
+pub async fn generate_session_title(llm: &dyn Llm, prompt: &str, answer: &str) -> Option<String> {
+    let request = format!(
+        "Write a title of at most 6 words for a conversation that starts like this. \
+         Output the title only, without quotes or punctuation at the end.\n\n\
+         User: {}\nAssistant: {}",
+        prompt,
+        answer.chars().take(500).collect::<String>()
+    );
+
+    let raw = match llm.generate(&request).await {
+        Ok(raw) => raw,
+        Err(e) => {
+            eprintln!("Session title generation failed: {}", e);
+            return None;
+        }
+    };
+
+    let title = raw
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())?
+        .trim_matches(|c: char| c == '"' || c == '\'' || c == '*' || c == '#' || c == '.')
+        .trim();
+
+    if title.is_empty() {
+        return None;
+    }
+    Some(title.chars().take(MAX_TITLE_CHARS).collect())
+}
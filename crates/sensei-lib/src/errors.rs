@@ -23,6 +23,9 @@ pub enum SenseiError {
     #[error("Tool execution error: {0}")]
     Tool(String),
 
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
@@ -0,0 +1,484 @@
+use crate::errors::SenseiError;
+use crate::llm::Llm;
+use crate::mcp_client::McpClient;
+use crate::memory::{Document, DocumentMetadata, MemoryStore};
+use sensei_common::Classification;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Chunk size and overlap, in bytes of text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkConfig {
+    pub size: usize,
+    pub overlap: usize,
+}
+
+impl Default for ChunkConfig {
+    fn default() -> Self {
+        Self {
+            size: 1500,
+            overlap: 200,
+        }
+    }
+}
+
+impl ChunkConfig {
+    pub fn new(size: usize, overlap: usize) -> Self {
+        let size = size.max(100);
+        Self {
+            size,
+            // Overlap must leave room for new text in every chunk.
+            overlap: overlap.min(size / 2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub index: usize,
+    pub content: String,
+    /// SHA-256 of `content`.
+    pub hash: String,
+}
+
+/// A document to ingest. `source` (e.g. a file path) identifies it across versions.
+#[derive(Debug, Clone)]
+pub struct IngestRequest {
+    pub content: String,
+    pub source: Option<String>,
+    pub classification: Classification,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct IngestReport {
+    pub source: Option<String>,
+    /// Chunks stored by this ingest.
+    pub chunks: usize,
+    /// Chunks of an older version of the source that were removed.
+    pub replaced: usize,
+    /// The source was already ingested with identical content.
+    pub unchanged: bool,
+    /// Chunks of an unchanged source whose classification or tags were updated.
+    pub relabeled: usize,
+}
+
+pub fn content_hash(text: &str) -> String {
+    hex::encode(Sha256::digest(text.as_bytes()))
+}
+
+/// Chunk, embed and store a document.
+/// Re-ingesting an unchanged source only updates its classification and tags;
+/// a new version replaces the old chunks.
+pub async fn ingest(
+    memory: &MemoryStore,
+    llm: &dyn Llm,
+    request: IngestRequest,
+    config: &ChunkConfig,
+) -> Result<IngestReport, SenseiError> {
+    let source_hash = content_hash(&request.content);
+
+    if let Some(source) = &request.source
+        && memory.source_hash(source).await?.as_deref() == Some(source_hash.as_str())
+    {
+        let relabeled = memory
+            .relabel_source(source, request.classification, &request.tags)
+            .await?;
+        return Ok(IngestReport {
+            source: request.source,
+            chunks: 0,
+            replaced: 0,
+            unchanged: true,
+            relabeled,
+        });
+    }
+
+    let chunks = chunk_markdown(&request.content, config);
+    let mut stored = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        let embedding = llm.embed(&chunk.content).await?;
+        let metadata = DocumentMetadata {
+            classification: request.classification,
+            source: request.source.clone(),
+            chunk_index: Some(chunk.index),
+            content_hash: Some(chunk.hash),
+            source_hash: Some(source_hash.clone()),
+            tags: request.tags.clone(),
+        };
+        stored.push((chunk.content, metadata, embedding));
+    }
+
+    let chunks = stored.len();
+    let replaced = memory
+        .replace_source_documents(request.source.as_deref(), stored)
+        .await?;
+
+    Ok(IngestReport {
+        source: request.source,
+        chunks,
+        replaced,
+        unchanged: false,
+        relabeled: 0,
+    })
+}
+
+/// Ingest the text resources of an MCP server so agents retrieve them as knowledge.
+/// Each resource is stored under its URI as source and tagged `mcp` and `server`;
+/// binary contents are skipped. Unchanged resources are not embedded again.
+pub async fn ingest_mcp_resources(
+    memory: &MemoryStore,
+    llm: &dyn Llm,
+    client: &McpClient,
+    server: &str,
+    classification: Classification,
+    config: &ChunkConfig,
+) -> Result<Vec<IngestReport>, SenseiError> {
+    let mcp_error = |e: anyhow::Error| SenseiError::Tool(format!("MCP server '{}': {}", server, e));
+    let mut reports = Vec::new();
+    for resource in client.list_resources().await.map_err(mcp_error)? {
+        let Some(uri) = resource.get("uri").and_then(|u| u.as_str()) else {
+            continue;
+        };
+        let content = client
+            .read_resource(uri)
+            .await
+            .map_err(mcp_error)?
+            .iter()
+            .filter_map(|c| c.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        if content.trim().is_empty() {
+            continue;
+        }
+        let request = IngestRequest {
+            content,
+            source: Some(uri.to_string()),
+            classification,
+            tags: vec!["mcp".to_string(), server.to_string()],
+        };
+        reports.push(ingest(memory, llm, request, config).await?);
+    }
+    Ok(reports)
+}
+
+/// Changes to a stored document. Fields left out are kept.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DocumentUpdate {
+    pub content: Option<String>,
+    pub classification: Option<Classification>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// Apply `update` to a document, re-embedding it when its content changes.
+/// Returns `None` if the document does not exist.
+pub async fn update_document(
+    memory: &MemoryStore,
+    llm: &dyn Llm,
+    id: i64,
+    update: DocumentUpdate,
+) -> Result<Option<Document>, SenseiError> {
+    let Some(mut document) = memory.find_document(id).await? else {
+        return Ok(None);
+    };
+
+    let mut embedding = None;
+    if let Some(content) = update.content
+        && content != document.content
+    {
+        embedding = Some(llm.embed(&content).await?);
+        document.metadata.content_hash = Some(content_hash(&content));
+        // The source no longer matches the stored chunks, so its next ingest must not be skipped.
+        document.metadata.source_hash = None;
+        document.content = content;
+    }
+    if let Some(classification) = update.classification {
+        document.metadata.classification = classification;
+    }
+    if let Some(tags) = update.tags {
+        document.metadata.tags = tags;
+    }
+
+    if !memory
+        .update_document(id, &document.content, &document.metadata, embedding)
+        .await?
+    {
+        return Ok(None);
+    }
+    Ok(Some(document))
+}
+
+/// Rows embedded per batch by `reembed`.
+const REEMBED_BATCH: i64 = 64;
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ReembedReport {
+    /// Vector tables recreated for a new dimension.
+    pub resized: usize,
+    pub documents: usize,
+    pub router_cache: usize,
+}
+
+/// Bring every stored vector to the embedding model `memory` is configured with.
+/// Vector tables of another dimension are recreated, then documents and router cache entries
+/// embedded by another model are embedded again. Safe to rerun after an interruption.
+pub async fn reembed(memory: &MemoryStore, llm: &dyn Llm) -> Result<ReembedReport, SenseiError> {
+    let mut report = ReembedReport {
+        resized: memory.resize_vector_tables().await?,
+        ..Default::default()
+    };
+
+    loop {
+        let batch = memory.stale_documents(REEMBED_BATCH).await?;
+        if batch.is_empty() {
+            break;
+        }
+        for (id, content) in batch {
+            memory
+                .set_document_embedding(id, &llm.embed(&content).await?)
+                .await?;
+            report.documents += 1;
+        }
+    }
+
+    loop {
+        let batch = memory.stale_router_cache(REEMBED_BATCH).await?;
+        if batch.is_empty() {
+            break;
+        }
+        for (id, query) in batch {
+            memory
+                .set_router_cache_embedding(id, &llm.embed(&query).await?)
+                .await?;
+            report.router_cache += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+// --- Markdown aware chunking ---
+
+#[derive(Debug)]
+enum Block {
+    Heading { level: usize, text: String },
+    Code(String),
+    Text(String),
+}
+
+/// Split Markdown into chunks of about `config.size` bytes (plus heading context).
+/// Chunks never straddle a heading, fenced code blocks are kept whole when they fit,
+/// and consecutive text chunks of a section share `config.overlap` bytes.
+/// Every chunk is prefixed with the headings it belongs to, e.g. `# Runbook > ## Restore`.
+/// Identical chunks are only kept once.
+pub fn chunk_markdown(text: &str, config: &ChunkConfig) -> Vec<Chunk> {
+    let mut contents: Vec<String> = Vec::new();
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    let mut pieces: Vec<(String, bool)> = Vec::new();
+
+    for block in parse_blocks(text) {
+        match block {
+            Block::Heading { level, text } => {
+                pack_section(&headings, &pieces, config, &mut contents);
+                pieces.clear();
+                headings.retain(|(l, _)| *l < level);
+                headings.push((level, text));
+            }
+            Block::Code(code) => pieces.extend(split_code(&code, config.size)),
+            Block::Text(text) => pieces.extend(
+                split_text(&text, config.size)
+                    .into_iter()
+                    .map(|piece| (piece, false)),
+            ),
+        }
+    }
+    pack_section(&headings, &pieces, config, &mut contents);
+
+    let mut chunks: Vec<Chunk> = Vec::new();
+    for content in contents {
+        let hash = content_hash(&content);
+        if chunks.iter().any(|c| c.hash == hash) {
+            continue;
+        }
+        chunks.push(Chunk {
+            index: chunks.len(),
+            content,
+            hash,
+        });
+    }
+    chunks
+}
+
+fn parse_blocks(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut fence: Option<(String, Vec<&str>)> = None;
+
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Text(paragraph.join("\n")));
+            paragraph.clear();
+        }
+    };
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+
+        if let Some((marker, mut lines)) = fence.take() {
+            lines.push(line);
+            if trimmed.starts_with(marker.as_str()) && lines.len() > 1 {
+                blocks.push(Block::Code(lines.join("\n")));
+            } else {
+                fence = Some((marker, lines));
+            }
+            continue;
+        }
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            flush(&mut paragraph, &mut blocks);
+            fence = Some((trimmed[..3].to_string(), vec![line]));
+        } else if let Some((level, title)) = heading(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Heading {
+                level,
+                text: format!("{} {}", "#".repeat(level), title),
+            });
+        } else if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+        } else {
+            paragraph.push(line);
+        }
+    }
+
+    // An unterminated fence is still code.
+    if let Some((_, lines)) = fence {
+        blocks.push(Block::Code(lines.join("\n")));
+    }
+    flush(&mut paragraph, &mut blocks);
+    blocks
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let title = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then(|| (level, title.trim()))
+}
+
+/// Pack the pieces of one section into chunks.
+fn pack_section(
+    headings: &[(usize, String)],
+    pieces: &[(String, bool)],
+    config: &ChunkConfig,
+    out: &mut Vec<String>,
+) {
+    let context = headings
+        .iter()
+        .map(|(_, h)| h.as_str())
+        .collect::<Vec<_>>()
+        .join(" > ");
+    let emit = |body: &str, out: &mut Vec<String>| {
+        if body.trim().is_empty() {
+            return;
+        }
+        if context.is_empty() {
+            out.push(body.to_string());
+        } else {
+            out.push(format!("{}\n\n{}", context, body));
+        }
+    };
+
+    let mut body = String::new();
+    let mut ends_with_code = false;
+    for (piece, is_code) in pieces {
+        if !body.is_empty() && body.len() + 2 + piece.len() > config.size {
+            emit(&body, out);
+            // Carry the end of the previous text over; code is never cut for overlap.
+            let carried = if ends_with_code {
+                String::new()
+            } else {
+                tail(&body, config.overlap).to_string()
+            };
+            body = carried;
+        }
+        if !body.is_empty() {
+            body.push_str("\n\n");
+        }
+        body.push_str(piece);
+        ends_with_code = *is_code;
+    }
+    emit(&body, out);
+}
+
+/// Split text longer than `size` at whitespace.
+fn split_text(text: &str, size: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut rest = text.trim();
+    while rest.len() > size {
+        let limit = rest.floor_char_boundary(size);
+        let cut = rest[..limit]
+            .rfind(char::is_whitespace)
+            .filter(|i| *i > 0)
+            .unwrap_or(limit);
+        pieces.push(rest[..cut].trim_end().to_string());
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() {
+        pieces.push(rest.to_string());
+    }
+    pieces
+}
+
+/// Split a fenced block longer than `size` by lines, re-fencing every part.
+fn split_code(code: &str, size: usize) -> Vec<(String, bool)> {
+    if code.len() <= size {
+        return vec![(code.to_string(), true)];
+    }
+
+    let mut lines: Vec<&str> = code.lines().collect();
+    let open = lines.remove(0);
+    let marker = &open.trim_start()[..3];
+    if lines
+        .last()
+        .is_some_and(|l| l.trim_start().starts_with(marker))
+    {
+        lines.pop();
+    }
+
+    let mut parts = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut len = open.len() + marker.len() + 2;
+    for line in lines {
+        if !current.is_empty() && len + line.len() + 1 > size {
+            parts.push((
+                format!("{}\n{}\n{}", open, current.join("\n"), marker),
+                true,
+            ));
+            current.clear();
+            len = open.len() + marker.len() + 2;
+        }
+        len += line.len() + 1;
+        current.push(line);
+    }
+    if !current.is_empty() {
+        parts.push((
+            format!("{}\n{}\n{}", open, current.join("\n"), marker),
+            true,
+        ));
+    }
+    parts
+}
+
+/// The last `overlap` bytes of `text`, starting at a word boundary.
+fn tail(text: &str, overlap: usize) -> &str {
+    if overlap == 0 {
+        return "";
+    }
+    if text.len() <= overlap {
+        return text;
+    }
+    let start = text.ceil_char_boundary(text.len() - overlap);
+    let tail = &text[start..];
+    match tail.find(char::is_whitespace) {
+        Some(i) => tail[i..].trim_start(),
+        None => tail,
+    }
+}
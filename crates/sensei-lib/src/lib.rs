@@ -1,7 +1,14 @@
 pub mod agents;
+pub mod auth;
 pub mod config;
+pub mod context;
 pub mod errors;
+pub mod ingest;
 pub mod llm;
 pub mod mcp_client;
+pub mod mcp_transport;
 pub mod memory;
+pub mod rag;
+pub mod reload;
+pub mod runtime;
 pub mod tools;
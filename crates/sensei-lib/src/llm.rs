@@ -1,10 +1,127 @@
 use crate::errors::SenseiError;
+use crate::tools::{ToolInvocation, ToolSpec};
 use async_trait::async_trait;
-use genai::Client;
-use genai::chat::{ChatMessage, ChatRequest};
-use genai::embed::EmbedRequest;
+use futures::stream::{self, BoxStream, StreamExt};
+use genai::chat::{ChatMessage, ChatOptions, ChatRequest, ChatResponse, ChatStreamEvent};
+use genai::embed::{EmbedOptions, EmbedRequest};
+use genai::resolver::Endpoint;
+use genai::{Client, ServiceTarget};
 use serde_json::{Value, json};
 use std::env;
+use std::sync::Arc;
+
+/// A stream of text deltas produced by an LLM.
+pub type LlmStream = BoxStream<'static, Result<String, SenseiError>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+}
+
+/// One structured message of a conversation sent to an LLM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatTurn {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+impl ChatTurn {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: ChatRole::System,
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: ChatRole::User,
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: ChatRole::Assistant,
+            content: content.into(),
+        }
+    }
+}
+
+/// Flatten a conversation into a single prompt for clients without chat support.
+/// The last user turn becomes the `User Query`, earlier turns are replayed as a transcript.
+pub fn flatten_turns(turns: &[ChatTurn]) -> String {
+    let system = turns
+        .iter()
+        .filter(|t| t.role == ChatRole::System)
+        .map(|t| t.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let mut dialogue: Vec<&ChatTurn> = turns
+        .iter()
+        .filter(|t| t.role != ChatRole::System)
+        .collect();
+    let query = match dialogue.last() {
+        Some(last) if last.role == ChatRole::User => dialogue.pop().map(|t| t.content.as_str()),
+        _ => None,
+    };
+
+    let mut prompt = system;
+    if !dialogue.is_empty() {
+        let transcript = dialogue
+            .iter()
+            .map(|t| match t.role {
+                ChatRole::Assistant => format!("Assistant: {}", t.content),
+                _ => format!("User: {}", t.content),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        prompt = format!("{}\n\nConversation so far:\n{}", prompt, transcript);
+    }
+    if let Some(query) = query {
+        prompt = format!("{}\n\nUser Query: {}", prompt, query);
+    }
+    prompt.trim_start().to_string()
+}
+
+fn to_chat_request(turns: &[ChatTurn]) -> ChatRequest {
+    let messages = turns
+        .iter()
+        .map(|t| match t.role {
+            ChatRole::System => ChatMessage::system(t.content.as_str()),
+            ChatRole::User => ChatMessage::user(t.content.as_str()),
+            ChatRole::Assistant => ChatMessage::assistant(t.content.as_str()),
+        })
+        .collect();
+    ChatRequest::new(messages)
+}
+
+fn to_tool_request(prompt: &str, tools: &[ToolSpec]) -> ChatRequest {
+    let tools = tools
+        .iter()
+        .map(|t| {
+            genai::chat::Tool::new(t.name.as_str())
+                .with_description(t.description.as_str())
+                .with_schema(t.input_schema.clone())
+        })
+        .collect::<Vec<_>>();
+    ChatRequest::from_user(prompt).with_tools(tools)
+}
+
+/// The first tool call of a response, if the model chose to call one.
+fn first_tool_call(response: ChatResponse) -> Option<ToolInvocation> {
+    response
+        .into_tool_calls()
+        .into_iter()
+        .next()
+        .map(|call| ToolInvocation {
+            tool_name: call.fn_name,
+            arguments: call.fn_arguments,
+        })
+}
 
 #[async_trait]
 pub trait Llm: Send + Sync {
@@ -13,18 +130,136 @@ pub trait Llm: Send + Sync {
     async fn generate_raw(&self, prompt: &str) -> Result<String, SenseiError> {
         self.generate(prompt).await
     }
+
+    /// Stream the answer as text deltas.
+    /// Clients without native streaming yield the whole answer as a single chunk.
+    async fn generate_stream(&self, prompt: &str) -> Result<LlmStream, SenseiError> {
+        let text = self.generate(prompt).await?;
+        Ok(stream::once(async move { Ok(text) }).boxed())
+    }
+
+    /// Answer a multi-turn conversation.
+    /// Clients without chat support receive the conversation flattened into one prompt.
+    async fn chat(&self, turns: &[ChatTurn]) -> Result<String, SenseiError> {
+        self.generate(&flatten_turns(turns)).await
+    }
+
+    /// Streaming counterpart of `chat`.
+    async fn chat_stream(&self, turns: &[ChatTurn]) -> Result<LlmStream, SenseiError> {
+        self.generate_stream(&flatten_turns(turns)).await
+    }
+
+    /// Pick one of `tools` through native function calling.
+    /// `Ok(None)` means the model answered without calling a tool.
+    /// Clients without function calling return an error so callers can fall back to prompting.
+    async fn select_tool(
+        &self,
+        _prompt: &str,
+        _tools: &[ToolSpec],
+    ) -> Result<Option<ToolInvocation>, SenseiError> {
+        Err(SenseiError::Llm(
+            "Native function calling is not supported by this client".to_string(),
+        ))
+    }
+}
+
+/// Adapts a genai chat stream into plain text deltas, dropping non-text events.
+fn text_deltas(stream: genai::chat::ChatStream, model: String) -> LlmStream {
+    stream
+        .filter_map(move |event| {
+            let model = model.clone();
+            async move {
+                match event {
+                    Ok(ChatStreamEvent::Chunk(chunk)) => Some(Ok(chunk.content)),
+                    Ok(_) => None,
+                    Err(e) => Some(Err(SenseiError::Llm(format!(
+                        "Stream from model '{}' failed: {}",
+                        model, e
+                    )))),
+                }
+            }
+        })
+        .boxed()
 }
 
 pub const MODEL_CHAT_FAST: &str = "gemini-2.5-flash";
 pub const MODEL_CHAT_SMART: &str = "gemini-3-pro-preview";
 pub const MODEL_CHAT_DEFAULT: &str = MODEL_CHAT_FAST;
+/// Sampling temperature for chat unless an agent sets its own.
+pub const DEFAULT_TEMPERATURE: f64 = 0.7;
 pub const MODEL_EMBEDDING: &str = "gemini-embedding-001";
+pub const DEFAULT_EMBEDDING_DIMENSION: usize = 3072;
+
+/// Namespace of local embedding models, e.g. `ollama::nomic-embed-text`.
+const OLLAMA_NAMESPACE: &str = "ollama::";
+
+/// The model turning text into vectors and the size of those vectors.
+/// Vectors of different models are never compared: changing either field requires `sensei-server reembed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddingConfig {
+    /// genai model id: `gemini-embedding-001`, or `ollama::<model>` for a local model.
+    pub model: String,
+    pub dimension: usize,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self::new(MODEL_EMBEDDING, DEFAULT_EMBEDDING_DIMENSION)
+    }
+}
+
+impl EmbeddingConfig {
+    pub fn new(model: &str, dimension: usize) -> Self {
+        Self {
+            model: model.trim().to_string(),
+            dimension,
+        }
+    }
+
+    /// Whether the model is served by Ollama.
+    pub fn is_local(&self) -> bool {
+        self.model.starts_with(OLLAMA_NAMESPACE)
+    }
+}
+
+/// Embed `text` with `config.model`, checking the vector has `config.dimension` values.
+async fn embed_with(
+    client: &Client,
+    config: &EmbeddingConfig,
+    text: &str,
+) -> Result<Vec<f32>, SenseiError> {
+    let req = EmbedRequest::new(text.to_string());
+    // Gemini can shorten its vectors; local models have a fixed size.
+    let options =
+        (!config.is_local()).then(|| EmbedOptions::default().with_dimensions(config.dimension));
+    let response = client
+        .exec_embed(&config.model, req, options.as_ref())
+        .await
+        .map_err(|e| {
+            SenseiError::Llm(format!("Embedding model '{}' failed: {}", config.model, e))
+        })?;
+
+    let Some(embedding) = response.embeddings.into_iter().next() else {
+        return Err(SenseiError::Llm("No embedding generated".to_string()));
+    };
+    if embedding.vector.len() != config.dimension {
+        return Err(SenseiError::Llm(format!(
+            "Embedding model '{}' returned {} dimensions, expected {}",
+            config.model,
+            embedding.vector.len(),
+            config.dimension
+        )));
+    }
+    Ok(embedding.vector)
+}
 
 // --- Gemini Implementation ---
 
 pub struct GeminiClient {
     client: Client,
     model_config: String,
+    embedding: EmbeddingConfig,
+    temperature: f64,
 }
 
 impl GeminiClient {
@@ -33,25 +268,26 @@ impl GeminiClient {
         Self {
             client,
             model_config: model.to_string(),
+            embedding: EmbeddingConfig::default(),
+            temperature: DEFAULT_TEMPERATURE,
         }
     }
+
+    pub fn with_embedding(mut self, embedding: EmbeddingConfig) -> Self {
+        self.embedding = embedding;
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: f64) -> Self {
+        self.temperature = temperature;
+        self
+    }
 }
 
 #[async_trait]
 impl Llm for GeminiClient {
     async fn embed(&self, text: &str) -> Result<Vec<f32>, SenseiError> {
-        let req = EmbedRequest::new(text.to_string());
-        let response = self
-            .client
-            .exec_embed(MODEL_EMBEDDING, req, None)
-            .await
-            .map_err(|e| SenseiError::Llm(e.to_string()))?;
-
-        if let Some(embedding) = response.embeddings.first() {
-            Ok(embedding.vector.clone())
-        } else {
-            Err(SenseiError::Llm("No embedding generated".to_string()))
-        }
+        embed_with(&self.client, &self.embedding, text).await
     }
 
     async fn generate_raw(&self, prompt: &str) -> Result<String, SenseiError> {
@@ -110,9 +346,16 @@ impl Llm for GeminiClient {
     }
 
     async fn generate(&self, prompt: &str) -> Result<String, SenseiError> {
-        let chat_req = ChatRequest::new(vec![ChatMessage::user(prompt)]);
-        use genai::chat::ChatOptions;
-        let options = ChatOptions::default().with_temperature(0.7);
+        self.chat(&[ChatTurn::user(prompt)]).await
+    }
+
+    async fn generate_stream(&self, prompt: &str) -> Result<LlmStream, SenseiError> {
+        self.chat_stream(&[ChatTurn::user(prompt)]).await
+    }
+
+    async fn chat(&self, turns: &[ChatTurn]) -> Result<String, SenseiError> {
+        let chat_req = to_chat_request(turns);
+        let options = ChatOptions::default().with_temperature(self.temperature);
 
         let model = if self.model_config == "auto" {
             MODEL_CHAT_DEFAULT
@@ -135,6 +378,53 @@ impl Llm for GeminiClient {
             ))),
         }
     }
+
+    async fn chat_stream(&self, turns: &[ChatTurn]) -> Result<LlmStream, SenseiError> {
+        let chat_req = to_chat_request(turns);
+        let options = ChatOptions::default().with_temperature(self.temperature);
+
+        let model = if self.model_config == "auto" {
+            MODEL_CHAT_DEFAULT
+        } else {
+            &self.model_config
+        };
+
+        match self
+            .client
+            .exec_chat_stream(model, chat_req, Some(&options))
+            .await
+        {
+            Ok(response) => Ok(text_deltas(response.stream, model.to_string())),
+            Err(e) => Err(SenseiError::Llm(format!(
+                "Gemini model '{}' failed to stream: {}",
+                model, e
+            ))),
+        }
+    }
+
+    async fn select_tool(
+        &self,
+        prompt: &str,
+        tools: &[ToolSpec],
+    ) -> Result<Option<ToolInvocation>, SenseiError> {
+        let model = if self.model_config == "auto" {
+            MODEL_CHAT_DEFAULT
+        } else {
+            &self.model_config
+        };
+
+        match self
+            .client
+            .exec_chat(model, to_tool_request(prompt, tools), None)
+            .await
+        {
+            Ok(response) => Ok(first_tool_call(response)),
+            Err(e) => Err(SenseiError::Llm(format!(
+                "Gemini model '{}' failed to call tools: {}",
+                model, e
+            ))),
+        }
+    }
 }
 
 // --- Ollama Implementation ---
@@ -142,6 +432,8 @@ impl Llm for GeminiClient {
 pub struct OllamaClient {
     client: Client,
     model: String,
+    embedding: Option<EmbeddingConfig>,
+    options: Option<ChatOptions>,
 }
 
 impl OllamaClient {
@@ -149,16 +441,80 @@ impl OllamaClient {
         Self {
             client: Client::default(),
             model: format!("ollama/{}", model), // genai expects "ollama/modelname"
+            embedding: None,
+            options: None,
+        }
+    }
+
+    /// Sampling temperature; the model's own default applies otherwise.
+    pub fn with_temperature(mut self, temperature: f64) -> Self {
+        self.options = Some(ChatOptions::default().with_temperature(temperature));
+        self
+    }
+
+    /// Reach Ollama at `base_url`, e.g. `http://gpu-box:11434`, instead of localhost.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        let endpoint = Endpoint::from_owned(ollama_endpoint(base_url));
+        self.client = Client::builder()
+            .with_service_target_resolver_fn(move |mut target: ServiceTarget| {
+                target.endpoint = endpoint;
+                Ok(target)
+            })
+            .build();
+        self
+    }
+
+    /// Embed with a local model, e.g. `EmbeddingConfig::new("ollama::nomic-embed-text", 768)`.
+    /// A model name without the `ollama::` namespace is put in it.
+    pub fn with_embedding(mut self, mut embedding: EmbeddingConfig) -> Self {
+        if !embedding.is_local() {
+            embedding.model = format!("{}{}", OLLAMA_NAMESPACE, embedding.model);
         }
+        self.embedding = Some(embedding);
+        self
     }
 }
 
+/// Ollama's OpenAI-compatible API, under `/v1/` of the server.
+fn ollama_endpoint(base_url: &str) -> String {
+    let base_url = base_url.trim().trim_end_matches('/');
+    let base_url = base_url.strip_suffix("/v1").unwrap_or(base_url);
+    format!("{}/v1/", base_url)
+}
+
 #[async_trait]
 impl Llm for OllamaClient {
     async fn generate(&self, prompt: &str) -> Result<String, SenseiError> {
-        let chat_req = ChatRequest::new(vec![ChatMessage::user(prompt)]);
+        self.chat(&[ChatTurn::user(prompt)]).await
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, SenseiError> {
+        match &self.embedding {
+            Some(embedding) => embed_with(&self.client, embedding, text).await,
+            None => Err(SenseiError::Llm(
+                "No Ollama embedding model configured (set SENSEI_EMBEDDING_MODEL=ollama::<model>)"
+                    .to_string(),
+            )),
+        }
+    }
+
+    // Ollama is uncensored by design for many models, so raw = generate
+    async fn generate_raw(&self, prompt: &str) -> Result<String, SenseiError> {
+        self.generate(prompt).await
+    }
+
+    async fn generate_stream(&self, prompt: &str) -> Result<LlmStream, SenseiError> {
+        self.chat_stream(&[ChatTurn::user(prompt)]).await
+    }
+
+    async fn chat(&self, turns: &[ChatTurn]) -> Result<String, SenseiError> {
+        let chat_req = to_chat_request(turns);
         // Ollama usually runs local, so no special auth needed by default in genai
-        match self.client.exec_chat(&self.model, chat_req, None).await {
+        match self
+            .client
+            .exec_chat(&self.model, chat_req, self.options.as_ref())
+            .await
+        {
             Ok(response) =>
             {
                 #[allow(deprecated)]
@@ -174,16 +530,38 @@ impl Llm for OllamaClient {
         }
     }
 
-    async fn embed(&self, _text: &str) -> Result<Vec<f32>, SenseiError> {
-        // TODO: Implement local embedding via Ollama if needed
-        Err(SenseiError::Llm(
-            "Ollama embedding not yet implemented".to_string(),
-        ))
+    async fn chat_stream(&self, turns: &[ChatTurn]) -> Result<LlmStream, SenseiError> {
+        let chat_req = to_chat_request(turns);
+        match self
+            .client
+            .exec_chat_stream(&self.model, chat_req, self.options.as_ref())
+            .await
+        {
+            Ok(response) => Ok(text_deltas(response.stream, self.model.clone())),
+            Err(e) => Err(SenseiError::Llm(format!(
+                "Ollama model '{}' failed to stream: {}",
+                self.model, e
+            ))),
+        }
     }
 
-    // Ollama is uncensored by design for many models, so raw = generate
-    async fn generate_raw(&self, prompt: &str) -> Result<String, SenseiError> {
-        self.generate(prompt).await
+    async fn select_tool(
+        &self,
+        prompt: &str,
+        tools: &[ToolSpec],
+    ) -> Result<Option<ToolInvocation>, SenseiError> {
+        // Models without tool support make Ollama reject the request, which triggers the prompt fallback.
+        match self
+            .client
+            .exec_chat(&self.model, to_tool_request(prompt, tools), None)
+            .await
+        {
+            Ok(response) => Ok(first_tool_call(response)),
+            Err(e) => Err(SenseiError::Llm(format!(
+                "Ollama model '{}' failed to call tools: {}",
+                self.model, e
+            ))),
+        }
     }
 }
 
@@ -192,11 +570,22 @@ impl Llm for OllamaClient {
 pub struct TieredLlmClient {
     primary: Box<dyn Llm>,
     secondary: Option<Box<dyn Llm>>,
+    embedder: Option<Arc<dyn Llm>>,
 }
 
 impl TieredLlmClient {
     pub fn new(primary: Box<dyn Llm>, secondary: Option<Box<dyn Llm>>) -> Self {
-        Self { primary, secondary }
+        Self {
+            primary,
+            secondary,
+            embedder: None,
+        }
+    }
+
+    /// Send embeddings to `embedder` instead of the primary client.
+    pub fn with_embedder(mut self, embedder: Arc<dyn Llm>) -> Self {
+        self.embedder = Some(embedder);
+        self
     }
 }
 
@@ -233,9 +622,73 @@ impl Llm for TieredLlmClient {
         }
     }
 
+    async fn generate_stream(&self, prompt: &str) -> Result<LlmStream, SenseiError> {
+        // Failover only covers opening the stream; a stream that breaks mid-answer is surfaced as-is.
+        match self.primary.generate_stream(prompt).await {
+            Ok(stream) => Ok(stream),
+            Err(e) => {
+                if let Some(ref sec) = self.secondary {
+                    eprintln!("⚠️ Primary LLM stream failed ({}), failing over...", e);
+                    sec.generate_stream(prompt).await
+                } else {
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    async fn chat(&self, turns: &[ChatTurn]) -> Result<String, SenseiError> {
+        match self.primary.chat(turns).await {
+            Ok(res) => Ok(res),
+            Err(e) => {
+                if let Some(ref sec) = self.secondary {
+                    eprintln!("⚠️ Primary LLM chat failed ({}), failing over...", e);
+                    sec.chat(turns).await
+                } else {
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    async fn chat_stream(&self, turns: &[ChatTurn]) -> Result<LlmStream, SenseiError> {
+        match self.primary.chat_stream(turns).await {
+            Ok(stream) => Ok(stream),
+            Err(e) => {
+                if let Some(ref sec) = self.secondary {
+                    eprintln!("⚠️ Primary LLM stream failed ({}), failing over...", e);
+                    sec.chat_stream(turns).await
+                } else {
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    async fn select_tool(
+        &self,
+        prompt: &str,
+        tools: &[ToolSpec],
+    ) -> Result<Option<ToolInvocation>, SenseiError> {
+        match self.primary.select_tool(prompt, tools).await {
+            Ok(res) => Ok(res),
+            Err(e) => {
+                if let Some(ref sec) = self.secondary {
+                    eprintln!("⚠️ Primary LLM tool call failed ({}), failing over...", e);
+                    sec.select_tool(prompt, tools).await
+                } else {
+                    Err(e)
+                }
+            }
+        }
+    }
+
     async fn embed(&self, text: &str) -> Result<Vec<f32>, SenseiError> {
-        // Embeddings usually stick to primary for consistency
-        self.primary.embed(text).await
+        // Never fail over: vectors of another model are not comparable with the stored ones.
+        match &self.embedder {
+            Some(embedder) => embedder.embed(text).await,
+            None => self.primary.embed(text).await,
+        }
     }
 }
 
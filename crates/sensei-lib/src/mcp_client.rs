@@ -1,40 +1,291 @@
-use anyhow::{Context, Result, bail};
+use crate::config::{McpServerConfig, RemoteTransport};
+use crate::mcp_transport::{
+    Disconnected, Inbox, Incoming, JsonRpcRequest, JsonRpcResponse, SseTransport, StdioTransport,
+    StreamableHttpTransport, Transport,
+};
+use anyhow::{Result, anyhow, bail};
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::collections::HashMap;
-use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{Child, Command};
-use tokio::sync::Mutex;
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct JsonRpcRequest {
-    jsonrpc: String,
-    id: u64,
-    method: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    params: Option<Value>,
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify, broadcast, mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+/// How often a supervised server is checked for an exited process.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// First delay before restarting an exited server; doubled on every failed attempt.
+pub const DEFAULT_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+pub const DEFAULT_MAX_RESTART_BACKOFF: Duration = Duration::from_secs(60);
+/// How long a request may wait for its response before it is cancelled.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// JSON-RPC error code for server requests the client does not implement.
+const METHOD_NOT_FOUND: i32 = -32601;
+
+/// Lifecycle of an MCP server process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpState {
+    Running,
+    /// The process exited and is being restarted with backoff.
+    Restarting,
+    /// Shut down (unregistered or replaced).
+    Stopped,
+    /// Never started; the server is retried on the next reload.
+    Failed,
+}
+
+/// Health of an MCP server, as shown by the admin API.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct McpStatus {
+    pub state: McpState,
+    /// Restarts after the process exited.
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
+
+impl McpStatus {
+    pub fn failed(error: impl std::fmt::Display) -> Self {
+        Self {
+            state: McpState::Failed,
+            restarts: 0,
+            last_error: Some(error.to_string()),
+        }
+    }
+}
+
+/// What the server reported when it was initialized.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ServerInfo {
+    pub protocol_version: String,
+    /// Capabilities by name (`tools`, `resources`, `prompts`, `logging`) with their options.
+    pub capabilities: serde_json::Map<String, Value>,
+    pub server_info: Option<Value>,
+    pub instructions: Option<String>,
+}
+
+impl ServerInfo {
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.contains_key(capability)
+    }
+}
+
+/// A notification sent by the server, e.g. `notifications/progress`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct McpNotification {
+    pub method: String,
+    pub params: Option<Value>,
+}
+
+/// Where the server lives and how to reach it.
+enum Endpoint {
+    Stdio {
+        command: String,
+        args: Vec<String>,
+        env: Option<HashMap<String, String>>,
+    },
+    Http {
+        url: String,
+        headers: HashMap<String, String>,
+        transport: RemoteTransport,
+    },
+}
+
+impl Endpoint {
+    async fn open(&self, inbox: Inbox) -> Result<Box<dyn Transport>> {
+        Ok(match self {
+            Endpoint::Stdio { command, args, env } => {
+                Box::new(StdioTransport::spawn(command, args, env.as_ref(), inbox)?)
+            }
+            Endpoint::Http {
+                url,
+                headers,
+                transport: RemoteTransport::StreamableHttp,
+            } => Box::new(StreamableHttpTransport::new(url, headers, inbox)?),
+            Endpoint::Http {
+                url,
+                headers,
+                transport: RemoteTransport::Sse,
+            } => Box::new(SseTransport::connect(url, headers, inbox).await?),
+        })
+    }
+
+    /// The command or URL, for log and error messages.
+    fn name(&self) -> &str {
+        match self {
+            Endpoint::Stdio { command, .. } => command,
+            Endpoint::Http { url, .. } => url,
+        }
+    }
+}
+
+type Pending = Arc<std::sync::Mutex<HashMap<u64, oneshot::Sender<JsonRpcResponse>>>>;
+
+/// An open transport and the task dispatching what the server sends over it.
+struct Connection {
+    transport: Arc<dyn Transport>,
+    // Requests waiting for their response, by id
+    pending: Pending,
+    // Why the connection closed, once it has
+    closed: Arc<std::sync::Mutex<Option<String>>>,
+    dispatcher: JoinHandle<()>,
+}
+
+impl Connection {
+    async fn open(
+        server: &Endpoint,
+        notifications: broadcast::Sender<McpNotification>,
+        exited: Arc<Notify>,
+    ) -> Result<Self> {
+        let (inbox, mut incoming) = mpsc::unbounded_channel();
+        let transport: Arc<dyn Transport> = server.open(inbox).await?.into();
+        let pending = Pending::default();
+        let closed = Arc::new(std::sync::Mutex::new(None));
+
+        let dispatcher = {
+            let (transport, pending, closed) = (transport.clone(), pending.clone(), closed.clone());
+            tokio::spawn(async move {
+                while let Some(incoming) = incoming.recv().await {
+                    match incoming {
+                        Incoming::Message(message) => {
+                            dispatch(message, &transport, &pending, &notifications)
+                        }
+                        Incoming::Closed(reason) => {
+                            *lock(&closed) = Some(reason);
+                            // Waiting requests fail at once instead of timing out.
+                            lock(&pending).clear();
+                            exited.notify_one();
+                            break;
+                        }
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            transport,
+            pending,
+            closed,
+            dispatcher,
+        })
+    }
+
+    /// Why the server is gone, if it is.
+    async fn down(&self) -> Option<String> {
+        let closed = lock(&self.closed).clone();
+        match closed {
+            Some(reason) => Some(reason),
+            None => self.transport.exited().await,
+        }
+    }
+
+    async fn close(&self) {
+        self.dispatcher.abort();
+        self.transport.close().await;
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct JsonRpcResponse {
-    jsonrpc: String,
-    id: Option<u64>,
-    result: Option<Value>,
-    error: Option<JsonRpcError>,
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // The dispatcher holds the transport; without it a spawned server is killed on drop.
+        self.dispatcher.abort();
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct JsonRpcError {
-    code: i32,
-    message: String,
+fn lock<T>(mutex: &std::sync::Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
 }
 
+/// Route a message from the server: responses to their waiting request,
+/// notifications to subscribers, and server requests to an answer.
+fn dispatch(
+    message: Value,
+    transport: &Arc<dyn Transport>,
+    pending: &Pending,
+    notifications: &broadcast::Sender<McpNotification>,
+) {
+    let method = message.get("method").and_then(Value::as_str);
+    match (method, message.get("id")) {
+        (Some(method), Some(id)) => {
+            let reply = match method {
+                "ping" => json!({ "jsonrpc": "2.0", "id": id, "result": {} }),
+                _ => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": METHOD_NOT_FOUND, "message": format!("Method not found: {}", method) }
+                }),
+            };
+            let transport = transport.clone();
+            tokio::spawn(async move {
+                let _ = transport.send(&reply).await;
+            });
+        }
+        (Some(method), None) => {
+            // Nobody listening is fine.
+            let _ = notifications.send(McpNotification {
+                method: method.to_string(),
+                params: message.get("params").cloned(),
+            });
+        }
+        (None, Some(_)) => {
+            if let Ok(response) = serde_json::from_value::<JsonRpcResponse>(message)
+                && let Some(id) = response.id
+                && let Some(waiting) = lock(pending).remove(&id)
+            {
+                let _ = waiting.send(response);
+            }
+        }
+        (None, None) => {}
+    }
+}
+
+/// A request in flight. Dropped before its response arrived (timeout, or the caller gave up),
+/// it tells the server with `notifications/cancelled`.
+struct InFlight<'a> {
+    connection: &'a Connection,
+    id: u64,
+    cancel: Option<&'static str>,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        lock(&self.connection.pending).remove(&self.id);
+        let (Some(reason), Ok(runtime)) = (self.cancel, tokio::runtime::Handle::try_current())
+        else {
+            return;
+        };
+        let transport = self.connection.transport.clone();
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": { "requestId": self.id, "reason": reason }
+        });
+        runtime.spawn(async move {
+            let _ = transport.send(&notification).await;
+        });
+    }
+}
+
+/// Client of an MCP server, spawned as a child process (stdio) or reached over HTTP
+/// (Streamable HTTP or legacy SSE). Requests are multiplexed: any number may be in flight
+/// at once, each answered through its id. Once [`supervise`](McpClient::supervise)d, a server
+/// that exits or drops the connection is reconnected with exponential backoff.
 pub struct McpClient {
-    _child: Child,
-    reader: Mutex<BufReader<tokio::process::ChildStdout>>,
-    writer: Mutex<tokio::process::ChildStdin>,
-    next_id: Mutex<u64>,
+    server: Endpoint,
+    // None while the server is down
+    connection: Mutex<Option<Arc<Connection>>>,
+    next_id: AtomicU64,
+    status: std::sync::Mutex<McpStatus>,
+    stopped: AtomicBool,
+    exited: Arc<Notify>,
+    backoff: (Duration, Duration),
+    timeout: Duration,
+    notifications: broadcast::Sender<McpNotification>,
+    // From the last initialize handshake
+    server_info: std::sync::Mutex<Option<ServerInfo>>,
 }
 
 impl McpClient {
@@ -43,94 +294,399 @@ impl McpClient {
         args: &[&str],
         env: Option<HashMap<String, String>>,
     ) -> Result<Self> {
-        let mut cmd = Command::new(command);
-        cmd.args(args)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::inherit());
+        Self::open(Endpoint::Stdio {
+            command: command.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            env,
+        })
+        .await
+    }
 
-        if let Some(vars) = env {
-            cmd.envs(vars);
-        }
+    /// Connect to a remote server at `url`, sending `headers` with every request.
+    pub async fn connect(
+        url: &str,
+        headers: HashMap<String, String>,
+        transport: RemoteTransport,
+    ) -> Result<Self> {
+        Self::open(Endpoint::Http {
+            url: url.to_string(),
+            headers,
+            transport,
+        })
+        .await
+    }
 
-        let mut child = cmd.spawn().context(format!(
-            "Failed to spawn MCP server: {} {:?}",
-            command, args
-        ))?;
+    /// Spawn or connect to the server described in `mcp_settings.json`.
+    pub async fn from_config(config: &McpServerConfig) -> Result<Self> {
+        match (&config.command, &config.url) {
+            (Some(command), _) => {
+                let args: Vec<&str> = config.args.iter().map(String::as_str).collect();
+                Self::new(command, &args, config.env.clone()).await
+            }
+            (None, Some(url)) => Self::connect(url, config.headers.clone(), config.transport).await,
+            (None, None) => bail!("MCP server config has neither a command nor a url"),
+        }
+    }
 
-        let stdin = child.stdin.take().context("Failed to open stdin")?;
-        let stdout = child.stdout.take().context("Failed to open stdout")?;
+    async fn open(server: Endpoint) -> Result<Self> {
+        let exited = Arc::new(Notify::new());
+        let (notifications, _) = broadcast::channel(64);
+        let connection = Connection::open(&server, notifications.clone(), exited.clone()).await?;
 
         Ok(Self {
-            _child: child,
-            reader: Mutex::new(BufReader::new(stdout)),
-            writer: Mutex::new(stdin),
-            next_id: Mutex::new(1),
+            server,
+            connection: Mutex::new(Some(Arc::new(connection))),
+            next_id: AtomicU64::new(1),
+            status: std::sync::Mutex::new(McpStatus {
+                state: McpState::Running,
+                restarts: 0,
+                last_error: None,
+            }),
+            stopped: AtomicBool::new(false),
+            exited,
+            backoff: (DEFAULT_RESTART_BACKOFF, DEFAULT_MAX_RESTART_BACKOFF),
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+            notifications,
+            server_info: std::sync::Mutex::new(None),
         })
     }
 
+    /// Delay before the first restart attempt and the cap it doubles up to.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = (initial, max);
+        self
+    }
+
+    /// How long a request waits for its response before it is cancelled.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Notifications sent by the server from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<McpNotification> {
+        self.notifications.subscribe()
+    }
+
+    /// The server's version and capabilities, once initialized.
+    pub fn server_info(&self) -> Option<ServerInfo> {
+        lock(&self.server_info).clone()
+    }
+
+    /// Whether the initialized server declared `capability`.
+    pub fn supports(&self, capability: &str) -> bool {
+        lock(&self.server_info)
+            .as_ref()
+            .is_some_and(|info| info.supports(capability))
+    }
+
+    pub fn status(&self) -> McpStatus {
+        lock(&self.status).clone()
+    }
+
+    fn update_status(&self, update: impl FnOnce(&mut McpStatus)) {
+        update(&mut lock(&self.status));
+    }
+
+    /// Process id of the running server (stdio only).
+    pub async fn pid(&self) -> Option<u32> {
+        self.connection.lock().await.as_ref()?.transport.pid()
+    }
+
+    /// Restart the server whenever it exits, until [`shutdown`](McpClient::shutdown) or the client is dropped.
+    pub fn supervise(self: &Arc<Self>) {
+        let client = Arc::downgrade(self);
+        let exited = self.exited.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = exited.notified() => {}
+                    _ = tokio::time::sleep(HEALTH_CHECK_INTERVAL) => {}
+                }
+                let Some(alive) = Self::check(&client).await else {
+                    break;
+                };
+                if !alive && !Self::restart(&client).await {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Whether the server is up (`None` once the client is gone or stopped).
+    async fn check(client: &Weak<Self>) -> Option<bool> {
+        let client = client.upgrade()?;
+        if client.stopped.load(Ordering::SeqCst) {
+            return None;
+        }
+        let mut connection = client.connection.lock().await;
+        let exit = match connection.as_ref() {
+            Some(c) => match c.down().await {
+                None => return Some(true),
+                Some(exit) => exit,
+            },
+            None => return Some(false),
+        };
+        if let Some(c) = connection.take() {
+            c.close().await;
+        }
+        client.update_status(|s| {
+            s.state = McpState::Restarting;
+            s.last_error = Some(exit);
+        });
+        Some(false)
+    }
+
+    /// Restart with exponential backoff until it succeeds; false if the client went away meanwhile.
+    async fn restart(client: &Weak<Self>) -> bool {
+        let Some((mut delay, max)) = client.upgrade().map(|c| c.backoff) else {
+            return false;
+        };
+        loop {
+            tokio::time::sleep(delay).await;
+            let Some(client) = client.upgrade() else {
+                return false;
+            };
+            if client.stopped.load(Ordering::SeqCst) {
+                return false;
+            }
+            match client.reconnect().await {
+                Ok(()) => {
+                    println!("🔌 MCP server '{}' restarted", client.server.name());
+                    client.update_status(|s| {
+                        s.state = McpState::Running;
+                        s.restarts += 1;
+                    });
+                    return true;
+                }
+                Err(e) => {
+                    eprintln!(
+                        "MCP server '{}' failed to restart: {}",
+                        client.server.name(),
+                        e
+                    );
+                    client.update_status(|s| s.last_error = Some(e.to_string()));
+                    delay = (delay * 2).min(max);
+                }
+            }
+        }
+    }
+
+    async fn reconnect(&self) -> Result<()> {
+        let mut connection = self.connection.lock().await;
+        if let Some(old) = connection.take() {
+            old.close().await;
+        }
+        let fresh = Connection::open(
+            &self.server,
+            self.notifications.clone(),
+            self.exited.clone(),
+        )
+        .await?;
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let handshake = match self
+            .exchange(&fresh, id, "initialize", Some(initialize_params()))
+            .await
+        {
+            Ok(result) => fresh.transport.send(&initialized()).await.map(|_| result),
+            Err(e) => Err(e),
+        };
+        match handshake {
+            Ok(result) => {
+                *lock(&self.server_info) = serde_json::from_value(result).ok();
+                *connection = Some(Arc::new(fresh));
+                Ok(())
+            }
+            Err(e) => {
+                fresh.close().await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Stop supervising and close the connection; a spawned server is killed and reaped.
+    pub async fn shutdown(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(connection) = self.connection.lock().await.take() {
+            connection.close().await;
+        }
+        self.update_status(|s| s.state = McpState::Stopped);
+        self.exited.notify_one();
+    }
+
     async fn send_request(&self, method: &str, params: Option<Value>) -> Result<Value> {
-        let id = {
-            let mut lock = self.next_id.lock().await;
-            let id = *lock;
-            *lock += 1;
-            id
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        // Held only to pick the connection, so requests run concurrently.
+        let Some(connection) = self.connection.lock().await.clone() else {
+            return Err(match self.status().state {
+                McpState::Stopped => anyhow!("MCP server '{}' is stopped", self.server.name()),
+                _ => anyhow!("MCP server '{}' is restarting", self.server.name()),
+            });
+        };
+
+        match self.exchange(&connection, id, method, params).await {
+            Err(e) if e.downcast_ref::<Disconnected>().is_some() => {
+                // The server is gone: let the supervisor bring it back, unless it already has.
+                let mut current = self.connection.lock().await;
+                if current
+                    .as_ref()
+                    .is_some_and(|c| Arc::ptr_eq(c, &connection))
+                {
+                    current.take();
+                    connection.close().await;
+                    self.update_status(|s| {
+                        s.state = McpState::Restarting;
+                        s.last_error = Some(e.to_string());
+                    });
+                    self.exited.notify_one();
+                }
+                Err(e)
+            }
+            result => result,
+        }
+    }
+
+    async fn exchange(
+        &self,
+        connection: &Connection,
+        id: u64,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Value> {
+        let (tx, rx) = oneshot::channel();
+        lock(&connection.pending).insert(id, tx);
+        let mut request = InFlight {
+            connection,
+            id,
+            // initialize must not be cancelled
+            cancel: (method != "initialize").then_some("Request cancelled by the client"),
         };
+        // Closed before we registered: the dispatcher will never answer.
+        if let Some(reason) = lock(&connection.closed).clone() {
+            request.cancel = None;
+            return Err(Disconnected(reason).into());
+        }
 
-        let req = JsonRpcRequest {
+        let message = serde_json::to_value(JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id,
             method: method.to_string(),
             params,
+        })?;
+        // One deadline for both: a stalled transport (full pipe, unresponsive HTTP server)
+        // blocks the send as surely as a missing answer.
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        match tokio::time::timeout_at(deadline, connection.transport.send(&message)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                request.cancel = None;
+                return Err(e);
+            }
+            Err(_) => return Err(self.timed_out(&mut request, method)),
+        }
+
+        let resp = match tokio::time::timeout_at(deadline, rx).await {
+            Ok(Ok(resp)) => resp,
+            Ok(Err(_)) => {
+                request.cancel = None;
+                let reason = lock(&connection.closed).clone();
+                return Err(Disconnected(
+                    reason.unwrap_or_else(|| "MCP server connection closed".to_string()),
+                )
+                .into());
+            }
+            Err(_) => return Err(self.timed_out(&mut request, method)),
         };
+        request.cancel = None;
 
-        let mut json = serde_json::to_string(&req)?;
-        json.push('\n');
+        if let Some(err) = resp.error {
+            bail!("MCP Error {}: {}", err.code, err.message);
+        }
+        Ok(resp.result.unwrap_or(Value::Null))
+    }
 
-        {
-            let mut writer = self.writer.lock().await;
-            writer.write_all(json.as_bytes()).await?;
-            writer.flush().await?;
+    fn timed_out(&self, request: &mut InFlight<'_>, method: &str) -> anyhow::Error {
+        if request.cancel.is_some() {
+            request.cancel = Some("Request timed out");
         }
+        anyhow!(
+            "MCP request '{}' timed out after {:?}",
+            method,
+            self.timeout
+        )
+    }
 
-        {
-            let mut reader = self.reader.lock().await;
-            let mut line = String::new();
+    /// Negotiate with the server, then tell it the client is ready (`notifications/initialized`).
+    pub async fn initialize(&self) -> Result<ServerInfo> {
+        let res = self
+            .send_request("initialize", Some(initialize_params()))
+            .await?;
+        let info: ServerInfo = serde_json::from_value(res)?;
+        *lock(&self.server_info) = Some(info.clone());
+        self.notify(&initialized()).await?;
+        Ok(info)
+    }
 
-            loop {
-                line.clear();
-                let bytes = reader.read_line(&mut line).await?;
-                if bytes == 0 {
-                    bail!("MCP Server closed connection unexpectedly");
-                }
+    async fn notify(&self, notification: &Value) -> Result<()> {
+        let connection = self.connection.lock().await.clone();
+        match connection {
+            Some(connection) => connection.transport.send(notification).await,
+            None => bail!("MCP server '{}' is not connected", self.server.name()),
+        }
+    }
 
-                if let Ok(resp) = serde_json::from_str::<JsonRpcResponse>(&line) {
-                    if resp.id == Some(id) {
-                        if let Some(err) = resp.error {
-                            bail!("MCP Error {}: {}", err.code, err.message);
-                        }
-                        return Ok(resp.result.unwrap_or(Value::Null));
-                    } else {
-                        continue;
-                    }
-                }
+    /// Every page of a list method, following `nextCursor`.
+    async fn list_all(&self, method: &str, key: &str) -> Result<Vec<Value>> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let res = self.send_request(method, Some(params)).await?;
+            if let Some(page) = res.get(key).and_then(|t| t.as_array()) {
+                items.extend(page.iter().cloned());
+            }
+            match res.get("nextCursor").and_then(|c| c.as_str()) {
+                // A server repeating its cursor would loop forever.
+                Some(next) if cursor.as_deref() != Some(next) => cursor = Some(next.to_string()),
+                _ => return Ok(items),
             }
         }
     }
 
-    pub async fn initialize(&self) -> Result<()> {
-        let _res = self.send_request("initialize", Some(json!({ "protocolVersion": "2024-11-05", "clientInfo": { "name": "sensei-client", "version": "0.1.0" }, "capabilities": {} }))).await?;
-        Ok(())
+    pub async fn list_tools(&self) -> Result<Vec<Value>> {
+        self.list_all("tools/list", "tools").await
     }
 
-    pub async fn list_tools(&self) -> Result<Vec<Value>> {
-        let res = self.send_request("tools/list", Some(json!({}))).await?;
+    pub async fn list_resources(&self) -> Result<Vec<Value>> {
+        self.list_all("resources/list", "resources").await
+    }
 
-        if let Some(tools) = res.get("tools").and_then(|t| t.as_array()) {
-            Ok(tools.clone())
-        } else {
-            Ok(vec![])
-        }
+    /// The contents of a resource: entries with a `uri` and either `text` or a base64 `blob`.
+    pub async fn read_resource(&self, uri: &str) -> Result<Vec<Value>> {
+        let res = self
+            .send_request("resources/read", Some(json!({ "uri": uri })))
+            .await?;
+        Ok(res
+            .get("contents")
+            .and_then(|c| c.as_array())
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    pub async fn list_prompts(&self) -> Result<Vec<Value>> {
+        self.list_all("prompts/list", "prompts").await
+    }
+
+    /// A prompt rendered with `arguments`: its `description` and `messages`.
+    pub async fn get_prompt(&self, name: &str, arguments: Value) -> Result<Value> {
+        self.send_request(
+            "prompts/get",
+            Some(json!({ "name": name, "arguments": arguments })),
+        )
+        .await
     }
 
     pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<String> {
@@ -155,6 +711,14 @@ impl McpClient {
     }
 }
 
+fn initialized() -> Value {
+    json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })
+}
+
+fn initialize_params() -> Value {
+    json!({ "protocolVersion": "2024-11-05", "clientInfo": { "name": "sensei-client", "version": "0.1.0" }, "capabilities": {} })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,483 @@
+use anyhow::{Context, Result, bail};
+use async_trait::async_trait;
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use reqwest::header::{ACCEPT, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{Mutex, mpsc};
+use tokio::task::JoinHandle;
+
+/// Session header of the Streamable HTTP transport.
+const SESSION_HEADER: &str = "mcp-session-id";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct JsonRpcRequest {
+    pub jsonrpc: String,
+    pub id: u64,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct JsonRpcResponse {
+    pub jsonrpc: String,
+    pub id: Option<u64>,
+    pub result: Option<Value>,
+    pub error: Option<JsonRpcError>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct JsonRpcError {
+    pub code: i32,
+    pub message: String,
+}
+
+/// The connection itself failed (process exited, stream closed, server unreachable),
+/// as opposed to an error answered by the server. Clients reconnect on it.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct Disconnected(pub String);
+
+/// What a transport delivers to its client.
+pub(crate) enum Incoming {
+    /// A response, notification or request from the server.
+    Message(Value),
+    /// The connection is gone; nothing more will arrive.
+    Closed(String),
+}
+
+pub(crate) type Inbox = mpsc::UnboundedSender<Incoming>;
+
+/// Carries JSON-RPC messages to and from one MCP server. Messages from the server,
+/// including responses, are delivered to the inbox the transport was opened with.
+#[async_trait]
+pub(crate) trait Transport: Send + Sync {
+    /// Send one message (request, response or notification).
+    async fn send(&self, message: &Value) -> Result<()>;
+
+    /// Why the server is gone, when that can be known without a message (a process that exited).
+    async fn exited(&self) -> Option<String> {
+        None
+    }
+
+    fn pid(&self) -> Option<u32> {
+        None
+    }
+
+    /// Release the connection; a spawned server is killed and reaped.
+    async fn close(&self) {}
+}
+
+// --- stdio: newline-delimited JSON-RPC with a child process ---
+
+pub(crate) struct StdioTransport {
+    child: Mutex<Child>,
+    pid: Option<u32>,
+    writer: Mutex<ChildStdin>,
+    reader: JoinHandle<()>,
+}
+
+impl StdioTransport {
+    pub fn spawn(
+        command: &str,
+        args: &[String],
+        env: Option<&HashMap<String, String>>,
+        inbox: Inbox,
+    ) -> Result<Self> {
+        let mut cmd = Command::new(command);
+        cmd.args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true);
+
+        if let Some(vars) = env {
+            cmd.envs(vars);
+        }
+
+        let mut child = cmd.spawn().context(format!(
+            "Failed to spawn MCP server: {} {:?}",
+            command, args
+        ))?;
+
+        let stdin = child.stdin.take().context("Failed to open stdin")?;
+        let stdout = child.stdout.take().context("Failed to open stdout")?;
+
+        let reader = tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            let closed = loop {
+                match lines.next_line().await {
+                    // Anything that is not JSON-RPC (stray logging) is skipped.
+                    Ok(Some(line)) => {
+                        if let Ok(message) = serde_json::from_str(&line)
+                            && inbox.send(Incoming::Message(message)).is_err()
+                        {
+                            return;
+                        }
+                    }
+                    Ok(None) => break "MCP Server closed connection unexpectedly".to_string(),
+                    Err(e) => break io_error(e).0,
+                }
+            };
+            let _ = inbox.send(Incoming::Closed(closed));
+        });
+
+        Ok(Self {
+            pid: child.id(),
+            child: Mutex::new(child),
+            writer: Mutex::new(stdin),
+            reader,
+        })
+    }
+}
+
+fn io_error(e: std::io::Error) -> Disconnected {
+    Disconnected(format!("MCP server connection failed: {}", e))
+}
+
+#[async_trait]
+impl Transport for StdioTransport {
+    async fn send(&self, message: &Value) -> Result<()> {
+        let mut json = serde_json::to_string(message)?;
+        json.push('\n');
+
+        let mut writer = self.writer.lock().await;
+        writer.write_all(json.as_bytes()).await.map_err(io_error)?;
+        writer.flush().await.map_err(io_error)?;
+        Ok(())
+    }
+
+    async fn exited(&self) -> Option<String> {
+        match self.child.lock().await.try_wait() {
+            Ok(None) => None,
+            Ok(Some(status)) => Some(format!("MCP server exited ({})", status)),
+            Err(e) => Some(format!("MCP server is unreachable: {}", e)),
+        }
+    }
+
+    fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    async fn close(&self) {
+        self.reader.abort();
+        let mut child = self.child.lock().await;
+        let _ = child.start_kill();
+        let _ = child.wait().await;
+    }
+}
+
+// --- Server-Sent Events parsing shared by the HTTP transports ---
+
+struct SseEvent {
+    event: String,
+    data: String,
+}
+
+struct SseStream {
+    body: BoxStream<'static, reqwest::Result<Vec<u8>>>,
+    buffer: String,
+}
+
+impl SseStream {
+    fn new(response: reqwest::Response) -> Self {
+        Self {
+            body: response
+                .bytes_stream()
+                .map(|chunk| chunk.map(|bytes| bytes.to_vec()))
+                .boxed(),
+            buffer: String::new(),
+        }
+    }
+
+    /// The next event, or `None` when the server closed the stream.
+    async fn next_event(&mut self) -> Result<Option<SseEvent>> {
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let block: String = self.buffer.drain(..end + 2).collect();
+                if let Some(event) = parse_event(&block) {
+                    return Ok(Some(event));
+                }
+                continue;
+            }
+            match self.body.next().await {
+                Some(Ok(chunk)) => self
+                    .buffer
+                    .push_str(&String::from_utf8_lossy(&chunk).replace("\r\n", "\n")),
+                Some(Err(e)) => {
+                    return Err(Disconnected(format!("MCP event stream failed: {}", e)).into());
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Deliver every `message` event to `inbox` until the stream ends, and return why it ended.
+    async fn forward(mut self, inbox: &Inbox) -> String {
+        loop {
+            match self.next_event().await {
+                Ok(Some(event)) => {
+                    if event.event != "message" {
+                        continue;
+                    }
+                    if let Ok(message) = serde_json::from_str(&event.data)
+                        && inbox.send(Incoming::Message(message)).is_err()
+                    {
+                        return "MCP client is gone".to_string();
+                    }
+                }
+                Ok(None) => return "MCP event stream closed".to_string(),
+                Err(e) => return e.to_string(),
+            }
+        }
+    }
+}
+
+fn parse_event(block: &str) -> Option<SseEvent> {
+    let mut event = "message".to_string();
+    let mut data: Vec<&str> = Vec::new();
+    for line in block.lines() {
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event = value.to_string(),
+            "data" => data.push(value),
+            _ => {} // comments (keep-alives), id and retry
+        }
+    }
+    (!data.is_empty()).then(|| SseEvent {
+        event,
+        data: data.join("\n"),
+    })
+}
+
+fn header_map(headers: &HashMap<String, String>) -> Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        map.insert(
+            HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("Invalid header name '{}'", name))?,
+            HeaderValue::from_str(value)
+                .with_context(|| format!("Invalid value for header '{}'", name))?,
+        );
+    }
+    Ok(map)
+}
+
+fn unreachable(url: &str, e: reqwest::Error) -> Disconnected {
+    Disconnected(format!("MCP server {} is unreachable: {}", url, e))
+}
+
+async fn http_error(response: reqwest::Response) -> anyhow::Error {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    anyhow::anyhow!("MCP server answered HTTP {}: {}", status, body)
+}
+
+// --- Streamable HTTP (MCP 2025-03-26): one POST per message, answered with JSON or an event stream ---
+
+pub(crate) struct StreamableHttpTransport {
+    http: reqwest::Client,
+    url: String,
+    headers: HeaderMap,
+    session: std::sync::Mutex<Option<String>>,
+    inbox: Inbox,
+}
+
+impl StreamableHttpTransport {
+    pub fn new(url: &str, headers: &HashMap<String, String>, inbox: Inbox) -> Result<Self> {
+        Url::parse(url).with_context(|| format!("Invalid MCP server URL '{}'", url))?;
+        Ok(Self {
+            http: reqwest::Client::new(),
+            url: url.to_string(),
+            headers: header_map(headers)?,
+            session: std::sync::Mutex::new(None),
+            inbox,
+        })
+    }
+
+    fn session(&self) -> Option<String> {
+        self.session
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+#[async_trait]
+impl Transport for StreamableHttpTransport {
+    async fn send(&self, message: &Value) -> Result<()> {
+        let session = self.session();
+        let mut post = self
+            .http
+            .post(&self.url)
+            .headers(self.headers.clone())
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(message);
+        if let Some(session) = &session {
+            post = post.header(SESSION_HEADER, session);
+        }
+        let response = post.send().await.map_err(|e| unreachable(&self.url, e))?;
+
+        // The server ends sessions with 404; a new one starts with the next initialize.
+        if response.status() == StatusCode::NOT_FOUND && session.is_some() {
+            return Err(Disconnected("MCP session expired".to_string()).into());
+        }
+        if !response.status().is_success() {
+            return Err(http_error(response).await);
+        }
+        if let Some(session) = response
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            *self.session.lock().unwrap_or_else(|e| e.into_inner()) = Some(session.to_string());
+        }
+
+        let is_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        if is_stream {
+            // The server may send requests and notifications before the response.
+            let events = SseStream::new(response);
+            let inbox = self.inbox.clone();
+            tokio::spawn(async move { events.forward(&inbox).await });
+            return Ok(());
+        }
+
+        // Notifications and responses are acknowledged with an empty 202.
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| unreachable(&self.url, e))?;
+        if body.is_empty() {
+            return Ok(());
+        }
+        let messages = match serde_json::from_slice(&body).context("Invalid JSON-RPC response")? {
+            Value::Array(batch) => batch,
+            message => vec![message],
+        };
+        for message in messages {
+            let _ = self.inbox.send(Incoming::Message(message));
+        }
+        Ok(())
+    }
+
+    async fn close(&self) {
+        let session = self
+            .session
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        if let Some(session) = session {
+            let _ = self
+                .http
+                .delete(&self.url)
+                .headers(self.headers.clone())
+                .header(SESSION_HEADER, session)
+                .send()
+                .await;
+        }
+    }
+}
+
+// --- Legacy HTTP+SSE (MCP 2024-11-05): messages come back on a long-lived event stream ---
+
+pub(crate) struct SseTransport {
+    http: reqwest::Client,
+    endpoint: Url,
+    headers: HeaderMap,
+    reader: JoinHandle<()>,
+}
+
+impl SseTransport {
+    /// Open the event stream and wait for the endpoint messages are posted to.
+    pub async fn connect(
+        url: &str,
+        headers: &HashMap<String, String>,
+        inbox: Inbox,
+    ) -> Result<Self> {
+        let base = Url::parse(url).with_context(|| format!("Invalid MCP server URL '{}'", url))?;
+        let headers = header_map(headers)?;
+        let http = reqwest::Client::new();
+
+        let response = http
+            .get(base.clone())
+            .headers(headers.clone())
+            .header(ACCEPT, "text/event-stream")
+            .send()
+            .await
+            .map_err(|e| unreachable(url, e))?;
+        if !response.status().is_success() {
+            return Err(http_error(response).await);
+        }
+
+        let mut events = SseStream::new(response);
+        while let Some(event) = events.next_event().await? {
+            if event.event == "endpoint" {
+                let endpoint = base
+                    .join(event.data.trim())
+                    .context("Invalid MCP message endpoint")?;
+                // Messages carry the configured headers (API keys): never post them elsewhere
+                if endpoint.origin() != base.origin() {
+                    bail!(
+                        "MCP server {} announced a message endpoint on another origin: {}",
+                        url,
+                        endpoint
+                    );
+                }
+                let reader = tokio::spawn(async move {
+                    let closed = events.forward(&inbox).await;
+                    let _ = inbox.send(Incoming::Closed(closed));
+                });
+                return Ok(Self {
+                    http,
+                    endpoint,
+                    headers,
+                    reader,
+                });
+            }
+        }
+        bail!(
+            "MCP server {} closed the event stream before sending its endpoint",
+            url
+        )
+    }
+}
+
+#[async_trait]
+impl Transport for SseTransport {
+    async fn send(&self, message: &Value) -> Result<()> {
+        let response = self
+            .http
+            .post(self.endpoint.clone())
+            .headers(self.headers.clone())
+            .json(message)
+            .send()
+            .await
+            .map_err(|e| unreachable(self.endpoint.as_str(), e))?;
+        if !response.status().is_success() {
+            return Err(http_error(response).await);
+        }
+        Ok(())
+    }
+
+    async fn close(&self) {
+        self.reader.abort();
+    }
+}
+
+impl Drop for SseTransport {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
@@ -1,6 +1,9 @@
+use crate::auth::{self, ApiKey, Scope};
 use crate::errors::SenseiError;
+use crate::llm::EmbeddingConfig;
 use chrono::NaiveDateTime;
 use libsqlite3_sys::sqlite3_auto_extension;
+use sensei_common::Classification;
 use serde::{Deserialize, Serialize};
 use sqlite_vec::sqlite3_vec_init;
 use sqlx::sqlite::SqlitePool;
@@ -10,7 +13,10 @@ use uuid::Uuid;
 pub struct Session {
     pub id: String,
     pub title: Option<String>,
+    /// Caller that opened the session (see `auth::Caller`).
+    pub owner: Option<String>,
     pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,11 +26,196 @@ pub struct Message {
     pub role: String,
     pub content: String,
     pub created_at: NaiveDateTime,
+    /// Who sent the request that produced the message, when known.
+    pub caller: Option<String>,
+    /// Highest classification of what the message was built from.
+    #[serde(default)]
+    pub classification: Classification,
+}
+
+/// A `messages` row, before its classification is parsed.
+struct MessageRow {
+    id: String,
+    session_id: String,
+    role: String,
+    content: String,
+    created_at: NaiveDateTime,
+    caller: Option<String>,
+    classification: String,
+}
+
+impl From<MessageRow> for Message {
+    fn from(row: MessageRow) -> Self {
+        Self {
+            id: row.id,
+            session_id: row.session_id,
+            role: row.role,
+            content: row.content,
+            created_at: row.created_at,
+            caller: row.caller,
+            // Unknown labels are treated as the most restrictive one.
+            classification: row
+                .classification
+                .parse()
+                .unwrap_or(Classification::TopSecret),
+        }
+    }
+}
+
+/// JSON stored in `documents.metadata`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocumentMetadata {
+    #[serde(default)]
+    pub classification: Classification,
+    /// Where the document came from (e.g. a file path), shared by all its chunks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_index: Option<usize>,
+    /// SHA-256 of the chunk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// SHA-256 of the whole source, to skip unchanged re-ingests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl DocumentMetadata {
+    /// Documents stored before labels existed have no metadata and are unclassified.
+    /// Metadata that cannot be read, e.g. an unknown label, is treated as the most
+    /// restrictive level.
+    pub fn parse(metadata: Option<String>) -> Self {
+        match metadata {
+            None => Self::default(),
+            Some(m) => serde_json::from_str(&m).unwrap_or_else(|_| Self {
+                classification: Classification::TopSecret,
+                ..Self::default()
+            }),
+        }
+    }
+}
+
+/// A stored document (or chunk of a source).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Document {
+    pub id: i64,
+    pub content: String,
+    pub metadata: DocumentMetadata,
+    pub created_at: NaiveDateTime,
+}
+
+/// A document in listings: metadata and the start of its content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSummary {
+    pub id: i64,
+    pub snippet: String,
+    pub metadata: DocumentMetadata,
+    pub created_at: NaiveDateTime,
+}
+
+/// Restricts document listings. Empty fields match everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DocumentFilter {
+    pub source: Option<String>,
+    pub tag: Option<String>,
+}
+
+/// Characters of content shown in document listings.
+const SNIPPET_CHARS: i64 = 120;
+
+/// A read refused by the "no read up" rule.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccessDenial {
+    /// Who asked, e.g. the agent category.
+    pub subject: String,
+    pub clearance: Classification,
+    pub document_id: i64,
+    pub classification: Classification,
+    pub created_at: NaiveDateTime,
+}
+
+/// Candidates fetched per requested search result, see `search_documents`.
+const SEARCH_CANDIDATE_FACTOR: i64 = 4;
+
+/// Words too common to be useful keyword matches.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "can", "do", "does", "for", "from", "how",
+    "i", "in", "is", "it", "me", "my", "of", "on", "or", "the", "this", "that", "to", "was",
+    "what", "when", "where", "which", "who", "why", "with", "you", "your",
+];
+
+/// Tuning for hybrid (vector + keyword) document search.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchConfig {
+    /// Vector matches further than this are ignored.
+    /// Embeddings are normalized, so 1.0 is a cosine similarity of 0.5.
+    pub max_distance: f32,
+    /// Keyword matches scoring below this share of the BM25 of a term found in a single
+    /// document are ignored, whatever the size of the corpus. Terms found in many documents
+    /// score well below 0.5. Exact matches of identifiers such as CVE IDs or hostnames
+    /// are always kept.
+    pub min_bm25: f64,
+    /// Reciprocal-rank fusion constant: larger values flatten the advantage of top ranks.
+    pub rrf_k: f64,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            max_distance: 1.0,
+            min_bm25: 0.5,
+            rrf_k: 60.0,
+        }
+    }
+}
+
+/// A document returned by `search_documents`, with the scores that ranked it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub id: i64,
+    pub content: String,
+    pub metadata: DocumentMetadata,
+    /// Reciprocal-rank fusion score, higher is better.
+    pub score: f64,
+    /// Vector distance, when the document was a nearest neighbour.
+    pub distance: Option<f32>,
+    /// BM25 relevance (higher is better), when the document matched the keywords.
+    pub bm25: Option<f64>,
+}
+
+/// vec0 tables and the table whose rows they embed.
+const VECTOR_TABLES: [(&str, &str); 2] = [
+    ("vec_items", "documents"),
+    ("vec_router_cache", "router_cache"),
+];
+
+/// How the stored vectors compare to the configured embedding model.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VectorIndexStatus {
+    pub model: String,
+    pub dimension: usize,
+    /// Vector tables created for another dimension, with that dimension.
+    pub resize: Vec<(String, usize)>,
+    /// Documents embedded with another model (or not at all).
+    pub stale_documents: i64,
+    /// Router cache entries embedded with another model.
+    pub stale_router_cache: i64,
+}
+
+impl VectorIndexStatus {
+    /// Whether every vector was made by the configured model.
+    pub fn is_current(&self) -> bool {
+        self.resize.is_empty() && self.stale_documents == 0 && self.stale_router_cache == 0
+    }
 }
 
 #[derive(Clone)]
 pub struct MemoryStore {
     pool: SqlitePool,
+    search: SearchConfig,
+    embedding: EmbeddingConfig,
 }
 
 impl MemoryStore {
@@ -36,7 +227,26 @@ impl MemoryStore {
         }
 
         let pool = SqlitePool::connect(database_url).await?;
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            search: SearchConfig::default(),
+            embedding: EmbeddingConfig::default(),
+        })
+    }
+
+    /// The embedding model stored vectors and queries must come from.
+    pub fn with_embedding(mut self, embedding: EmbeddingConfig) -> Self {
+        self.embedding = embedding;
+        self
+    }
+
+    pub fn embedding(&self) -> &EmbeddingConfig {
+        &self.embedding
+    }
+
+    pub fn with_search_config(mut self, search: SearchConfig) -> Self {
+        self.search = search;
+        self
     }
 
     pub fn get_pool(&self) -> &SqlitePool {
@@ -51,17 +261,67 @@ impl MemoryStore {
     // --- Sessions ---
 
     pub async fn create_session(&self, title: Option<&str>) -> Result<String, SenseiError> {
+        self.create_session_as(title, None).await
+    }
+
+    /// Like `create_session`, recording the caller that owns the session.
+    pub async fn create_session_as(
+        &self,
+        title: Option<&str>,
+        owner: Option<&str>,
+    ) -> Result<String, SenseiError> {
         let id = Uuid::new_v4().to_string();
-        sqlx::query!("INSERT INTO sessions (id, title) VALUES (?, ?)", id, title)
-            .execute(&self.pool)
-            .await?;
+        sqlx::query!(
+            "INSERT INTO sessions (id, title, owner) VALUES (?, ?, ?)",
+            id,
+            title,
+            owner
+        )
+        .execute(&self.pool)
+        .await?;
         Ok(id)
     }
 
     pub async fn list_sessions(&self) -> Result<Vec<Session>, SenseiError> {
         let sessions = sqlx::query_as!(
             Session,
-            r#"SELECT id, title, created_at as "created_at: NaiveDateTime" FROM sessions ORDER BY updated_at DESC"#
+            r#"SELECT id, title, owner, created_at as "created_at: NaiveDateTime", updated_at as "updated_at: NaiveDateTime" FROM sessions ORDER BY updated_at DESC"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(sessions)
+    }
+
+    /// Most recently active sessions first.
+    pub async fn list_sessions_page(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Session>, SenseiError> {
+        let sessions = sqlx::query_as!(
+            Session,
+            r#"SELECT id, title, owner, created_at as "created_at: NaiveDateTime", updated_at as "updated_at: NaiveDateTime" FROM sessions ORDER BY updated_at DESC, rowid DESC LIMIT ? OFFSET ?"#,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(sessions)
+    }
+
+    /// Like `list_sessions_page`, restricted to the sessions of `owner`.
+    pub async fn list_owned_sessions_page(
+        &self,
+        owner: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Session>, SenseiError> {
+        let sessions = sqlx::query_as!(
+            Session,
+            r#"SELECT id, title, owner, created_at as "created_at: NaiveDateTime", updated_at as "updated_at: NaiveDateTime" FROM sessions WHERE owner = ? ORDER BY updated_at DESC, rowid DESC LIMIT ? OFFSET ?"#,
+            owner,
+            limit,
+            offset
         )
         .fetch_all(&self.pool)
         .await?;
@@ -71,7 +331,7 @@ impl MemoryStore {
     pub async fn get_session(&self, id: &str) -> Result<Session, SenseiError> {
         let session = sqlx::query_as!(
             Session,
-            r#"SELECT id, title, created_at as "created_at: NaiveDateTime" FROM sessions WHERE id = ?"#,
+            r#"SELECT id, title, owner, created_at as "created_at: NaiveDateTime", updated_at as "updated_at: NaiveDateTime" FROM sessions WHERE id = ?"#,
             id
         )
         .fetch_one(&self.pool)
@@ -79,6 +339,18 @@ impl MemoryStore {
         Ok(session)
     }
 
+    /// Like `get_session`, but `None` when the session does not exist.
+    pub async fn find_session(&self, id: &str) -> Result<Option<Session>, SenseiError> {
+        let session = sqlx::query_as!(
+            Session,
+            r#"SELECT id, title, owner, created_at as "created_at: NaiveDateTime", updated_at as "updated_at: NaiveDateTime" FROM sessions WHERE id = ?"#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(session)
+    }
+
     pub async fn update_session_title(&self, id: &str, title: &str) -> Result<(), SenseiError> {
         sqlx::query!(
             "UPDATE sessions SET title = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
@@ -104,14 +376,45 @@ impl MemoryStore {
         session_id: &str,
         role: &str,
         content: &str,
+    ) -> Result<String, SenseiError> {
+        self.add_message_as(
+            session_id,
+            role,
+            content,
+            None,
+            Classification::Unclassified,
+        )
+        .await
+    }
+
+    /// Like `add_message`, recording the identity of the caller for audit and the
+    /// classification of the message, which decides which agents it is replayed to.
+    pub async fn add_message_as(
+        &self,
+        session_id: &str,
+        role: &str,
+        content: &str,
+        caller: Option<&str>,
+        classification: Classification,
     ) -> Result<String, SenseiError> {
         let id = Uuid::new_v4().to_string();
+        let classification = classification.as_str();
         sqlx::query!(
-            "INSERT INTO messages (id, session_id, role, content) VALUES (?, ?, ?, ?)",
+            "INSERT INTO messages (id, session_id, role, content, caller, classification) VALUES (?, ?, ?, ?, ?, ?)",
             id,
             session_id,
             role,
-            content
+            content,
+            caller,
+            classification
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Keep session listings ordered by activity.
+        sqlx::query!(
+            "UPDATE sessions SET updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+            session_id
         )
         .execute(&self.pool)
         .await?;
@@ -120,13 +423,175 @@ impl MemoryStore {
 
     pub async fn get_messages(&self, session_id: &str) -> Result<Vec<Message>, SenseiError> {
         let messages = sqlx::query_as!(
-            Message,
-            r#"SELECT id, session_id, role, content, created_at as "created_at: NaiveDateTime" FROM messages WHERE session_id = ? ORDER BY created_at ASC"#,
+            MessageRow,
+            r#"SELECT id, session_id, role, content, created_at as "created_at: NaiveDateTime", caller, classification FROM messages WHERE session_id = ? ORDER BY created_at ASC, rowid ASC"#,
             session_id
         )
         .fetch_all(&self.pool)
         .await?;
-        Ok(messages)
+        Ok(messages.into_iter().map(Message::from).collect())
+    }
+
+    /// A page of messages, oldest first.
+    pub async fn get_messages_page(
+        &self,
+        session_id: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Message>, SenseiError> {
+        let messages = sqlx::query_as!(
+            MessageRow,
+            r#"SELECT id, session_id, role, content, created_at as "created_at: NaiveDateTime", caller, classification FROM messages WHERE session_id = ? ORDER BY created_at ASC, rowid ASC LIMIT ? OFFSET ?"#,
+            session_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(messages.into_iter().map(Message::from).collect())
+    }
+
+    pub async fn count_messages(&self, session_id: &str) -> Result<i64, SenseiError> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!: i64" FROM messages WHERE session_id = ?"#,
+            session_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+
+    // --- API Keys ---
+
+    /// Create a key with `scopes`. Returns the stored key and the plaintext, which is not kept.
+    pub async fn create_api_key(
+        &self,
+        name: &str,
+        scopes: &[Scope],
+    ) -> Result<(ApiKey, String), SenseiError> {
+        self.create_cleared_api_key(name, scopes, Classification::Unclassified)
+            .await
+    }
+
+    /// Like `create_api_key`, for a key that may read documents up to `clearance`.
+    /// Names are never reused, not even those of revoked keys: sessions belong to a name.
+    pub async fn create_cleared_api_key(
+        &self,
+        name: &str,
+        scopes: &[Scope],
+        clearance: Classification,
+    ) -> Result<(ApiKey, String), SenseiError> {
+        let taken = sqlx::query!(r#"SELECT id as "id!" FROM api_keys WHERE name = ?"#, name)
+            .fetch_optional(&self.pool)
+            .await?;
+        if taken.is_some() {
+            return Err(SenseiError::InvalidInput(format!(
+                "An API key named '{}' already exists",
+                name
+            )));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let key = auth::generate_key();
+        let hash = auth::hash_key(&key);
+        let prefix = auth::key_prefix(&key);
+        let scopes = auth::format_scopes(scopes);
+        let clearance = clearance.as_str();
+
+        sqlx::query!(
+            "INSERT INTO api_keys (id, name, key_hash, prefix, scopes, clearance) VALUES (?, ?, ?, ?, ?, ?)",
+            id,
+            name,
+            hash,
+            prefix,
+            scopes,
+            clearance
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let api_key = self
+            .find_api_key(&id)
+            .await?
+            .ok_or_else(|| SenseiError::Unknown("API key vanished after insert".to_string()))?;
+        Ok((api_key, key))
+    }
+
+    pub async fn list_api_keys(&self) -> Result<Vec<ApiKey>, SenseiError> {
+        let rows = sqlx::query!(
+            r#"SELECT id as "id!", name, prefix, scopes, clearance, created_at as "created_at: NaiveDateTime", last_used_at as "last_used_at: NaiveDateTime", revoked_at as "revoked_at: NaiveDateTime" FROM api_keys ORDER BY created_at ASC, rowid ASC"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|r| {
+                Ok(ApiKey {
+                    id: r.id,
+                    name: r.name,
+                    prefix: r.prefix,
+                    scopes: auth::parse_scopes(&r.scopes)?,
+                    clearance: r.clearance.parse().map_err(SenseiError::Config)?,
+                    created_at: r.created_at,
+                    last_used_at: r.last_used_at,
+                    revoked_at: r.revoked_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Find a key by id or by its displayed prefix.
+    pub async fn find_api_key(&self, id_or_prefix: &str) -> Result<Option<ApiKey>, SenseiError> {
+        Ok(self
+            .list_api_keys()
+            .await?
+            .into_iter()
+            .find(|k| k.id == id_or_prefix || k.prefix == id_or_prefix))
+    }
+
+    /// Revoke a key by id or prefix. Returns false when no active key matched.
+    pub async fn revoke_api_key(&self, id_or_prefix: &str) -> Result<bool, SenseiError> {
+        let result = sqlx::query!(
+            "UPDATE api_keys SET revoked_at = CURRENT_TIMESTAMP WHERE (id = ? OR prefix = ?) AND revoked_at IS NULL",
+            id_or_prefix,
+            id_or_prefix
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Resolve a plaintext key to an active API key and record its use.
+    pub async fn verify_api_key(&self, key: &str) -> Result<Option<ApiKey>, SenseiError> {
+        let hash = auth::hash_key(key);
+        let row = sqlx::query!(
+            r#"SELECT id as "id!" FROM api_keys WHERE key_hash = ? AND revoked_at IS NULL"#,
+            hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        sqlx::query!(
+            "UPDATE api_keys SET last_used_at = CURRENT_TIMESTAMP WHERE id = ?",
+            row.id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.find_api_key(&row.id).await
+    }
+
+    pub async fn count_api_keys(&self) -> Result<i64, SenseiError> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!: i64" FROM api_keys WHERE revoked_at IS NULL"#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
     }
 
     // --- RAG / Vectors ---
@@ -136,51 +601,492 @@ impl MemoryStore {
         content: &str,
         embedding: Vec<f32>,
     ) -> Result<(), SenseiError> {
+        self.add_classified_document(content, embedding, Classification::Unclassified)
+            .await
+            .map(|_| ())
+    }
+
+    /// Store a document labelled with `classification` (kept in `documents.metadata`).
+    /// Returns the document id.
+    pub async fn add_classified_document(
+        &self,
+        content: &str,
+        embedding: Vec<f32>,
+        classification: Classification,
+    ) -> Result<i64, SenseiError> {
+        let metadata = DocumentMetadata {
+            classification,
+            ..Default::default()
+        };
+
+        self.check_dimension(&embedding)?;
         let mut tx = self.pool.begin().await?;
+        let id = insert_document(
+            &mut tx,
+            content,
+            &metadata,
+            &embedding,
+            &self.embedding.model,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(id)
+    }
 
+    /// Whether `subject`, cleared for `clearance`, may replace, relabel or delete `source`:
+    /// only when it is cleared for every chunk of it. A refusal is audited like a denied read.
+    pub async fn may_rewrite_source(
+        &self,
+        source: &str,
+        clearance: Classification,
+        subject: &str,
+    ) -> Result<bool, SenseiError> {
         use sqlx::Row;
-        let row = sqlx::query("INSERT INTO documents (content) VALUES (?) RETURNING id")
-            .bind(content)
-            .fetch_one(&mut *tx)
-            .await?;
+        let rows = sqlx::query(
+            "SELECT id, metadata FROM documents WHERE json_extract(metadata, '$.source') = ?",
+        )
+        .bind(source)
+        .fetch_all(&self.pool)
+        .await?;
 
-        let id: i64 = row.get("id");
+        for row in rows {
+            let label = DocumentMetadata::parse(row.get("metadata")).classification;
+            if !clearance.dominates(label) {
+                self.record_access_denial(subject, clearance, row.get("id"), label)
+                    .await?;
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
 
-        let vector_bytes = f32_vec_to_bytes(&embedding);
-        sqlx::query("INSERT INTO vec_items (rowid, embedding) VALUES (?, ?)")
-            .bind(id)
-            .bind(vector_bytes)
-            .execute(&mut *tx)
-            .await?;
+    /// Content hash of the current version of `source`, if it was ingested
+    /// and none of its chunks was edited since.
+    pub async fn source_hash(&self, source: &str) -> Result<Option<String>, SenseiError> {
+        use sqlx::Row;
+        let hashes: Vec<Option<String>> = sqlx::query(
+            "SELECT json_extract(metadata, '$.source_hash') as source_hash FROM documents WHERE json_extract(metadata, '$.source') = ?",
+        )
+        .bind(source)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(|row| row.get("source_hash"))
+        .collect();
+
+        let first = hashes.first().cloned().flatten();
+        Ok(first.filter(|hash| hashes.iter().all(|h| h.as_ref() == Some(hash))))
+    }
+
+    /// Set the classification and tags of every chunk of `source`, without touching content
+    /// or vectors. Returns the number of chunks whose labels changed.
+    pub async fn relabel_source(
+        &self,
+        source: &str,
+        classification: Classification,
+        tags: &[String],
+    ) -> Result<usize, SenseiError> {
+        let tags = serde_json::to_string(tags)
+            .map_err(|e| SenseiError::Unknown(format!("Failed to encode tags: {}", e)))?;
+        let result = sqlx::query(
+            "UPDATE documents SET metadata = json_set(metadata, '$.classification', ?1, '$.tags', json(?2)) \
+             WHERE json_extract(metadata, '$.source') = ?3 \
+             AND (json_extract(metadata, '$.classification') IS NOT ?1 \
+                  OR coalesce(json_extract(metadata, '$.tags'), '[]') IS NOT json(?2))",
+        )
+        .bind(classification.as_str())
+        .bind(tags)
+        .bind(source)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    /// Store the chunks of a document, atomically replacing the chunks of an older version
+    /// of the same `source`. Returns the number of chunks removed.
+    pub async fn replace_source_documents(
+        &self,
+        source: Option<&str>,
+        chunks: Vec<(String, DocumentMetadata, Vec<f32>)>,
+    ) -> Result<usize, SenseiError> {
+        for (_, _, embedding) in &chunks {
+            self.check_dimension(embedding)?;
+        }
+        let mut tx = self.pool.begin().await?;
+
+        let mut replaced = 0;
+        if let Some(source) = source {
+            use sqlx::Row;
+            let ids: Vec<i64> = sqlx::query(
+                "SELECT id FROM documents WHERE json_extract(metadata, '$.source') = ?",
+            )
+            .bind(source)
+            .fetch_all(&mut *tx)
+            .await?
+            .iter()
+            .map(|row| row.get("id"))
+            .collect();
+
+            replaced = delete_documents(&mut tx, &ids).await?;
+        }
+
+        for (content, metadata, embedding) in &chunks {
+            insert_document(&mut tx, content, metadata, embedding, &self.embedding.model).await?;
+        }
 
         tx.commit().await?;
-        Ok(())
+        Ok(replaced)
     }
 
+    /// Hybrid search: sqlite-vec nearest neighbours and FTS5 BM25 keyword matches of `query`,
+    /// merged with reciprocal-rank fusion. Vector matches beyond `SearchConfig::max_distance`
+    /// and weak keyword matches (see `SearchConfig::min_bm25`) are dropped, so a query
+    /// unrelated to every document returns nothing.
+    /// Only vectors of the configured embedding model are matched.
+    ///
+    /// Enforces "no read up": only documents at or below `clearance` are returned.
+    /// Documents that would have ranked in the results but are above `clearance`
+    /// are withheld and recorded in the access audit under `subject`.
     pub async fn search_documents(
         &self,
+        query: &str,
         query_embedding: Vec<f32>,
         limit: i64,
-    ) -> Result<Vec<String>, SenseiError> {
-        let vector_bytes = f32_vec_to_bytes(&query_embedding);
+        clearance: Classification,
+        subject: &str,
+    ) -> Result<Vec<SearchHit>, SenseiError> {
+        use sqlx::Row;
+        self.check_dimension(&query_embedding)?;
+        // Over-fetch so that withheld documents do not starve the results.
+        let candidates = limit * SEARCH_CANDIDATE_FACTOR;
+        let mut hits: Vec<SearchHit> = Vec::new();
 
         let rows = sqlx::query(
             r#"
-            SELECT d.content, v.distance
+            SELECT d.id, d.content, d.metadata, v.distance
             FROM vec_items v
             JOIN documents d ON v.rowid = d.id
-            WHERE v.embedding MATCH ? AND k = ?
+            WHERE v.embedding MATCH ? AND k = ? AND d.embedding_model = ?
             ORDER BY v.distance
             "#,
         )
-        .bind(vector_bytes)
+        .bind(f32_vec_to_bytes(&query_embedding))
+        .bind(candidates)
+        .bind(&self.embedding.model)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let nearest = rows
+            .iter()
+            .filter(|row| row.get::<f32, _>("distance") <= self.search.max_distance);
+        for (rank, row) in nearest.enumerate() {
+            hits.push(SearchHit {
+                id: row.get("id"),
+                content: row.get("content"),
+                metadata: DocumentMetadata::parse(row.get("metadata")),
+                score: self.rrf(rank),
+                distance: Some(row.get("distance")),
+                bm25: None,
+            });
+        }
+
+        if let Some(keywords) = keyword_query(query) {
+            // bm25() is lower for better matches.
+            let rows = sqlx::query(
+                r#"
+                SELECT d.id, d.content, d.metadata, bm25(documents_fts) AS rank
+                FROM documents_fts
+                JOIN documents d ON documents_fts.rowid = d.id
+                WHERE documents_fts MATCH ?
+                ORDER BY rank
+                LIMIT ?
+                "#,
+            )
+            .bind(keywords)
+            .bind(candidates)
+            .fetch_all(&self.pool)
+            .await?;
+
+            let documents = sqlx::query!(r#"SELECT COUNT(*) as "count!: i64" FROM documents"#)
+                .fetch_one(&self.pool)
+                .await?
+                .count;
+            let floor = self.search.min_bm25 * single_document_idf(documents);
+            let identifiers = identifier_terms(query);
+            let relevant = rows.iter().filter(|row| {
+                -row.get::<f64, _>("rank") >= floor
+                    || matches_any_phrase(&row.get::<String, _>("content"), &identifiers)
+            });
+            for (rank, row) in relevant.enumerate() {
+                let id: i64 = row.get("id");
+                let bm25 = -row.get::<f64, _>("rank");
+                match hits.iter_mut().find(|hit| hit.id == id) {
+                    Some(hit) => {
+                        hit.score += self.rrf(rank);
+                        hit.bm25 = Some(bm25);
+                    }
+                    None => hits.push(SearchHit {
+                        id,
+                        content: row.get("content"),
+                        metadata: DocumentMetadata::parse(row.get("metadata")),
+                        score: self.rrf(rank),
+                        distance: None,
+                        bm25: Some(bm25),
+                    }),
+                }
+            }
+        }
+
+        // Stable, so equal scores keep vector order first.
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        let mut results = Vec::new();
+        for hit in hits {
+            if results.len() as i64 >= limit {
+                break;
+            }
+
+            let label = hit.metadata.classification;
+            if clearance.dominates(label) {
+                results.push(hit);
+            } else {
+                self.record_access_denial(subject, clearance, hit.id, label)
+                    .await?;
+            }
+        }
+        Ok(results)
+    }
+
+    /// Reciprocal-rank fusion contribution of a 0-based rank in one result list.
+    fn rrf(&self, rank: usize) -> f64 {
+        1.0 / (self.search.rrf_k + rank as f64 + 1.0)
+    }
+
+    async fn record_access_denial(
+        &self,
+        subject: &str,
+        clearance: Classification,
+        document_id: i64,
+        classification: Classification,
+    ) -> Result<(), SenseiError> {
+        eprintln!(
+            "🛑 MAC: denied {} (cleared {}) read of document {} ({})",
+            subject, clearance, document_id, classification
+        );
+        let (clearance, classification) = (clearance.as_str(), classification.as_str());
+        sqlx::query!(
+            "INSERT INTO access_audit (subject, clearance, document_id, classification) VALUES (?, ?, ?, ?)",
+            subject,
+            clearance,
+            document_id,
+            classification
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Most recent "no read up" denials, newest first.
+    pub async fn list_access_denials(&self, limit: i64) -> Result<Vec<AccessDenial>, SenseiError> {
+        let rows = sqlx::query!(
+            r#"SELECT subject, clearance, document_id, classification, created_at as "created_at: NaiveDateTime" FROM access_audit ORDER BY id DESC LIMIT ?"#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| AccessDenial {
+                subject: r.subject,
+                clearance: r.clearance.parse().unwrap_or_default(),
+                document_id: r.document_id,
+                classification: r.classification.parse().unwrap_or_default(),
+                created_at: r.created_at,
+            })
+            .collect())
+    }
+
+    // --- Knowledge Base Management ---
+
+    /// Documents matching `filter` that `clearance` may read, newest first, with the total
+    /// number of such matches.
+    ///
+    /// Documents above `clearance` that would have been on this page are withheld and
+    /// recorded in the access audit under `subject`, as in `search_documents`.
+    pub async fn list_documents_page(
+        &self,
+        filter: &DocumentFilter,
+        clearance: Classification,
+        subject: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<DocumentSummary>, i64), SenseiError> {
+        const WHERE: &str = r#"
+            WHERE (?1 IS NULL OR json_extract(metadata, '$.source') = ?1)
+              AND (?2 IS NULL OR EXISTS (
+                    SELECT 1 FROM json_each(documents.metadata, '$.tags') WHERE json_each.value = ?2))
+        "#;
+        const CLEARED: &str = r#"
+              AND coalesce(json_extract(metadata, '$.classification'), 'unclassified')
+                    IN (SELECT value FROM json_each(?3))
+        "#;
+        let readable = serde_json::to_string(
+            &Classification::ALL
+                .into_iter()
+                .filter(|label| clearance.dominates(*label))
+                .map(|label| label.as_str())
+                .collect::<Vec<_>>(),
+        )
+        .map_err(|e| SenseiError::Unknown(format!("Failed to encode labels: {}", e)))?;
+
+        use sqlx::Row;
+        let total: i64 = sqlx::query(&format!(
+            "SELECT COUNT(*) as total FROM documents {} {}",
+            WHERE, CLEARED
+        ))
+        .bind(&filter.source)
+        .bind(&filter.tag)
+        .bind(&readable)
+        .fetch_one(&self.pool)
+        .await?
+        .get("total");
+
+        let rows = sqlx::query(&format!(
+            "SELECT id, substr(content, 1, {}) as snippet, metadata, created_at FROM documents {} {} ORDER BY created_at DESC, id DESC LIMIT ?4 OFFSET ?5",
+            SNIPPET_CHARS, WHERE, CLEARED
+        ))
+        .bind(&filter.source)
+        .bind(&filter.tag)
+        .bind(&readable)
         .bind(limit)
+        .bind(offset)
         .fetch_all(&self.pool)
         .await?;
 
+        if clearance < Classification::TopSecret {
+            let page = sqlx::query(&format!(
+                "SELECT id, metadata FROM documents {} ORDER BY created_at DESC, id DESC LIMIT ?3 OFFSET ?4",
+                WHERE
+            ))
+            .bind(&filter.source)
+            .bind(&filter.tag)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+            for row in page {
+                let label = DocumentMetadata::parse(row.get("metadata")).classification;
+                if !clearance.dominates(label) {
+                    self.record_access_denial(subject, clearance, row.get("id"), label)
+                        .await?;
+                }
+            }
+        }
+
+        let documents = rows
+            .into_iter()
+            .map(|row| DocumentSummary {
+                id: row.get("id"),
+                snippet: row.get("snippet"),
+                metadata: DocumentMetadata::parse(row.get("metadata")),
+                created_at: row.get("created_at"),
+            })
+            .collect();
+        Ok((documents, total))
+    }
+
+    /// Like `find_document`, enforcing "no read up": a document above `clearance` is
+    /// reported as missing and the denied read is recorded under `subject`.
+    pub async fn read_document(
+        &self,
+        id: i64,
+        clearance: Classification,
+        subject: &str,
+    ) -> Result<Option<Document>, SenseiError> {
+        let Some(document) = self.find_document(id).await? else {
+            return Ok(None);
+        };
+        let label = document.metadata.classification;
+        if !clearance.dominates(label) {
+            self.record_access_denial(subject, clearance, id, label)
+                .await?;
+            return Ok(None);
+        }
+        Ok(Some(document))
+    }
+
+    /// A document regardless of its classification, for internal use.
+    /// Reads on behalf of a subject go through `read_document`.
+    pub async fn find_document(&self, id: i64) -> Result<Option<Document>, SenseiError> {
         use sqlx::Row;
-        let results: Vec<String> = rows.iter().map(|row| row.get("content")).collect();
-        Ok(results)
+        let row =
+            sqlx::query("SELECT id, content, metadata, created_at FROM documents WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(row.map(|row| Document {
+            id: row.get("id"),
+            content: row.get("content"),
+            metadata: DocumentMetadata::parse(row.get("metadata")),
+            created_at: row.get("created_at"),
+        }))
+    }
+
+    /// Overwrite a document's content and metadata. Pass the new `embedding` when the content changed.
+    /// Returns false if the document does not exist.
+    pub async fn update_document(
+        &self,
+        id: i64,
+        content: &str,
+        metadata: &DocumentMetadata,
+        embedding: Option<Vec<f32>>,
+    ) -> Result<bool, SenseiError> {
+        let metadata = serde_json::to_string(metadata)
+            .map_err(|e| SenseiError::Unknown(format!("Failed to encode metadata: {}", e)))?;
+        if let Some(embedding) = &embedding {
+            self.check_dimension(embedding)?;
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query("UPDATE documents SET content = ?, metadata = ? WHERE id = ?")
+            .bind(content)
+            .bind(metadata)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        if let Some(embedding) = embedding {
+            replace_vector(
+                &mut tx,
+                VECTOR_TABLES[0],
+                id,
+                &embedding,
+                &self.embedding.model,
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Delete a document and its vector. Returns false if it did not exist.
+    pub async fn delete_document(&self, id: i64) -> Result<bool, SenseiError> {
+        let mut tx = self.pool.begin().await?;
+        let deleted = delete_documents(&mut tx, &[id]).await?;
+        tx.commit().await?;
+        Ok(deleted > 0)
+    }
+
+    /// Delete every chunk of `source`. Returns the number of documents removed.
+    pub async fn delete_source(&self, source: &str) -> Result<usize, SenseiError> {
+        self.replace_source_documents(Some(source), Vec::new())
+            .await
     }
 
     // MCP Support methods
@@ -216,15 +1122,17 @@ impl MemoryStore {
         enhanced_query: &str,
         embedding: Vec<f32>,
     ) -> Result<(), SenseiError> {
+        self.check_dimension(&embedding)?;
         let mut tx = self.pool.begin().await?;
 
         use sqlx::Row;
         let row = sqlx::query(
-            "INSERT INTO router_cache (query_text, category, enhanced_query) VALUES (?, ?, ?) RETURNING id",
+            "INSERT INTO router_cache (query_text, category, enhanced_query, embedding_model) VALUES (?, ?, ?, ?) RETURNING id",
         )
         .bind(query_text)
         .bind(category)
         .bind(enhanced_query)
+        .bind(&self.embedding.model)
         .fetch_one(&mut *tx)
         .await?;
 
@@ -246,6 +1154,7 @@ impl MemoryStore {
         query_embedding: Vec<f32>,
         similarity_threshold: f32,
     ) -> Result<Option<(String, String)>, SenseiError> {
+        self.check_dimension(&query_embedding)?;
         let vector_bytes = f32_vec_to_bytes(&query_embedding);
 
         // Search for nearest neighbor
@@ -254,11 +1163,12 @@ impl MemoryStore {
             SELECT c.category, c.enhanced_query, v.distance
             FROM vec_router_cache v
             JOIN router_cache c ON v.rowid = c.id
-            WHERE v.embedding MATCH ? AND k = 1
+            WHERE v.embedding MATCH ? AND k = 1 AND c.embedding_model = ?
             ORDER BY v.distance ASC
             "#,
         )
         .bind(vector_bytes)
+        .bind(&self.embedding.model)
         .fetch_optional(&self.pool)
         .await?;
 
@@ -281,18 +1191,21 @@ impl MemoryStore {
         query_embedding: Vec<f32>,
         new_category: &str,
     ) -> Result<bool, SenseiError> {
+        self.check_dimension(&query_embedding)?;
         let vector_bytes = f32_vec_to_bytes(&query_embedding);
 
         // Find nearest neighbor to update
         let row = sqlx::query(
             r#"
-            SELECT rowid, distance
-            FROM vec_router_cache
-            WHERE embedding MATCH ? AND k = 1
-            ORDER BY distance ASC
+            SELECT v.rowid AS rowid, v.distance AS distance
+            FROM vec_router_cache v
+            JOIN router_cache c ON v.rowid = c.id
+            WHERE v.embedding MATCH ? AND k = 1 AND c.embedding_model = ?
+            ORDER BY v.distance ASC
             "#,
         )
         .bind(vector_bytes)
+        .bind(&self.embedding.model)
         .fetch_optional(&self.pool)
         .await?;
 
@@ -314,6 +1227,281 @@ impl MemoryStore {
 
         Ok(false)
     }
+
+    // --- Embedding Models ---
+
+    /// Compare the vector tables and stored vectors with the configured embedding model.
+    pub async fn vector_index_status(&self) -> Result<VectorIndexStatus, SenseiError> {
+        use sqlx::Row;
+        let mut resize = Vec::new();
+        for row in sqlx::query("SELECT name, dimension FROM vector_tables ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?
+        {
+            let dimension = row.get::<i64, _>("dimension") as usize;
+            if dimension != self.embedding.dimension {
+                resize.push((row.get("name"), dimension));
+            }
+        }
+
+        let mut stale = [0; 2];
+        for (count, (_, table)) in stale.iter_mut().zip(VECTOR_TABLES) {
+            *count = sqlx::query(&format!(
+                "SELECT COUNT(*) as stale FROM {} WHERE embedding_model IS NOT ?",
+                table
+            ))
+            .bind(&self.embedding.model)
+            .fetch_one(&self.pool)
+            .await?
+            .get("stale");
+        }
+
+        Ok(VectorIndexStatus {
+            model: self.embedding.model.clone(),
+            dimension: self.embedding.dimension,
+            resize,
+            stale_documents: stale[0],
+            stale_router_cache: stale[1],
+        })
+    }
+
+    /// Recreate vector tables whose dimension differs from the configured one.
+    /// Their vectors are dropped and every row they embedded becomes stale.
+    /// Returns the number of tables recreated.
+    pub async fn resize_vector_tables(&self) -> Result<usize, SenseiError> {
+        let status = self.vector_index_status().await?;
+        let dimension = self.embedding.dimension as i64;
+
+        let mut tx = self.pool.begin().await?;
+        for (name, _) in &status.resize {
+            let Some((vectors, table)) = VECTOR_TABLES.into_iter().find(|(v, _)| v == name) else {
+                continue;
+            };
+            sqlx::query(&format!("DROP TABLE IF EXISTS {}", vectors))
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(&format!(
+                "CREATE VIRTUAL TABLE {} USING vec0(embedding float[{}])",
+                vectors, dimension
+            ))
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query("UPDATE vector_tables SET dimension = ? WHERE name = ?")
+                .bind(dimension)
+                .bind(vectors)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(&format!("UPDATE {} SET embedding_model = NULL", table))
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(status.resize.len())
+    }
+
+    /// Up to `limit` documents not embedded with the configured model, as (id, content).
+    pub async fn stale_documents(&self, limit: i64) -> Result<Vec<(i64, String)>, SenseiError> {
+        self.stale_rows(VECTOR_TABLES[0], "content", limit).await
+    }
+
+    /// Up to `limit` router cache entries not embedded with the configured model, as (id, query).
+    pub async fn stale_router_cache(&self, limit: i64) -> Result<Vec<(i64, String)>, SenseiError> {
+        self.stale_rows(VECTOR_TABLES[1], "query_text", limit).await
+    }
+
+    async fn stale_rows(
+        &self,
+        (_, table): (&str, &str),
+        text: &str,
+        limit: i64,
+    ) -> Result<Vec<(i64, String)>, SenseiError> {
+        use sqlx::Row;
+        let rows = sqlx::query(&format!(
+            "SELECT id, {} as text FROM {} WHERE embedding_model IS NOT ? ORDER BY id LIMIT ?",
+            text, table
+        ))
+        .bind(&self.embedding.model)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("id"), row.get("text")))
+            .collect())
+    }
+
+    /// Replace the vector of a document with one from the configured model.
+    pub async fn set_document_embedding(
+        &self,
+        id: i64,
+        embedding: &[f32],
+    ) -> Result<(), SenseiError> {
+        self.set_embedding(VECTOR_TABLES[0], id, embedding).await
+    }
+
+    /// Replace the vector of a router cache entry with one from the configured model.
+    pub async fn set_router_cache_embedding(
+        &self,
+        id: i64,
+        embedding: &[f32],
+    ) -> Result<(), SenseiError> {
+        self.set_embedding(VECTOR_TABLES[1], id, embedding).await
+    }
+
+    async fn set_embedding(
+        &self,
+        tables: (&str, &str),
+        id: i64,
+        embedding: &[f32],
+    ) -> Result<(), SenseiError> {
+        self.check_dimension(embedding)?;
+        let mut tx = self.pool.begin().await?;
+        replace_vector(&mut tx, tables, id, embedding, &self.embedding.model).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    fn check_dimension(&self, embedding: &[f32]) -> Result<(), SenseiError> {
+        if embedding.len() != self.embedding.dimension {
+            return Err(SenseiError::Config(format!(
+                "Embedding has {} dimensions but '{}' is configured with {}",
+                embedding.len(),
+                self.embedding.model,
+                self.embedding.dimension
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Store the vector of row `id` and record the model that made it.
+async fn replace_vector(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    (vectors, table): (&str, &str),
+    id: i64,
+    embedding: &[f32],
+    model: &str,
+) -> Result<(), SenseiError> {
+    sqlx::query(&format!("DELETE FROM {} WHERE rowid = ?", vectors))
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query(&format!(
+        "INSERT INTO {} (rowid, embedding) VALUES (?, ?)",
+        vectors
+    ))
+    .bind(id)
+    .bind(f32_vec_to_bytes(embedding))
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query(&format!(
+        "UPDATE {} SET embedding_model = ? WHERE id = ?",
+        table
+    ))
+    .bind(model)
+    .bind(id)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Delete documents together with their vectors. Returns the number of documents removed.
+async fn delete_documents(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    ids: &[i64],
+) -> Result<usize, SenseiError> {
+    let mut deleted = 0;
+    for id in ids {
+        sqlx::query("DELETE FROM vec_items WHERE rowid = ?")
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+        let result = sqlx::query("DELETE FROM documents WHERE id = ?")
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+        deleted += result.rows_affected() as usize;
+    }
+    Ok(deleted)
+}
+
+async fn insert_document(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    content: &str,
+    metadata: &DocumentMetadata,
+    embedding: &[f32],
+    model: &str,
+) -> Result<i64, SenseiError> {
+    use sqlx::Row;
+    let metadata = serde_json::to_string(metadata)
+        .map_err(|e| SenseiError::Unknown(format!("Failed to encode metadata: {}", e)))?;
+    let row = sqlx::query(
+        "INSERT INTO documents (content, metadata, embedding_model) VALUES (?, ?, ?) RETURNING id",
+    )
+    .bind(content)
+    .bind(metadata)
+    .bind(model)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    let id: i64 = row.get("id");
+
+    let vector_bytes = f32_vec_to_bytes(embedding);
+    sqlx::query("INSERT INTO vec_items (rowid, embedding) VALUES (?, ?)")
+        .bind(id)
+        .bind(vector_bytes)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(id)
+}
+
+/// Turn free text into an FTS5 query matching any of its significant words.
+/// Every word is quoted, so `CVE-2024-3094` or `db01.corp.local` match as phrases
+/// and FTS5 operators in the input are taken literally.
+fn keyword_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty() && !STOPWORDS.contains(&word.to_lowercase().as_str()))
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" OR "))
+}
+
+/// Tokens as FTS5's `unicode61` tokenizer sees them: alphanumeric runs, lowercased.
+fn fts_tokens(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Query words made of several tokens, such as `CVE-2024-3094` or `db01.corp.local`.
+fn identifier_terms(query: &str) -> Vec<Vec<String>> {
+    query
+        .split_whitespace()
+        .map(fts_tokens)
+        .filter(|tokens| tokens.len() > 1)
+        .collect()
+}
+
+fn matches_any_phrase(content: &str, phrases: &[Vec<String>]) -> bool {
+    if phrases.is_empty() {
+        return false;
+    }
+    let tokens = fts_tokens(content);
+    phrases.iter().any(|phrase| {
+        tokens
+            .windows(phrase.len())
+            .any(|window| window == phrase.as_slice())
+    })
+}
+
+/// IDF FTS5's `bm25()` gives a term found in one of `documents` documents,
+/// including its floor for tiny corpora.
+fn single_document_idf(documents: i64) -> f64 {
+    ((documents as f64 - 0.5) / 1.5).ln().max(1e-6)
 }
 
 fn f32_vec_to_bytes(v: &[f32]) -> Vec<u8> {
@@ -0,0 +1,117 @@
+use crate::agents::Orchestrator;
+use crate::errors::SenseiError;
+use crate::llm::Llm;
+use crate::memory::{MemoryStore, SearchHit};
+use sensei_common::{AgentCategory, Citation, Classification, knowledge_uri};
+
+/// Characters of a retrieved chunk quoted in a citation.
+const CITATION_SNIPPET_CHARS: usize = 160;
+
+/// Chunks relevant to `query` that `clearance` may read, best first (hybrid vector + keyword search).
+/// `subject` is recorded when documents are withheld.
+pub async fn retrieve(
+    memory: &MemoryStore,
+    llm: &dyn Llm,
+    query: &str,
+    limit: i64,
+    clearance: Classification,
+    subject: &str,
+) -> Result<Vec<SearchHit>, SenseiError> {
+    let embedding = llm.embed(query).await?;
+    memory
+        .search_documents(query, embedding, limit, clearance, subject)
+        .await
+}
+
+/// How an answer cites `hit` as `[index]`.
+pub fn citation(index: usize, hit: &SearchHit) -> Citation {
+    Citation {
+        index,
+        uri: knowledge_uri(hit.id),
+        source: hit.metadata.source.clone(),
+        score: hit.score,
+        snippet: snippet(&hit.content),
+        classification: hit.metadata.classification,
+    }
+}
+
+/// Fetch relevant knowledge the agent serving `category` is cleared for and prepend it to the query,
+/// numbered so the answer can cite it. Returns the enriched prompt and the citations.
+pub async fn enrich_with_rag(
+    orchestrator: &Orchestrator,
+    memory: &MemoryStore,
+    llm: &dyn Llm,
+    category: &AgentCategory,
+    query: String,
+) -> (String, Vec<Citation>) {
+    enrich_with_rag_capped(
+        orchestrator,
+        memory,
+        llm,
+        category,
+        query,
+        Classification::TopSecret,
+    )
+    .await
+}
+
+/// Like `enrich_with_rag`, for a caller cleared only up to `ceiling`:
+/// knowledge above the agent's clearance or above `ceiling` is withheld.
+pub async fn enrich_with_rag_capped(
+    orchestrator: &Orchestrator,
+    memory: &MemoryStore,
+    llm: &dyn Llm,
+    category: &AgentCategory,
+    query: String,
+    ceiling: Classification,
+) -> (String, Vec<Citation>) {
+    if !orchestrator.uses_rag(category).await {
+        return (query, Vec::new());
+    }
+    let clearance = orchestrator.clearance(category).await.min(ceiling);
+    let subject = format!("agent:{}", category.as_str());
+
+    let context_docs = match retrieve(memory, llm, &query, 3, clearance, &subject).await {
+        Ok(hits) => hits,
+        Err(e) => {
+            eprintln!("RAG Retrieval Failed: {}", e);
+            vec![]
+        }
+    };
+
+    if context_docs.is_empty() {
+        return (query, Vec::new());
+    }
+
+    println!("📚 RAG: Found {} relevant documents.", context_docs.len());
+    let mut knowledge = Vec::new();
+    let mut citations = Vec::new();
+    for (i, hit) in context_docs.into_iter().enumerate() {
+        println!(
+            "   #{} score {:.4} (distance {:?}, bm25 {:?})",
+            hit.id, hit.score, hit.distance, hit.bm25
+        );
+        let citation = citation(i + 1, &hit);
+        let label = citation.source.as_deref().unwrap_or(&citation.uri);
+        knowledge.push(format!("[{}] ({})\n{}", citation.index, label, hit.content));
+        citations.push(citation);
+    }
+
+    let prompt = format!(
+        "RELEVANT KNOWLEDGE:\n{}\n\n\
+         Cite the knowledge you use with its number in brackets, e.g. [1]. \
+         Do not cite anything else.\n\nUSER QUERY:\n{}",
+        knowledge.join("\n---\n"),
+        query
+    );
+    (prompt, citations)
+}
+
+/// The start of `content` on one line, for citations.
+fn snippet(content: &str) -> String {
+    let flat = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    match flat.char_indices().nth(CITATION_SNIPPET_CHARS) {
+        Some((end, _)) => format!("{}...", &flat[..end]),
+        None => flat,
+    }
+}
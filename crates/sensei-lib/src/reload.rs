@@ -0,0 +1,521 @@
+use crate::agents::Agent;
+use crate::agents::Orchestrator;
+use crate::agents::mcp_agent::McpAgent;
+use crate::agents::router::RouterAgent;
+use crate::agents::swarm::{build_agent, category_list};
+use crate::config::{
+    AgentDefinition, McpServerConfig, McpSettings, PromptsConfig, ROUTER_PROMPT, load_mcp_settings,
+    load_prompts,
+};
+use crate::errors::SenseiError;
+use crate::ingest::{ChunkConfig, ingest_mcp_resources};
+use crate::llm::Llm;
+use crate::mcp_client::{McpClient, McpState, McpStatus};
+use crate::memory::MemoryStore;
+use crate::tools::ToolRegistry;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use sensei_common::AgentCategory;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{Mutex, mpsc};
+use tracing::{info, warn};
+
+/// Router prompt used when `prompts.yaml` has no `router` entry.
+pub const DEFAULT_ROUTER_PROMPT: &str = r#"
+        You are a Query Optimizer.
+        STANDARD CATEGORIES: {CATEGORIES}
+        ACTIVE EXTENSIONS: {EXTENSIONS}
+        RULES:
+        1. If the user asks to EXECUTE a specific tool or command (e.g. "scan IP", "check uptime"), classify as ACTION (or SYSTEM).
+        2. If the user asks for EXPLANATIONS or PLANS, classify as RED/BLUE/etc.
+        Classify user input into one of the above categories.
+        If the input requires an extension tool, output the extension name (e.g. FILESYSTEM) as the category.
+        Output strictly JSON format: {"category": "CategoryName", "enhanced_query": "Query"}
+        "#;
+
+/// Editors save in bursts (truncate, write, rename); events this close together trigger one reload.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Sent by servers whose resource list changed; indexed resources are ingested again.
+const RESOURCES_CHANGED: &str = "notifications/resources/list_changed";
+
+/// Builds the LLM client of a declared agent from its tier, model and temperature.
+pub type LlmFactory = Arc<dyn Fn(&AgentDefinition) -> Arc<dyn Llm> + Send + Sync>;
+
+/// Where the swarm is configured and what its agents are built with.
+pub struct SwarmConfig {
+    pub prompts_path: PathBuf,
+    pub mcp_path: PathBuf,
+    pub llm_for: LlmFactory,
+    /// LLM driving the MCP agents.
+    pub mcp_llm: Arc<dyn Llm>,
+    pub tools: ToolRegistry,
+    pub react_steps: usize,
+    /// First and maximum delay between restarts of an MCP server that exited.
+    pub mcp_backoff: (Duration, Duration),
+    /// Where resources of MCP servers with `index_resources` are ingested.
+    pub knowledge: Option<Arc<KnowledgeBase>>,
+    /// Environment set for every MCP server spawned, over its own `env`.
+    pub mcp_env: HashMap<String, String>,
+}
+
+/// The knowledge base agents retrieve from, with the model that embeds it.
+pub struct KnowledgeBase {
+    pub memory: MemoryStore,
+    pub llm: Arc<dyn Llm>,
+    pub chunking: ChunkConfig,
+}
+
+/// Categories changed by a reload.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct ReloadReport {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+    /// Whether the router prompt changed.
+    pub router: bool,
+}
+
+impl ReloadReport {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty() && !self.router
+    }
+}
+
+/// The configuration currently serving requests.
+#[derive(Default)]
+struct Applied {
+    // Declared agents by category
+    agents: BTreeMap<String, AgentDefinition>,
+    mcp_servers: BTreeMap<String, RunningServer>,
+    // Servers that failed to start, with the error; retried on the next reload
+    mcp_failed: BTreeMap<String, String>,
+    router_prompt: String,
+}
+
+struct RunningServer {
+    config: McpServerConfig,
+    client: Arc<McpClient>,
+}
+
+/// Applies `prompts.yaml` and `mcp_settings.json` to a running swarm.
+/// A configuration that fails validation is rejected as a whole and the running one is kept.
+pub struct Reloader {
+    config: SwarmConfig,
+    orchestrator: Arc<Orchestrator>,
+    router: Arc<RouterAgent>,
+    // Serializes reloads triggered at the same time (file change, SIGHUP, API)
+    reloading: Mutex<()>,
+    // Only held briefly, never while MCP servers start, so status requests do not wait
+    applied: Mutex<Applied>,
+}
+
+impl Reloader {
+    pub fn new(
+        config: SwarmConfig,
+        orchestrator: Arc<Orchestrator>,
+        router: Arc<RouterAgent>,
+    ) -> Self {
+        Self {
+            config,
+            orchestrator,
+            router,
+            reloading: Mutex::new(()),
+            applied: Mutex::new(Applied::default()),
+        }
+    }
+
+    /// Read both files and swap the changed agents into the orchestrator.
+    /// The first call registers the whole swarm.
+    pub async fn reload(&self) -> Result<ReloadReport, SenseiError> {
+        let _reloading = self.reloading.lock().await;
+
+        // 1. Validate everything before touching the running swarm
+        let prompts = read_prompts(&self.config.prompts_path)?;
+        let definitions = prompts.agent_definitions()?;
+        let mcp_servers = read_mcp_settings(&self.config.mcp_path)?.mcp_servers;
+        check_mcp_categories(&definitions, &mcp_servers)?;
+
+        let mut report = ReloadReport::default();
+        let mut agents: Vec<Box<dyn Agent>> = Vec::new();
+        let applied = self.applied.lock().await;
+        for definition in &definitions {
+            match applied.agents.get(definition.category.as_str()) {
+                Some(current) if current == definition => continue,
+                Some(_) => report
+                    .updated
+                    .push(definition.category.as_str().to_string()),
+                None => report.added.push(definition.category.as_str().to_string()),
+            }
+            let llm = (self.config.llm_for)(definition);
+            agents.push(build_agent(
+                definition,
+                llm,
+                &self.config.tools,
+                self.config.react_steps,
+            )?);
+        }
+        let mut removed: Vec<AgentCategory> = applied
+            .agents
+            .keys()
+            .filter(|category| !definitions.iter().any(|d| d.category.as_str() == *category))
+            .map(|category| AgentCategory::new(category))
+            .collect();
+
+        let changed: Vec<(&String, &McpServerConfig)> = mcp_servers
+            .iter()
+            .filter(|(name, server)| {
+                applied
+                    .mcp_servers
+                    .get(*name)
+                    .is_none_or(|running| &running.config != *server)
+            })
+            .collect();
+        drop(applied);
+
+        // 2. (Re)start the MCP servers that are new or whose command, args or env changed,
+        //    without holding the lock; a server that fails to start is skipped
+        let mut started = BTreeMap::new();
+        let mut failed = BTreeMap::new();
+        for (name, server) in changed {
+            match self.connect(name, server).await {
+                Ok((agent, client)) => {
+                    info!("   ✅ MCP Agent '{}' registered", name);
+                    agents.push(Box::new(agent));
+                    started.insert(
+                        name.clone(),
+                        RunningServer {
+                            config: server.clone(),
+                            client,
+                        },
+                    );
+                }
+                Err(e) => {
+                    warn!("   ❌ Failed to start MCP Agent '{}': {}", name, e);
+                    failed.insert(name.clone(), e.to_string());
+                }
+            }
+        }
+
+        // Only reloads change `applied` and they are serialized: the state read above still holds
+        let mut applied = self.applied.lock().await;
+        for name in started.keys() {
+            applied.mcp_failed.remove(name);
+        }
+        applied.mcp_failed.extend(failed);
+        applied
+            .mcp_failed
+            .retain(|name, _| mcp_servers.contains_key(name));
+        let stopped: Vec<String> = applied
+            .mcp_servers
+            .keys()
+            .filter(|name| !mcp_servers.contains_key(*name))
+            .cloned()
+            .collect();
+
+        // Replaced and removed servers are shut down once the swap is done
+        let mut retired = Vec::new();
+        for (name, server) in started {
+            let category = name.to_lowercase();
+            match applied.mcp_servers.insert(name, server) {
+                Some(old) => {
+                    report.updated.push(category);
+                    retired.push(old.client);
+                }
+                None => report.added.push(category),
+            }
+        }
+        for name in &stopped {
+            if let Some(old) = applied.mcp_servers.remove(name) {
+                retired.push(old.client);
+            }
+            removed.push(AgentCategory::new(name));
+        }
+
+        // 3. Swap the agents in one step, then point the router at the new swarm
+        for category in &removed {
+            report.removed.push(category.as_str().to_string());
+            applied.agents.remove(category.as_str());
+        }
+        self.orchestrator.replace(&removed, agents).await;
+
+        let extensions: Vec<String> = applied
+            .mcp_servers
+            .keys()
+            .map(|name| name.to_uppercase())
+            .collect();
+        let router_prompt = router_prompt(&prompts, &definitions, &extensions);
+        for definition in definitions {
+            applied
+                .agents
+                .insert(definition.category.as_str().to_string(), definition);
+        }
+        if router_prompt != applied.router_prompt {
+            self.router.set_system_prompt(&router_prompt);
+            applied.router_prompt = router_prompt;
+            report.router = true;
+        }
+        drop(applied);
+
+        for client in retired {
+            client.shutdown().await;
+        }
+        Ok(report)
+    }
+
+    /// Start a supervised MCP server and discover its tools.
+    async fn connect(
+        &self,
+        name: &str,
+        server: &McpServerConfig,
+    ) -> anyhow::Result<(McpAgent, Arc<McpClient>)> {
+        let mut server = server.clone();
+        if server.command.is_some() && !self.config.mcp_env.is_empty() {
+            let env = server.env.get_or_insert_with(HashMap::new);
+            env.extend(self.config.mcp_env.clone());
+        }
+        let client = Arc::new(
+            McpClient::from_config(&server)
+                .await?
+                .with_backoff(self.config.mcp_backoff.0, self.config.mcp_backoff.1),
+        );
+        match McpAgent::new(client.clone(), self.config.mcp_llm.clone(), name).await {
+            Ok(agent) => {
+                client.supervise();
+                if server.index_resources {
+                    self.index_resources(name, &server, &client);
+                }
+                Ok((agent.with_max_steps(self.config.react_steps), client))
+            }
+            Err(e) => {
+                client.shutdown().await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Ingest the server's resources in the background, and again whenever its resource list changes.
+    fn index_resources(&self, name: &str, server: &McpServerConfig, client: &Arc<McpClient>) {
+        let Some(knowledge) = self.config.knowledge.clone() else {
+            warn!(
+                "   ⚠️ MCP server '{}' sets index_resources, but there is no knowledge base",
+                name
+            );
+            return;
+        };
+        if !client.supports("resources") {
+            warn!("   ⚠️ MCP server '{}' has no resources to index", name);
+            return;
+        }
+        let mut notifications = client.subscribe();
+        let client = Arc::downgrade(client);
+        let (name, classification) = (name.to_string(), server.classification);
+        tokio::spawn(async move {
+            loop {
+                let Some(client) = client.upgrade() else {
+                    break;
+                };
+                if client.status().state == McpState::Stopped {
+                    break;
+                }
+                match ingest_mcp_resources(
+                    &knowledge.memory,
+                    knowledge.llm.as_ref(),
+                    &client,
+                    &name,
+                    classification,
+                    &knowledge.chunking,
+                )
+                .await
+                {
+                    Ok(reports) => info!(
+                        "📚 MCP server '{}': {} resources indexed ({} unchanged)",
+                        name,
+                        reports.len(),
+                        reports.iter().filter(|r| r.unchanged).count()
+                    ),
+                    Err(e) => warn!(
+                        "❌ Indexing the resources of MCP server '{}' failed: {}",
+                        name, e
+                    ),
+                }
+                drop(client);
+
+                loop {
+                    match notifications.recv().await {
+                        Ok(notification) if notification.method == RESOURCES_CHANGED => break,
+                        // Missed notifications may have included a change.
+                        Err(RecvError::Lagged(_)) => break,
+                        Ok(_) => continue,
+                        Err(RecvError::Closed) => return,
+                    }
+                }
+            }
+        });
+    }
+
+    /// Health of every configured MCP server. A server whose restart with a changed
+    /// configuration failed reports the previous instance, which keeps serving.
+    pub async fn mcp_status(&self) -> BTreeMap<String, McpStatus> {
+        let applied = self.applied.lock().await;
+        let failed = applied
+            .mcp_failed
+            .iter()
+            .map(|(name, error)| (name.clone(), McpStatus::failed(error)));
+        let running = applied
+            .mcp_servers
+            .iter()
+            .map(|(name, server)| (name.clone(), server.client.status()));
+        failed.chain(running).collect()
+    }
+
+    /// Reload and log the outcome; used by the file watcher and SIGHUP.
+    pub async fn reload_and_log(&self, trigger: &str) {
+        match self.reload().await {
+            Ok(report) if report.is_empty() => info!("🔄 Reload ({}): nothing changed", trigger),
+            Ok(report) => info!(
+                "🔄 Reloaded ({}): added {:?}, updated {:?}, removed {:?}",
+                trigger, report.added, report.updated, report.removed
+            ),
+            Err(e) => warn!(
+                "❌ Reload ({}) rejected, keeping the running configuration: {}",
+                trigger, e
+            ),
+        }
+    }
+
+    /// Reload whenever `prompts.yaml` or `mcp_settings.json` changes on disk (inotify on Linux).
+    /// The parent directories are watched so files replaced by a rename are seen too.
+    pub fn watch(self: &Arc<Self>) -> Result<(), SenseiError> {
+        let names: Vec<_> = [&self.config.prompts_path, &self.config.mcp_path]
+            .iter()
+            .filter_map(|path| path.file_name().map(|name| name.to_os_string()))
+            .collect();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                if let Ok(event) = event
+                    && !event.kind.is_access()
+                    && event
+                        .paths
+                        .iter()
+                        .any(|p| p.file_name().is_some_and(|n| names.iter().any(|m| m == n)))
+                {
+                    let _ = tx.send(());
+                }
+            })
+            .map_err(watch_error)?;
+
+        let mut dirs = vec![parent_dir(&self.config.prompts_path)];
+        let mcp_dir = parent_dir(&self.config.mcp_path);
+        if !dirs.contains(&mcp_dir) {
+            dirs.push(mcp_dir);
+        }
+        for dir in &dirs {
+            watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .map_err(watch_error)?;
+        }
+
+        let reloader = self.clone();
+        tokio::spawn(async move {
+            // The watcher stops when dropped, so the task owns it
+            let _watcher: RecommendedWatcher = watcher;
+            while rx.recv().await.is_some() {
+                tokio::time::sleep(DEBOUNCE).await;
+                while rx.try_recv().is_ok() {}
+                reloader.reload_and_log("file change").await;
+            }
+        });
+        Ok(())
+    }
+
+    /// Reload on `SIGHUP`.
+    #[cfg(unix)]
+    pub fn reload_on_sighup(self: &Arc<Self>) -> std::io::Result<()> {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut hangup = signal(SignalKind::hangup())?;
+        let reloader = self.clone();
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                reloader.reload_and_log("SIGHUP").await;
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Router prompt listing the declared categories and the MCP extensions.
+pub fn router_prompt(
+    prompts: &PromptsConfig,
+    definitions: &[AgentDefinition],
+    extensions: &[String],
+) -> String {
+    let extensions = if extensions.is_empty() {
+        "NONE".to_string()
+    } else {
+        extensions.join(", ")
+    };
+    prompts
+        .prompt(ROUTER_PROMPT)
+        .unwrap_or(DEFAULT_ROUTER_PROMPT)
+        .replace("{CATEGORIES}", &category_list(definitions))
+        .replace("{EXTENSIONS}", &extensions)
+}
+
+/// A missing file means the built-in swarm; a file that does not parse is an error.
+pub fn read_prompts(path: &Path) -> Result<PromptsConfig, SenseiError> {
+    if !path.exists() {
+        return Ok(PromptsConfig::default());
+    }
+    load_prompts(&path.to_string_lossy())
+}
+
+fn read_mcp_settings(path: &Path) -> Result<McpSettings, SenseiError> {
+    if !path.exists() {
+        return Ok(McpSettings::default());
+    }
+    load_mcp_settings(&path.to_string_lossy())
+}
+
+/// MCP agents are routed by their server name, lowercased like agent categories:
+/// a server must not shadow a declared agent or another server.
+fn check_mcp_categories(
+    definitions: &[AgentDefinition],
+    mcp_servers: &HashMap<String, McpServerConfig>,
+) -> Result<(), SenseiError> {
+    let mut categories: Vec<AgentCategory> =
+        definitions.iter().map(|d| d.category.clone()).collect();
+    // Sorted, so the same configuration always reports the same collision
+    let mut names: Vec<&String> = mcp_servers.keys().collect();
+    names.sort();
+    for name in names {
+        let category = AgentCategory::new(name);
+        if categories.contains(&category) {
+            return Err(SenseiError::Config(format!(
+                "MCP server '{}' collides with the '{}' category",
+                name,
+                category.as_str()
+            )));
+        }
+        categories.push(category);
+    }
+    Ok(())
+}
+
+fn parent_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+fn watch_error(e: notify::Error) -> SenseiError {
+    SenseiError::Config(format!("Failed to watch the configuration: {}", e))
+}
@@ -0,0 +1,552 @@
+use crate::agents::react::DEFAULT_MAX_STEPS;
+use crate::agents::{Orchestrator, router::RouterAgent};
+use crate::config::{AgentDefinition, LlmTier};
+use crate::context::{ContextWindow, DEFAULT_CONTEXT_TOKENS};
+use crate::errors::SenseiError;
+use crate::ingest::ChunkConfig;
+use crate::llm::{
+    DEFAULT_EMBEDDING_DIMENSION, DEFAULT_TEMPERATURE, EmbeddingConfig, GeminiClient, Llm,
+    MODEL_CHAT_FAST, MODEL_CHAT_SMART, MODEL_EMBEDDING, OllamaClient, TieredLlmClient,
+};
+use crate::mcp_client::{DEFAULT_MAX_RESTART_BACKOFF, DEFAULT_RESTART_BACKOFF};
+use crate::memory::{MemoryStore, SearchConfig};
+use crate::reload::{KnowledgeBase, LlmFactory, Reloader, SwarmConfig};
+use crate::tools::ToolRegistry;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Configuration file read when `SENSEI_CONFIG` is not set; optional.
+pub const DEFAULT_CONFIG_PATH: &str = "sensei.yaml";
+
+/// Everything the swarm is assembled from, read from `sensei.yaml` (or `.toml`).
+/// Every field is optional; environment variables override the file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuntimeConfig {
+    /// `DATABASE_URL`
+    pub database_url: String,
+    /// `SENSEI_PROMPTS_PATH`: agent definitions.
+    pub prompts_path: PathBuf,
+    /// `SENSEI_MCP_CONFIG`: MCP servers.
+    pub mcp_config: PathBuf,
+    pub llm: LlmSettings,
+    pub embedding: EmbeddingSettings,
+    pub rag: RagSettings,
+    pub context: ContextSettings,
+    pub chunking: ChunkSettings,
+    /// `SENSEI_REACT_MAX_STEPS`: tool calls a tool or MCP agent may chain for one request.
+    pub react_max_steps: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LlmSettings {
+    pub fast_model: String,
+    pub smart_model: String,
+    /// `OLLAMA_MODEL`: local model answering when Gemini fails.
+    pub ollama_model: Option<String>,
+    /// `OLLAMA_URL`
+    pub ollama_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbeddingSettings {
+    /// `SENSEI_EMBEDDING_MODEL`: `ollama::<model>` for a local one.
+    pub model: String,
+    /// `SENSEI_EMBEDDING_DIMENSION`: required for models other than the default one.
+    pub dimension: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RagSettings {
+    /// `SENSEI_RAG_MAX_DISTANCE`
+    pub max_distance: f32,
+    /// `SENSEI_RAG_MIN_BM25`
+    pub min_bm25: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContextSettings {
+    /// `SENSEI_CONTEXT_TOKENS`: session history replayed to agents.
+    pub tokens: usize,
+    /// `SENSEI_CONTEXT_SUMMARY`: summarize the turns that overflow the budget.
+    pub summary: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChunkSettings {
+    /// `SENSEI_CHUNK_SIZE`, in bytes.
+    pub size: usize,
+    /// `SENSEI_CHUNK_OVERLAP`, in bytes.
+    pub overlap: usize,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            database_url: "sqlite://sensei.db?mode=rwc".to_string(),
+            prompts_path: PathBuf::from("prompts.yaml"),
+            mcp_config: PathBuf::from("mcp_settings.json"),
+            llm: LlmSettings::default(),
+            embedding: EmbeddingSettings::default(),
+            rag: RagSettings::default(),
+            context: ContextSettings::default(),
+            chunking: ChunkSettings::default(),
+            react_max_steps: DEFAULT_MAX_STEPS,
+        }
+    }
+}
+
+impl Default for LlmSettings {
+    fn default() -> Self {
+        Self {
+            fast_model: MODEL_CHAT_FAST.to_string(),
+            smart_model: MODEL_CHAT_SMART.to_string(),
+            ollama_model: None,
+            ollama_url: None,
+        }
+    }
+}
+
+impl Default for EmbeddingSettings {
+    fn default() -> Self {
+        Self {
+            model: MODEL_EMBEDDING.to_string(),
+            dimension: None,
+        }
+    }
+}
+
+impl Default for RagSettings {
+    fn default() -> Self {
+        Self {
+            max_distance: SearchConfig::default().max_distance,
+            min_bm25: SearchConfig::default().min_bm25,
+        }
+    }
+}
+
+impl Default for ContextSettings {
+    fn default() -> Self {
+        Self {
+            tokens: DEFAULT_CONTEXT_TOKENS,
+            summary: false,
+        }
+    }
+}
+
+impl Default for ChunkSettings {
+    fn default() -> Self {
+        let chunking = ChunkConfig::default();
+        Self {
+            size: chunking.size,
+            overlap: chunking.overlap,
+        }
+    }
+}
+
+/// Overwrite `target` with the parsed value of `name`, when set.
+fn override_with<T: FromStr>(
+    var: &impl Fn(&str) -> Option<String>,
+    name: &str,
+    target: &mut T,
+) -> Result<(), SenseiError> {
+    if let Some(value) = var(name) {
+        *target = value
+            .trim()
+            .parse()
+            .map_err(|_| SenseiError::Config(format!("Invalid {} '{}'", name, value)))?;
+    }
+    Ok(())
+}
+
+impl RuntimeConfig {
+    /// Parse a YAML file, or TOML when the name ends with `.toml`.
+    pub fn load(path: &Path) -> Result<Self, SenseiError> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            SenseiError::Config(format!(
+                "Failed to read config file '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let parsed = if path.extension().is_some_and(|e| e == "toml") {
+            toml::from_str(&contents).map_err(|e| e.to_string())
+        } else {
+            serde_yaml::from_str(&contents).map_err(|e| e.to_string())
+        };
+        parsed.map_err(|e| {
+            SenseiError::Config(format!("Invalid config file '{}': {}", path.display(), e))
+        })
+    }
+
+    /// The file named by `SENSEI_CONFIG` (or `sensei.yaml` when present), the environment on top, validated.
+    pub fn from_env() -> Result<Self, SenseiError> {
+        let config = match env::var("SENSEI_CONFIG") {
+            Ok(path) => Self::load(Path::new(&path))?,
+            Err(_) if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::load(Path::new(DEFAULT_CONFIG_PATH))?
+            }
+            Err(_) => Self::default(),
+        };
+        let config = config.with_overrides(|name| env::var(name).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Apply the variables documented on each field, read with `var`.
+    pub fn with_overrides(
+        mut self,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, SenseiError> {
+        override_with(&var, "DATABASE_URL", &mut self.database_url)?;
+        override_with(&var, "SENSEI_PROMPTS_PATH", &mut self.prompts_path)?;
+        override_with(&var, "SENSEI_MCP_CONFIG", &mut self.mcp_config)?;
+        if let Some(model) = var("OLLAMA_MODEL") {
+            self.llm.ollama_model = Some(model);
+        }
+        if let Some(url) = var("OLLAMA_URL") {
+            self.llm.ollama_url = Some(url);
+        }
+        override_with(&var, "SENSEI_EMBEDDING_MODEL", &mut self.embedding.model)?;
+        if let Some(dimension) = var("SENSEI_EMBEDDING_DIMENSION") {
+            self.embedding.dimension = Some(dimension.trim().parse().map_err(|_| {
+                SenseiError::Config(format!(
+                    "Invalid SENSEI_EMBEDDING_DIMENSION '{}'",
+                    dimension
+                ))
+            })?);
+        }
+        override_with(&var, "SENSEI_RAG_MAX_DISTANCE", &mut self.rag.max_distance)?;
+        override_with(&var, "SENSEI_RAG_MIN_BM25", &mut self.rag.min_bm25)?;
+        override_with(&var, "SENSEI_CONTEXT_TOKENS", &mut self.context.tokens)?;
+        if let Some(summary) = var("SENSEI_CONTEXT_SUMMARY") {
+            self.context.summary = summary == "1" || summary == "true";
+        }
+        override_with(&var, "SENSEI_CHUNK_SIZE", &mut self.chunking.size)?;
+        override_with(&var, "SENSEI_CHUNK_OVERLAP", &mut self.chunking.overlap)?;
+        override_with(&var, "SENSEI_REACT_MAX_STEPS", &mut self.react_max_steps)?;
+        Ok(self)
+    }
+
+    /// Reject values the swarm cannot run with.
+    pub fn validate(&self) -> Result<(), SenseiError> {
+        let invalid = |message: String| Err(SenseiError::Config(message));
+        if self.database_url.trim().is_empty() {
+            return invalid("database_url must be set".to_string());
+        }
+        if self.llm.fast_model.trim().is_empty() || self.llm.smart_model.trim().is_empty() {
+            return invalid("llm.fast_model and llm.smart_model must be set".to_string());
+        }
+        match self.embedding.dimension {
+            Some(0) => return invalid("embedding.dimension must be positive".to_string()),
+            None if self.embedding.model != MODEL_EMBEDDING => {
+                return invalid(format!(
+                    "embedding.dimension must be set for '{}'",
+                    self.embedding.model
+                ));
+            }
+            _ => {}
+        }
+        if self.rag.max_distance.is_nan() || self.rag.max_distance < 0.0 {
+            return invalid("rag.max_distance must not be negative".to_string());
+        }
+        if self.rag.min_bm25.is_nan() || self.rag.min_bm25 < 0.0 {
+            return invalid("rag.min_bm25 must not be negative".to_string());
+        }
+        if self.context.tokens == 0 || self.react_max_steps == 0 {
+            return invalid("context.tokens and react_max_steps must be positive".to_string());
+        }
+        if self.chunking.overlap >= self.chunking.size {
+            return invalid(format!(
+                "chunking.overlap ({}) must be smaller than chunking.size ({})",
+                self.chunking.overlap, self.chunking.size
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn embedding_config(&self) -> EmbeddingConfig {
+        let dimension = self
+            .embedding
+            .dimension
+            .unwrap_or(DEFAULT_EMBEDDING_DIMENSION);
+        EmbeddingConfig::new(&self.embedding.model, dimension)
+    }
+
+    pub fn chunk_config(&self) -> ChunkConfig {
+        ChunkConfig::new(self.chunking.size, self.chunking.overlap)
+    }
+
+    /// Embedding client: Ollama (at `llm.ollama_url`) for `ollama::` models, Gemini otherwise.
+    pub fn embedder(&self) -> Arc<dyn Llm> {
+        let embedding = self.embedding_config();
+        if embedding.is_local() {
+            let model = embedding.model.trim_start_matches("ollama::");
+            Arc::new(ollama_client(model, self.llm.ollama_url.as_deref()).with_embedding(embedding))
+        } else {
+            Arc::new(GeminiClient::new(MODEL_CHAT_FAST).with_embedding(embedding))
+        }
+    }
+}
+
+fn ollama_client(model: &str, url: Option<&str>) -> OllamaClient {
+    let client = OllamaClient::new(model);
+    match url {
+        Some(url) => client.with_base_url(url),
+        None => client,
+    }
+}
+
+/// Gemini clients with an optional Ollama fallback (`OLLAMA_MODEL` at `OLLAMA_URL`), sharing one embedder.
+#[derive(Clone)]
+pub struct LlmTiers {
+    ollama_model: Option<String>,
+    ollama_url: Option<String>,
+    embedder: Arc<dyn Llm>,
+}
+
+impl LlmTiers {
+    pub fn new(llm: &LlmSettings, embedder: Arc<dyn Llm>) -> Self {
+        Self {
+            ollama_model: llm.ollama_model.clone(),
+            ollama_url: llm.ollama_url.clone(),
+            embedder,
+        }
+    }
+
+    pub fn build(&self, model_gemini: &str, temperature: Option<f64>) -> Arc<dyn Llm> {
+        let primary = Box::new(
+            GeminiClient::new(model_gemini)
+                .with_temperature(temperature.unwrap_or(DEFAULT_TEMPERATURE)),
+        );
+
+        let secondary: Option<Box<dyn Llm>> = self.ollama_model.as_ref().map(|m| {
+            let client = ollama_client(m, self.ollama_url.as_deref());
+            let client = match temperature {
+                Some(t) => client.with_temperature(t),
+                None => client,
+            };
+            Box::new(client) as Box<dyn Llm>
+        });
+
+        Arc::new(TieredLlmClient::new(primary, secondary).with_embedder(self.embedder.clone()))
+    }
+}
+
+/// The swarm and everything it runs on.
+/// Shared by `sensei-server`, `sensei-mcp` and embedders so all of them serve the same agents.
+pub struct SenseiRuntime {
+    pub config: RuntimeConfig,
+    pub orchestrator: Arc<Orchestrator>,
+    pub router: Arc<RouterAgent>,
+    pub memory: MemoryStore,
+    pub fast_llm: Arc<dyn Llm>,
+    pub smart_llm: Arc<dyn Llm>,
+    /// Tools the agents may run.
+    pub tools: ToolRegistry,
+    /// How ingested documents are split before embedding.
+    pub chunking: ChunkConfig,
+    /// Applies `prompts.yaml` and `mcp_settings.json`; the swarm is already loaded.
+    pub reloader: Arc<Reloader>,
+}
+
+impl SenseiRuntime {
+    pub fn builder(config: RuntimeConfig) -> SenseiRuntimeBuilder {
+        SenseiRuntimeBuilder {
+            config,
+            llms: None,
+            memory: None,
+            tools: ToolRegistry::builtin(),
+            mcp_env: HashMap::new(),
+        }
+    }
+
+    /// [`RuntimeConfig::from_env`] with the Gemini clients, the way both binaries run.
+    pub async fn from_env() -> Result<Self, SenseiError> {
+        Self::builder(RuntimeConfig::from_env()?).build().await
+    }
+}
+
+/// Assembles a [`SenseiRuntime`]; parts can be replaced, e.g. by test doubles.
+pub struct SenseiRuntimeBuilder {
+    config: RuntimeConfig,
+    llms: Option<(Arc<dyn Llm>, Arc<dyn Llm>)>,
+    memory: Option<MemoryStore>,
+    tools: ToolRegistry,
+    mcp_env: HashMap<String, String>,
+}
+
+impl SenseiRuntimeBuilder {
+    /// Use these clients for the fast and smart tiers instead of Gemini.
+    /// Agents declaring their own model or temperature get the client of their tier.
+    pub fn with_llms(mut self, fast: Arc<dyn Llm>, smart: Arc<dyn Llm>) -> Self {
+        self.llms = Some((fast, smart));
+        self
+    }
+
+    /// Use this store instead of connecting to `database_url`.
+    pub fn with_memory(mut self, memory: MemoryStore) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Tools available to the agents (default: the built-in ones).
+    pub fn with_tools(mut self, tools: ToolRegistry) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// Set `name` in the environment of the MCP servers the swarm spawns.
+    pub fn with_mcp_env(mut self, name: &str, value: &str) -> Self {
+        self.mcp_env.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub async fn build(self) -> Result<SenseiRuntime, SenseiError> {
+        let config = self.config;
+        config.validate()?;
+
+        // 1. Init LLM Clients
+        let embedding = config.embedding_config();
+        let (fast_llm, smart_llm, tiers) = match self.llms {
+            Some((fast, smart)) => (fast, smart, None),
+            None => {
+                env::var("GEMINI_API_KEY")
+                    .map_err(|_| SenseiError::Config("GEMINI_API_KEY must be set".to_string()))?;
+                let embedder = config.embedder();
+                info!(
+                    "🧮 Embedding model: {} ({} dimensions)",
+                    embedding.model, embedding.dimension
+                );
+                if let Some(ref m) = config.llm.ollama_model {
+                    info!("🦙 Ollama fallback enabled with model: {}", m);
+                }
+                let tiers = LlmTiers::new(&config.llm, embedder);
+
+                // ⚡ Fast Tier
+                let fast_llm = tiers.build(&config.llm.fast_model, None);
+                // 🧠 Smart Tier
+                let smart_llm = tiers.build(&config.llm.smart_model, None);
+                (fast_llm, smart_llm, Some(tiers))
+            }
+        };
+
+        // 2. Init Memory
+        let memory = match self.memory {
+            Some(memory) => memory,
+            None => {
+                info!("📦 Connecting to database: {}", config.database_url);
+                MemoryStore::new(&config.database_url).await?
+            }
+        };
+        let search = SearchConfig {
+            max_distance: config.rag.max_distance,
+            min_bm25: config.rag.min_bm25,
+            ..SearchConfig::default()
+        };
+        let memory = memory
+            .with_search_config(search)
+            .with_embedding(embedding.clone());
+
+        memory.migrate().await?;
+
+        let index = memory.vector_index_status().await?;
+        if !index.resize.is_empty() {
+            return Err(SenseiError::Config(format!(
+                "The vector tables do not match the {}-dimensional embedding model '{}'. Run `sensei-server reembed`.",
+                embedding.dimension, embedding.model
+            )));
+        }
+        if !index.is_current() {
+            warn!(
+                "⚠️ {} documents and {} router cache entries were embedded with another model and are ignored until `sensei-server reembed` runs.",
+                index.stale_documents, index.stale_router_cache
+            );
+        }
+
+        // 2.5 Conversation Context (token-budgeted session history)
+        let mut context = ContextWindow::new(memory.clone(), config.context.tokens);
+        if config.context.summary {
+            info!(
+                "📝 Summarizing older conversation turns (budget: {} tokens)",
+                config.context.tokens
+            );
+            context = context.with_summarizer(fast_llm.clone());
+        }
+
+        // 2.6 Ingestion chunking
+        let chunking = config.chunk_config();
+
+        // 3. Init Swarm: agents declared in prompts.yaml (on top of the built-in ones) and MCP agents
+        let orchestrator = Arc::new(Orchestrator::new().with_context(context));
+        let router = Arc::new(RouterAgent::new(fast_llm.clone(), Some(memory.clone()), ""));
+
+        let llm_for: LlmFactory = {
+            let (fast_llm, smart_llm) = (fast_llm.clone(), smart_llm.clone());
+            let models = config.llm.clone();
+            Arc::new(move |definition: &AgentDefinition| {
+                let tier_model = match definition.tier {
+                    LlmTier::Fast => &models.fast_model,
+                    LlmTier::Smart => &models.smart_model,
+                };
+                match (&tiers, &definition.model, definition.temperature) {
+                    (Some(tiers), model, temperature)
+                        if model.is_some() || temperature.is_some() =>
+                    {
+                        tiers.build(model.as_deref().unwrap_or(tier_model), temperature)
+                    }
+                    _ => match definition.tier {
+                        LlmTier::Fast => fast_llm.clone(),
+                        LlmTier::Smart => smart_llm.clone(),
+                    },
+                }
+            })
+        };
+        let reloader = Arc::new(Reloader::new(
+            SwarmConfig {
+                prompts_path: config.prompts_path.clone(),
+                mcp_path: config.mcp_config.clone(),
+                llm_for,
+                mcp_llm: fast_llm.clone(),
+                tools: self.tools.clone(),
+                react_steps: config.react_max_steps,
+                mcp_backoff: (DEFAULT_RESTART_BACKOFF, DEFAULT_MAX_RESTART_BACKOFF),
+                knowledge: Some(Arc::new(KnowledgeBase {
+                    memory: memory.clone(),
+                    llm: smart_llm.clone(),
+                    chunking,
+                })),
+                mcp_env: self.mcp_env,
+            },
+            orchestrator.clone(),
+            router.clone(),
+        ));
+        let swarm = reloader
+            .reload()
+            .await
+            .map_err(|e| SenseiError::Config(format!("Invalid agent configuration: {}", e)))?;
+        info!("🤖 Swarm: {}", swarm.added.join(", "));
+
+        Ok(SenseiRuntime {
+            config,
+            orchestrator,
+            router,
+            memory,
+            fast_llm,
+            smart_llm,
+            tools: self.tools,
+            chunking,
+            reloader,
+        })
+    }
+}
@@ -1,12 +1,67 @@
 use crate::errors::SenseiError;
 use async_trait::async_trait;
+use serde_json::{Value, json};
 
 pub mod nmap;
+pub mod registry;
 pub mod system;
 
+pub use registry::{ToolInvocation, ToolRegistry, ToolSpec, select_tool};
+
 #[async_trait]
 /// Trait for defining executable tools.
 pub trait Tool: Send + Sync {
     fn name(&self) -> &str;
+
+    /// What the tool does, shown to the LLM when it picks a tool.
+    fn description(&self) -> &str {
+        ""
+    }
+
+    /// JSON Schema of the arguments accepted by `call`.
+    /// Defaults to a single free-form `argument` string, matching `execute`.
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "argument": { "type": "string" }
+            },
+            "required": ["argument"]
+        })
+    }
+
     async fn execute(&self, args: &str) -> Result<String, SenseiError>;
+
+    /// Run the tool with structured arguments matching `input_schema`.
+    async fn call(&self, arguments: &Value) -> Result<String, SenseiError> {
+        self.execute(&string_argument(arguments, "argument")).await
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().to_string(),
+            description: self.description().to_string(),
+            input_schema: self.input_schema(),
+        }
+    }
+}
+
+/// Extract a string argument from structured tool arguments.
+/// Accepts a bare string, the `key` field of an object, or the only field of an object.
+pub fn string_argument(arguments: &Value, key: &str) -> String {
+    let value = match arguments {
+        Value::Object(map) => match map.get(key) {
+            Some(v) => Some(v),
+            None if map.len() == 1 => map.values().next(),
+            None => None,
+        },
+        Value::Null => None,
+        other => Some(other),
+    };
+
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
 }
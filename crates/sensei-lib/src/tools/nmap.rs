@@ -1,34 +1,340 @@
 use crate::errors::SenseiError;
 use crate::tools::Tool;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 use std::env;
-use std::process::Command;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// NSE scripts that only gather information (no brute force, no exploitation, no DoS).
+pub const SAFE_SCRIPTS: &[&str] = &[
+    "banner",
+    "dns-nsid",
+    "ftp-anon",
+    "http-headers",
+    "http-methods",
+    "http-server-header",
+    "http-title",
+    "smb-os-discovery",
+    "ssh-hostkey",
+    "ssh2-enum-algos",
+    "ssl-cert",
+    "ssl-enum-ciphers",
+];
+
+/// Broadest IPv4 range a single scan may cover (a /16, 65536 addresses).
+pub const MIN_IPV4_PREFIX: u8 = 16;
+/// Broadest IPv6 range a single scan may cover (one /64 subnet).
+pub const MIN_IPV6_PREFIX: u8 = 64;
+/// How long a scan may run before nmap is killed.
+pub const DEFAULT_SCAN_TIMEOUT: Duration = Duration::from_secs(600);
 
 pub struct NmapTool;
 
-#[async_trait]
-impl Tool for NmapTool {
-    fn name(&self) -> &str {
-        "nmap"
-    }
+/// Typed arguments of the nmap tool.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct NmapArgs {
+    pub target: String,
+    /// Port list/ranges such as "22,80,8000-8100". Defaults to nmap's fast scan (top 100 ports).
+    #[serde(default)]
+    pub ports: Option<String>,
+    /// Probe open ports for service/version info (`-sV`).
+    #[serde(default)]
+    pub service_detection: bool,
+    /// Timing template 0 (paranoid) to 5 (insane).
+    #[serde(default)]
+    pub timing: Option<u8>,
+    /// NSE scripts to run, restricted to `SAFE_SCRIPTS`.
+    #[serde(default)]
+    pub scripts: Vec<String>,
+}
 
-    async fn execute(&self, target: &str) -> Result<String, SenseiError> {
-        // Basic input sanitization
-        if target.contains(';')
-            || target.contains('&')
-            || target.contains('|')
-            || target.contains('$')
-            || target.contains('`')
-            || target.contains('"')
-            || target.contains('\'')
-            || target.contains('(')
-            || target.contains(')')
+impl NmapArgs {
+    /// Validate everything that ends up on the nmap command line.
+    pub fn validate(&self) -> Result<(), SenseiError> {
+        validate_target(&self.target)?;
+
+        if let Some(ports) = &self.ports {
+            validate_ports(ports)?;
+        }
+
+        if let Some(timing) = self.timing
+            && timing > 5
         {
-            return Err(SenseiError::Tool(
-                "Invalid characters in target name. Please provide a valid hostname or IP address."
-                    .to_string(),
-            ));
+            return Err(SenseiError::Tool(format!(
+                "Invalid timing template T{}. Allowed: 0-5.",
+                timing
+            )));
+        }
+
+        for script in &self.scripts {
+            if !SAFE_SCRIPTS.contains(&script.as_str()) {
+                return Err(SenseiError::Tool(format!(
+                    "NSE script '{}' is not allowed. Allowed: {}",
+                    script,
+                    SAFE_SCRIPTS.join(", ")
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Command line arguments, XML report on stdout.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        match &self.ports {
+            Some(ports) => args.extend(["-p".to_string(), ports.clone()]),
+            None => args.push("-F".to_string()), // Fast scan
+        }
+        if self.service_detection {
+            args.push("-sV".to_string());
         }
+        if let Some(timing) = self.timing {
+            args.push(format!("-T{}", timing));
+        }
+        if !self.scripts.is_empty() {
+            args.push(format!("--script={}", self.scripts.join(",")));
+        }
+
+        args.extend(["-oX".to_string(), "-".to_string()]);
+        // "--" keeps the target from ever being read as an option.
+        args.extend(["--".to_string(), self.target.clone()]);
+        args
+    }
+}
+
+fn validate_target(target: &str) -> Result<(), SenseiError> {
+    // Basic input sanitization
+    if target.contains(';')
+        || target.contains('&')
+        || target.contains('|')
+        || target.contains('$')
+        || target.contains('`')
+        || target.contains('"')
+        || target.contains('\'')
+        || target.contains('(')
+        || target.contains(')')
+        || target.chars().any(char::is_whitespace)
+    {
+        return Err(SenseiError::Tool(
+            "Invalid characters in target name. Please provide a valid hostname or IP address."
+                .to_string(),
+        ));
+    }
+
+    let valid = match target.split_once('/') {
+        Some((ip, prefix)) => match (ip.parse::<IpAddr>(), prefix.parse::<u8>()) {
+            (Ok(IpAddr::V4(_)), Ok(p)) if p <= 32 => {
+                return check_range_size(target, p, MIN_IPV4_PREFIX);
+            }
+            (Ok(IpAddr::V6(_)), Ok(p)) if p <= 128 => {
+                return check_range_size(target, p, MIN_IPV6_PREFIX);
+            }
+            _ => false,
+        },
+        None => target.parse::<IpAddr>().is_ok() || is_hostname(target),
+    };
+
+    if !valid {
+        return Err(SenseiError::Tool(format!(
+            "Invalid target '{}'. Expected an IP address, a CIDR range or a hostname.",
+            target
+        )));
+    }
+    Ok(())
+}
+
+fn check_range_size(target: &str, prefix: u8, min_prefix: u8) -> Result<(), SenseiError> {
+    if prefix < min_prefix {
+        return Err(SenseiError::Tool(format!(
+            "Target range '{}' is too broad. Use a /{} or narrower prefix.",
+            target, min_prefix
+        )));
+    }
+    Ok(())
+}
+
+fn is_hostname(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+fn validate_ports(ports: &str) -> Result<(), SenseiError> {
+    let invalid = || {
+        SenseiError::Tool(format!(
+            "Invalid port specification '{}'. Use ports and ranges like \"22,80,8000-8100\".",
+            ports
+        ))
+    };
+    let port = |p: &str| p.parse::<u16>().ok().filter(|p| *p > 0);
+
+    for part in ports.split(',') {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (
+                    port(start).ok_or_else(invalid)?,
+                    port(end).ok_or_else(invalid)?,
+                );
+                if start > end {
+                    return Err(invalid());
+                }
+            }
+            None => {
+                port(part).ok_or_else(invalid)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// --- Structured report ---
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NmapReport {
+    pub hosts: Vec<NmapHost>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NmapHost {
+    pub address: String,
+    pub hostnames: Vec<String>,
+    pub status: String,
+    pub ports: Vec<NmapPort>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NmapPort {
+    pub port: u16,
+    pub protocol: String,
+    pub state: String,
+    pub service: Option<NmapService>,
+    pub scripts: Vec<NmapScript>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NmapService {
+    pub name: String,
+    pub product: Option<String>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NmapScript {
+    pub id: String,
+    pub output: String,
+}
+
+/// Parse the XML report written by `nmap -oX`.
+pub fn parse_report(xml: &str) -> Result<NmapReport, SenseiError> {
+    // nmap always emits `<!DOCTYPE nmaprun>`.
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let doc = roxmltree::Document::parse_with_options(xml, options)
+        .map_err(|e| SenseiError::Tool(format!("Failed to parse nmap XML output: {}", e)))?;
+
+    let hosts = doc
+        .descendants()
+        .filter(|n| n.has_tag_name("host"))
+        .map(|host| {
+            let child = |name: &str| host.children().find(|n| n.has_tag_name(name));
+
+            let address = host
+                .children()
+                .filter(|n| n.has_tag_name("address"))
+                .find(|n| n.attribute("addrtype") != Some("mac"))
+                .and_then(|n| n.attribute("addr"))
+                .unwrap_or_default()
+                .to_string();
+
+            let hostnames = child("hostnames")
+                .map(|names| {
+                    names
+                        .children()
+                        .filter(|n| n.has_tag_name("hostname"))
+                        .filter_map(|n| n.attribute("name").map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            let status = child("status")
+                .and_then(|n| n.attribute("state"))
+                .unwrap_or("unknown")
+                .to_string();
+
+            let ports = child("ports")
+                .map(|ports| {
+                    ports
+                        .children()
+                        .filter(|n| n.has_tag_name("port"))
+                        .filter_map(parse_port)
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            NmapHost {
+                address,
+                hostnames,
+                status,
+                ports,
+            }
+        })
+        .collect();
+
+    Ok(NmapReport { hosts })
+}
+
+fn parse_port(node: roxmltree::Node) -> Option<NmapPort> {
+    let child = |name: &str| node.children().find(|n| n.has_tag_name(name));
+
+    Some(NmapPort {
+        port: node.attribute("portid")?.parse().ok()?,
+        protocol: node.attribute("protocol").unwrap_or("tcp").to_string(),
+        state: child("state")
+            .and_then(|n| n.attribute("state"))
+            .unwrap_or("unknown")
+            .to_string(),
+        service: child("service").map(|n| NmapService {
+            name: n.attribute("name").unwrap_or("unknown").to_string(),
+            product: n.attribute("product").map(str::to_string),
+            version: n.attribute("version").map(str::to_string),
+        }),
+        scripts: node
+            .children()
+            .filter(|n| n.has_tag_name("script"))
+            .map(|n| NmapScript {
+                id: n.attribute("id").unwrap_or_default().to_string(),
+                output: n.attribute("output").unwrap_or_default().trim().to_string(),
+            })
+            .collect(),
+    })
+}
+
+impl NmapTool {
+    /// Run a validated scan and return the parsed report.
+    pub async fn scan(&self, args: &NmapArgs) -> Result<NmapReport, SenseiError> {
+        self.scan_within(args, DEFAULT_SCAN_TIMEOUT).await
+    }
+
+    /// Like `scan`, but kills nmap once it has run for `timeout`.
+    pub async fn scan_within(
+        &self,
+        args: &NmapArgs,
+        timeout: Duration,
+    ) -> Result<NmapReport, SenseiError> {
+        args.validate()?;
 
         // Check if nmap is available in PATH or use provided path
         let nmap_path = match env::var("SYSTEM_NMAPPATH") {
@@ -36,11 +342,14 @@ impl Tool for NmapTool {
             Err(_) => "nmap".to_string(), // Default to looking in PATH
         };
 
-        // Execute nmap command
-        let output = Command::new(nmap_path)
-            .arg("-F") // Fast scan
-            .arg(target)
-            .output()
+        // Dropping the future on timeout kills the child instead of leaving it running.
+        let scan = Command::new(nmap_path)
+            .args(args.to_args())
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(timeout, scan)
+            .await
+            .map_err(|_| SenseiError::Tool(format!("Nmap scan timed out after {:?}", timeout)))?
             .map_err(|e| SenseiError::Tool(format!("Failed to execute nmap command: {}", e)))?;
 
         if !output.status.success() {
@@ -51,6 +360,68 @@ impl Tool for NmapTool {
             )));
         }
 
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        parse_report(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+#[async_trait]
+impl Tool for NmapTool {
+    fn name(&self) -> &str {
+        "nmap"
+    }
+
+    fn description(&self) -> &str {
+        "Scan a target for open ports and services. Returns hosts, ports and services as JSON."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "target": {
+                    "type": "string",
+                    "description": "IP address, CIDR range or hostname, e.g. 127.0.0.1 or 10.0.0.0/24"
+                },
+                "ports": {
+                    "type": "string",
+                    "description": "Ports and ranges, e.g. \"22,80,8000-8100\". Omit for a fast scan of the top 100 ports."
+                },
+                "service_detection": {
+                    "type": "boolean",
+                    "description": "Detect service versions (-sV)"
+                },
+                "timing": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "maximum": 5,
+                    "description": "Timing template (-T0 to -T5)"
+                },
+                "scripts": {
+                    "type": "array",
+                    "items": { "type": "string", "enum": SAFE_SCRIPTS },
+                    "description": "Safe NSE scripts to run"
+                }
+            },
+            "required": ["target"]
+        })
+    }
+
+    async fn execute(&self, target: &str) -> Result<String, SenseiError> {
+        self.call(&json!({ "target": target.trim() })).await
+    }
+
+    async fn call(&self, arguments: &Value) -> Result<String, SenseiError> {
+        let args = match arguments {
+            Value::String(target) => NmapArgs {
+                target: target.clone(),
+                ..Default::default()
+            },
+            other => serde_json::from_value(other.clone())
+                .map_err(|e| SenseiError::Tool(format!("Invalid nmap arguments: {}", e)))?,
+        };
+
+        let report = self.scan(&args).await?;
+        serde_json::to_string_pretty(&report)
+            .map_err(|e| SenseiError::Tool(format!("Failed to encode nmap report: {}", e)))
     }
 }
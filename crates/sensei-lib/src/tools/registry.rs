@@ -0,0 +1,178 @@
+use crate::llm::Llm;
+use crate::tools::Tool;
+use crate::tools::nmap::NmapTool;
+use crate::tools::system::SystemTool;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Name, description and input schema of a tool, as advertised to LLMs and MCP clients.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolSpec {
+    pub name: String,
+    pub description: String,
+    #[serde(alias = "input_schema")]
+    pub input_schema: Value,
+}
+
+/// A tool picked by the LLM together with its arguments.
+/// `tool_name == "none"` means no tool fits the request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolInvocation {
+    pub tool_name: String,
+    pub arguments: Value,
+}
+
+impl ToolInvocation {
+    pub fn none(reason: impl Into<String>) -> Self {
+        Self {
+            tool_name: "none".to_string(),
+            arguments: json!({ "reason": reason.into() }),
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.tool_name == "none"
+    }
+
+    /// Why no tool was picked, as explained by the LLM.
+    pub fn reason(&self) -> String {
+        super::string_argument(&self.arguments, "reason")
+    }
+}
+
+/// Central catalogue of the tools agents may run.
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: BTreeMap<String, Arc<dyn Tool>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with the tools shipped with Sensei.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(Arc::new(NmapTool));
+        registry.register(Arc::new(SystemTool));
+        registry
+    }
+
+    /// Add a tool, replacing any tool with the same name.
+    pub fn register(&mut self, tool: Arc<dyn Tool>) {
+        self.tools.insert(tool.name().to_string(), tool);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.tools.get(name).cloned()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.tools.keys().cloned().collect()
+    }
+
+    pub fn specs(&self) -> Vec<ToolSpec> {
+        self.tools.values().map(|t| t.spec()).collect()
+    }
+
+    /// A registry limited to the named tools; unknown names are ignored.
+    pub fn subset(&self, names: &[&str]) -> Self {
+        Self {
+            tools: self
+                .tools
+                .iter()
+                .filter(|(name, _)| names.contains(&name.as_str()))
+                .map(|(name, tool)| (name.clone(), tool.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tools.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+}
+
+#[derive(Deserialize)]
+struct PromptedInvocation {
+    tool_name: String,
+    #[serde(default)]
+    arguments: Value,
+    // Older prompts asked for a single string argument.
+    #[serde(default)]
+    argument: Option<Value>,
+}
+
+/// Let the LLM pick a tool of `registry` for `request`.
+///
+/// Uses native function calling when the provider supports it, and falls back to
+/// asking for a JSON answer otherwise. `role` introduces the agent in the prompt.
+pub async fn select_tool(
+    llm: &dyn Llm,
+    registry: &ToolRegistry,
+    role: &str,
+    request: &str,
+) -> Option<ToolInvocation> {
+    let specs = registry.specs();
+
+    match llm
+        .select_tool(&format!("{}\n\n{}", role, request), &specs)
+        .await
+    {
+        Ok(Some(invocation)) => return Some(invocation),
+        Ok(None) => return Some(ToolInvocation::none("No tool matches this request.")),
+        Err(e) => println!(
+            "🔧 Native tool calling unavailable ({}), prompting instead.",
+            e
+        ),
+    }
+
+    let tools_desc = serde_json::to_string_pretty(&specs).unwrap_or_default();
+    let prompt = format!(
+        r#"
+            {}
+
+            Available Tools (JSON Schema):
+            {}
+
+            Task: Analyze the user request and decide which tool to execute.
+            User Request: "{}"
+
+            Rules:
+            - If the request matches a tool, output strictly JSON: {{ "tool_name": "name", "arguments": {{ ... }} }}
+              where "arguments" follows the tool's input_schema.
+            - If NO tool matches or arguments are ambiguous, output strictly JSON: {{ "tool_name": "none", "arguments": {{ "reason": "..." }} }}
+
+            Output strictly JSON.
+            "#,
+        role, tools_desc, request
+    );
+
+    let response = llm.generate(&prompt).await.ok()?;
+
+    // Robust JSON extraction: the outermost object, if the answer has one
+    let start = response.find('{')?;
+    let end = start + response[start..].rfind('}')? + 1;
+    let parsed = serde_json::from_str::<PromptedInvocation>(&response[start..end]).ok()?;
+
+    let arguments = match (parsed.arguments, parsed.argument) {
+        (Value::Null, Some(argument)) if parsed.tool_name == "none" => {
+            json!({ "reason": argument })
+        }
+        (Value::Null, Some(argument)) => argument,
+        (Value::Null, None) => json!({}),
+        (arguments, _) => arguments,
+    };
+
+    Some(ToolInvocation {
+        tool_name: parsed.tool_name,
+        arguments,
+    })
+}
@@ -1,6 +1,7 @@
 use crate::errors::SenseiError;
-use crate::tools::Tool;
+use crate::tools::{Tool, string_argument};
 use async_trait::async_trait;
+use serde_json::{Value, json};
 use std::process::Command;
 
 pub struct SystemTool;
@@ -11,6 +12,28 @@ impl Tool for SystemTool {
         "system_diagnostic"
     }
 
+    fn description(&self) -> &str {
+        "Run system checks (uptime, disk, memory, whoami, date)."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "enum": ["uptime", "disk", "memory", "whoami", "date"],
+                    "description": "Diagnostic command to run"
+                }
+            },
+            "required": ["command"]
+        })
+    }
+
+    async fn call(&self, arguments: &Value) -> Result<String, SenseiError> {
+        self.execute(&string_argument(arguments, "command")).await
+    }
+
     async fn execute(&self, command_key: &str) -> Result<String, SenseiError> {
         // Strict allowlist of diagnostic commands
         let (cmd, args) = match command_key.trim() {
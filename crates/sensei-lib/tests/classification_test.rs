@@ -0,0 +1,166 @@
+use sensei_common::{AgentCategory, Classification};
+use sensei_lib::agents::Orchestrator;
+use sensei_lib::agents::specialists::SpecializedAgent;
+use sensei_lib::llm::GeminiClient;
+use sensei_lib::memory::{DocumentMetadata, MemoryStore};
+use std::sync::Arc;
+
+/// Embedding at distance `d` from the zero vector.
+fn at_distance(d: f32) -> Vec<f32> {
+    let mut v = vec![0.0; 3072];
+    v[0] = d;
+    v
+}
+
+async fn store_with_all_levels() -> MemoryStore {
+    let store = MemoryStore::new("sqlite::memory:").await.unwrap();
+    store.migrate().await.unwrap();
+
+    // The more sensitive a document, the closer it is to the query.
+    for (content, level, d) in [
+        ("top secret doc", Classification::TopSecret, 0.1),
+        ("secret doc", Classification::Secret, 0.2),
+        ("confidential doc", Classification::Confidential, 0.3),
+        ("public doc", Classification::Unclassified, 0.4),
+    ] {
+        store
+            .add_classified_document(content, at_distance(d), level)
+            .await
+            .unwrap();
+    }
+    store
+}
+
+#[test]
+fn levels_are_ordered_and_parsed() {
+    assert!(Classification::TopSecret > Classification::Secret);
+    assert!(Classification::Secret.dominates(Classification::Confidential));
+    assert!(!Classification::Unclassified.dominates(Classification::Confidential));
+    assert_eq!(
+        "Top Secret".parse::<Classification>().unwrap(),
+        Classification::TopSecret
+    );
+    assert_eq!(Classification::TopSecret.to_string(), "TOP SECRET");
+    assert!("cosmic".parse::<Classification>().is_err());
+}
+
+#[tokio::test]
+async fn search_enforces_no_read_up_and_audits_denials() {
+    let store = store_with_all_levels().await;
+
+    let results = store
+        .search_documents(
+            "",
+            at_distance(0.0),
+            1,
+            Classification::Unclassified,
+            "agent:casual",
+        )
+        .await
+        .unwrap();
+    let results: Vec<String> = results.into_iter().map(|hit| hit.content).collect();
+    assert_eq!(results, vec!["public doc".to_string()]);
+
+    let denials = store.list_access_denials(10).await.unwrap();
+    assert_eq!(denials.len(), 3);
+    assert!(denials.iter().all(|d| d.subject == "agent:casual"));
+    assert!(
+        denials
+            .iter()
+            .all(|d| d.clearance == Classification::Unclassified)
+    );
+    assert!(
+        denials
+            .iter()
+            .any(|d| d.classification == Classification::TopSecret)
+    );
+
+    let results = store
+        .search_documents("", at_distance(0.0), 2, Classification::Secret, "agent:red")
+        .await
+        .unwrap();
+    let results: Vec<String> = results.into_iter().map(|hit| hit.content).collect();
+    assert_eq!(
+        results,
+        vec!["secret doc".to_string(), "confidential doc".to_string()]
+    );
+    let denials = store.list_access_denials(1).await.unwrap();
+    assert_eq!(denials[0].subject, "agent:red");
+    assert_eq!(denials[0].classification, Classification::TopSecret);
+}
+
+#[tokio::test]
+async fn unlabelled_documents_are_unclassified() {
+    let store = MemoryStore::new("sqlite::memory:").await.unwrap();
+    store.migrate().await.unwrap();
+    store
+        .add_document("legacy doc", at_distance(0.1))
+        .await
+        .unwrap();
+
+    let results = store
+        .search_documents(
+            "",
+            at_distance(0.0),
+            3,
+            Classification::Unclassified,
+            "agent:casual",
+        )
+        .await
+        .unwrap();
+    let results: Vec<String> = results.into_iter().map(|hit| hit.content).collect();
+    assert_eq!(results, vec!["legacy doc".to_string()]);
+    assert!(store.list_access_denials(10).await.unwrap().is_empty());
+}
+
+#[test]
+fn unreadable_labels_are_top_secret() {
+    let label = |metadata: Option<&str>| {
+        DocumentMetadata::parse(metadata.map(str::to_string)).classification
+    };
+
+    assert_eq!(label(None), Classification::Unclassified);
+    assert_eq!(label(Some("{}")), Classification::Unclassified);
+    assert_eq!(
+        label(Some(r#"{"classification":"secret"}"#)),
+        Classification::Secret
+    );
+    assert_eq!(
+        label(Some(r#"{"classification":"cosmic","source":"a.md"}"#)),
+        Classification::TopSecret
+    );
+    assert_eq!(label(Some("{not json")), Classification::TopSecret);
+}
+
+#[tokio::test]
+async fn orchestrator_reports_agent_clearance() {
+    let llm = Arc::new(GeminiClient::new("dummy"));
+    let orchestrator = Orchestrator::new();
+    orchestrator
+        .register(Box::new(
+            SpecializedAgent::new(llm.clone(), AgentCategory::new("red"), "SYSTEM", None)
+                .with_clearance(Classification::Secret),
+        ))
+        .await;
+    orchestrator
+        .register(Box::new(SpecializedAgent::new(
+            llm,
+            AgentCategory::new("casual"),
+            "SYSTEM",
+            None,
+        )))
+        .await;
+
+    assert_eq!(
+        orchestrator.clearance(&AgentCategory::new("red")).await,
+        Classification::Secret
+    );
+    assert_eq!(
+        orchestrator.clearance(&AgentCategory::new("casual")).await,
+        Classification::Unclassified
+    );
+    assert_eq!(
+        orchestrator.clearance(&AgentCategory::new("unknown")).await,
+        Classification::Unclassified
+    );
+}
@@ -1,4 +1,9 @@
-use sensei_lib::config::load_prompts;
+use sensei_common::{AgentCategory, Classification};
+use sensei_lib::config::{
+    AgentDefinition, LlmTier, PromptsConfig, RemoteTransport, load_mcp_settings, load_prompts,
+};
+use sensei_lib::errors::SenseiError;
+use std::collections::HashMap;
 use std::io::Write;
 use tempfile::NamedTempFile;
 
@@ -25,3 +30,222 @@ agents:
         "You are Red."
     );
 }
+
+fn parse(yaml: &str) -> PromptsConfig {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+fn find<'a>(definitions: &'a [AgentDefinition], category: &str) -> &'a AgentDefinition {
+    definitions
+        .iter()
+        .find(|d| d.category == AgentCategory::new(category))
+        .unwrap()
+}
+
+#[test]
+fn builtin_swarm_is_used_without_a_prompts_file() {
+    let definitions = PromptsConfig::default().agent_definitions().unwrap();
+    let categories: Vec<&str> = definitions.iter().map(|d| d.category.as_str()).collect();
+    assert_eq!(
+        categories,
+        [
+            "action", "blue", "casual", "cloud", "crypto", "novice", "osint", "red", "system"
+        ]
+    );
+
+    let red = find(&definitions, "red");
+    assert_eq!(red.tier, LlmTier::Smart);
+    assert_eq!(red.clearance, Classification::Secret);
+    assert_eq!(
+        red.master_prompt.as_deref(),
+        Some("SYSTEM: You are SENSEI.")
+    );
+    assert!(red.rag);
+
+    let system = find(&definitions, "system");
+    assert_eq!(system.tools, ["system_diagnostic"]);
+    assert_eq!(system.clearance, Classification::Unclassified);
+}
+
+#[test]
+fn entries_override_builtins_and_declare_new_agents() {
+    let config = parse(
+        r#"
+agents:
+  router:
+    prompt: "You are a Router."
+  master:
+    prompt: "You are the Master."
+  red_team:
+    temperature: 0.2
+  casual:
+    prompt: "You are Casual."
+    model: "gemini-2.5-flash-lite"
+  forensics:
+    prompt: "You are a Forensics Examiner."
+    tier: smart
+    tools: [system_diagnostic]
+    rag: false
+    clearance: top_secret
+"#,
+    );
+    let definitions = config.agent_definitions().unwrap();
+    assert_eq!(definitions.len(), 10);
+    assert!(
+        definitions
+            .iter()
+            .all(|d| d.category.as_str() != "router" && d.category.as_str() != "master")
+    );
+
+    // Unset fields keep the built-in values.
+    let red = find(&definitions, "red");
+    assert_eq!(red.temperature, Some(0.2));
+    assert_eq!(red.prompt, "SYSTEM: You are a Red Team Operator.");
+    assert_eq!(red.master_prompt.as_deref(), Some("You are the Master."));
+    assert_eq!(red.tier, LlmTier::Smart);
+
+    let casual = find(&definitions, "casual");
+    assert_eq!(casual.prompt, "You are Casual.");
+    assert_eq!(casual.model.as_deref(), Some("gemini-2.5-flash-lite"));
+
+    let forensics = find(&definitions, "forensics");
+    assert_eq!(forensics.tier, LlmTier::Smart);
+    assert_eq!(forensics.tools, ["system_diagnostic"]);
+    assert!(!forensics.rag);
+    assert_eq!(forensics.clearance, Classification::TopSecret);
+    assert_eq!(forensics.master_prompt, None);
+}
+
+#[test]
+fn builtins_can_be_overridden_by_category() {
+    let config = parse(
+        r#"
+agents:
+  red:
+    prompt: "You are Red."
+"#,
+    );
+    let definitions = config.agent_definitions().unwrap();
+    assert_eq!(definitions.len(), 9);
+    assert_eq!(find(&definitions, "red").prompt, "You are Red.");
+}
+
+#[test]
+fn invalid_definitions_are_rejected() {
+    let missing_prompt = parse("agents:\n  forensics:\n    tier: smart\n");
+    assert!(matches!(
+        missing_prompt.agent_definitions(),
+        Err(SenseiError::Config(_))
+    ));
+
+    let duplicate = parse(
+        "agents:\n  a:\n    prompt: A\n    category: dfir\n  b:\n    prompt: B\n    category: DFIR\n",
+    );
+    assert!(matches!(
+        duplicate.agent_definitions(),
+        Err(SenseiError::Config(_))
+    ));
+
+    assert!(serde_yaml::from_str::<PromptsConfig>("agents:\n  a:\n    promt: A\n").is_err());
+}
+
+#[test]
+fn mcp_servers_are_spawned_or_remote() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(
+        file,
+        r#"{{ "mcpServers": {{
+            "local": {{ "command": "mcp-nmap" }},
+            "remote": {{ "url": "https://mcp.example.com/sse", "transport": "sse",
+                         "headers": {{ "Authorization": "Bearer t0ken" }} }},
+            "default": {{ "url": "https://mcp.example.com/mcp" }}
+        }} }}"#
+    )
+    .unwrap();
+    let settings = load_mcp_settings(file.path().to_str().unwrap()).unwrap();
+    let servers = &settings.mcp_servers;
+    assert!(servers["local"].args.is_empty());
+    assert_eq!(servers["remote"].transport, RemoteTransport::Sse);
+    assert_eq!(servers["remote"].headers["Authorization"], "Bearer t0ken");
+    assert_eq!(
+        servers["default"].transport,
+        RemoteTransport::StreamableHttp
+    );
+
+    for invalid in [
+        r#"{ "mcpServers": { "both": { "command": "x", "url": "http://localhost" } } }"#,
+        r#"{ "mcpServers": { "neither": { "args": [] } } }"#,
+    ] {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(invalid.as_bytes()).unwrap();
+        assert!(matches!(
+            load_mcp_settings(file.path().to_str().unwrap()),
+            Err(SenseiError::Config(_))
+        ));
+    }
+}
+
+#[test]
+fn agents_render_their_prompt_template() {
+    let config = parse(
+        r#"
+agents:
+  forensics:
+    prompt: "You are a Forensics Examiner."
+    arguments:
+      - name: evidence
+        required: true
+      - name: window
+    template: |
+      Examine {{ evidence }}.
+      Window: {{window}}
+"#,
+    );
+    let definitions = config.agent_definitions().unwrap();
+    let args = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    };
+
+    let forensics = find(&definitions, "forensics");
+    assert_eq!(
+        forensics
+            .render(&args(&[("evidence", "auth.log"), ("window", "May 3")]))
+            .unwrap(),
+        "You are a Forensics Examiner.\n\nExamine auth.log.\nWindow: May 3"
+    );
+    // The line of the missing optional argument is left out.
+    assert_eq!(
+        forensics
+            .render(&args(&[("evidence", "auth.log")]))
+            .unwrap(),
+        "You are a Forensics Examiner.\n\nExamine auth.log."
+    );
+    for invalid in [
+        args(&[("window", "May 3")]),
+        args(&[("evidence", "a"), ("target", "b")]),
+    ] {
+        assert!(matches!(
+            forensics.render(&invalid),
+            Err(SenseiError::InvalidInput(_))
+        ));
+    }
+
+    // Built-in agents take a target and scope or, without declared arguments, a task.
+    let red = find(&definitions, "red");
+    let names: Vec<&str> = red.arguments.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, ["target", "scope"]);
+    let crypto = find(&definitions, "crypto");
+    assert_eq!(
+        crypto.render(&args(&[("task", "Review AES-CBC")])).unwrap(),
+        "SYSTEM: You are a Cryptographer.\n\nReview AES-CBC"
+    );
+
+    let undeclared = parse("agents:\n  a:\n    prompt: A\n    template: \"{{target}}\"\n");
+    assert!(matches!(
+        undeclared.agent_definitions(),
+        Err(SenseiError::Config(_))
+    ));
+}
@@ -0,0 +1,190 @@
+use async_trait::async_trait;
+use sensei_common::Classification;
+use sensei_lib::context::ContextWindow;
+use sensei_lib::errors::SenseiError;
+use sensei_lib::llm::{ChatRole, ChatTurn, Llm};
+use sensei_lib::memory::MemoryStore;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct SummaryLlm {
+    calls: AtomicUsize,
+}
+
+#[async_trait]
+impl Llm for SummaryLlm {
+    async fn generate(&self, _prompt: &str) -> Result<String, SenseiError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok("The user is Max.".to_string())
+    }
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>, SenseiError> {
+        Ok(vec![])
+    }
+}
+
+async fn seeded_store() -> (MemoryStore, String) {
+    let store = MemoryStore::new("sqlite::memory:").await.unwrap();
+    store.migrate().await.unwrap();
+    let session_id = store.create_session(None).await.unwrap();
+
+    // 40 chars per message = 10 tokens each
+    for (role, content) in [
+        ("user", "My name is Max and I audit the DMZ host."),
+        ("assistant", "Nice to meet you Max, what do you need?"),
+        ("user", "Scan plan for 10.0.0.5 please, quietly."),
+        ("assistant", "Use a slow SYN scan on the top 100 port."),
+    ] {
+        store.add_message(&session_id, role, content).await.unwrap();
+    }
+    (store, session_id)
+}
+
+#[tokio::test]
+async fn context_window_keeps_recent_turns_in_order() {
+    let (store, session_id) = seeded_store().await;
+
+    let window = ContextWindow::new(store, 1000);
+    let history = window
+        .load(&session_id, Classification::Unclassified)
+        .await
+        .unwrap()
+        .turns;
+
+    assert_eq!(history.len(), 4);
+    assert_eq!(
+        history[0],
+        ChatTurn::user("My name is Max and I audit the DMZ host.")
+    );
+    assert_eq!(history[3].role, ChatRole::Assistant);
+}
+
+#[tokio::test]
+async fn context_window_drops_turns_over_budget() {
+    let (store, session_id) = seeded_store().await;
+
+    let window = ContextWindow::new(store, 20);
+    let history = window
+        .load(&session_id, Classification::Unclassified)
+        .await
+        .unwrap()
+        .turns;
+
+    assert_eq!(history.len(), 2);
+    assert_eq!(
+        history[0],
+        ChatTurn::user("Scan plan for 10.0.0.5 please, quietly.")
+    );
+}
+
+#[tokio::test]
+async fn context_window_summarizes_older_turns() {
+    let (store, session_id) = seeded_store().await;
+    let llm = Arc::new(SummaryLlm {
+        calls: AtomicUsize::new(0),
+    });
+
+    let window = ContextWindow::new(store, 20).with_summarizer(llm.clone());
+    let history = window
+        .load(&session_id, Classification::Unclassified)
+        .await
+        .unwrap()
+        .turns;
+
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0].role, ChatRole::System);
+    assert!(history[0].content.contains("The user is Max."));
+
+    // Same window again: the cached summary is reused.
+    window
+        .load(&session_id, Classification::Unclassified)
+        .await
+        .unwrap();
+    assert_eq!(llm.calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn context_window_bounds_cached_summaries() {
+    let (store, first) = seeded_store().await;
+    let second = store.create_session(None).await.unwrap();
+    for message in store.get_messages(&first).await.unwrap() {
+        store
+            .add_message(&second, &message.role, &message.content)
+            .await
+            .unwrap();
+    }
+    let llm = Arc::new(SummaryLlm {
+        calls: AtomicUsize::new(0),
+    });
+
+    let window = ContextWindow::new(store.clone(), 20)
+        .with_summarizer(llm.clone())
+        .with_summary_cache(1);
+    window
+        .load(&first, Classification::Unclassified)
+        .await
+        .unwrap();
+    window
+        .load(&second, Classification::Unclassified)
+        .await
+        .unwrap();
+    // The second session evicted the summary of the first one.
+    window
+        .load(&first, Classification::Unclassified)
+        .await
+        .unwrap();
+    assert_eq!(llm.calls.load(Ordering::SeqCst), 3);
+
+    // Forgotten sessions are summarized again.
+    window.forget(&first).await;
+    window
+        .load(&first, Classification::Unclassified)
+        .await
+        .unwrap();
+    assert_eq!(llm.calls.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn context_window_only_replays_turns_the_agent_is_cleared_for() {
+    let (store, session_id) = seeded_store().await;
+    store
+        .add_message_as(
+            &session_id,
+            "user",
+            "What is in the red team report?",
+            None,
+            Classification::Unclassified,
+        )
+        .await
+        .unwrap();
+    store
+        .add_message_as(
+            &session_id,
+            "assistant",
+            "The DMZ host runs an unpatched VPN.",
+            None,
+            Classification::Secret,
+        )
+        .await
+        .unwrap();
+    let window = ContextWindow::new(store, 1000);
+
+    let secret = window
+        .load(&session_id, Classification::TopSecret)
+        .await
+        .unwrap();
+    assert_eq!(secret.turns.len(), 6);
+    assert_eq!(secret.classification, Classification::Secret);
+
+    let unclassified = window
+        .load(&session_id, Classification::Confidential)
+        .await
+        .unwrap();
+    assert_eq!(unclassified.turns.len(), 5);
+    assert_eq!(unclassified.classification, Classification::Unclassified);
+    assert!(
+        unclassified
+            .turns
+            .iter()
+            .all(|turn| !turn.content.contains("VPN"))
+    );
+}
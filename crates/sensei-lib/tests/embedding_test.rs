@@ -0,0 +1,173 @@
+use async_trait::async_trait;
+use axum::{Json, Router, routing::post};
+use sensei_common::Classification;
+use sensei_lib::errors::SenseiError;
+use sensei_lib::ingest::reembed;
+use sensei_lib::llm::{EmbeddingConfig, Llm, OllamaClient};
+use sensei_lib::memory::MemoryStore;
+use serde_json::{Value, json};
+
+/// Embeds every text as `[1, 0, 0, ...]` in `dimension` values.
+struct UnitEmbedder {
+    dimension: usize,
+}
+
+#[async_trait]
+impl Llm for UnitEmbedder {
+    async fn generate(&self, _prompt: &str) -> Result<String, SenseiError> {
+        Ok(String::new())
+    }
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>, SenseiError> {
+        let mut v = vec![0.0; self.dimension];
+        v[0] = 1.0;
+        Ok(v)
+    }
+}
+
+fn unit(dimension: usize) -> Vec<f32> {
+    let mut v = vec![0.0; dimension];
+    v[0] = 1.0;
+    v
+}
+
+async fn store_with_gemini_vectors() -> MemoryStore {
+    let store = MemoryStore::new("sqlite::memory:").await.unwrap();
+    store.migrate().await.unwrap();
+    store
+        .add_document("Backups run nightly.", unit(3072))
+        .await
+        .unwrap();
+    store
+        .add_router_cache("scan 10.0.0.1", "action", "nmap 10.0.0.1", unit(3072))
+        .await
+        .unwrap();
+    store
+}
+
+async fn vector_hits(store: &MemoryStore, dimension: usize) -> usize {
+    store
+        .search_documents("", unit(dimension), 5, Classification::TopSecret, "test")
+        .await
+        .unwrap()
+        .len()
+}
+
+#[tokio::test]
+async fn new_dimension_requires_reembed() {
+    let store = store_with_gemini_vectors().await;
+    assert!(store.vector_index_status().await.unwrap().is_current());
+
+    let local = store
+        .clone()
+        .with_embedding(EmbeddingConfig::new("ollama::nomic-embed-text", 768));
+    let status = local.vector_index_status().await.unwrap();
+    assert_eq!(status.resize.len(), 2);
+    assert!(status.resize.iter().all(|(_, d)| *d == 3072));
+    assert_eq!((status.stale_documents, status.stale_router_cache), (1, 1));
+
+    // Vectors of another size than configured are refused, and the old tables reject the new size.
+    assert!(matches!(
+        local.add_document("Restores are manual.", unit(3072)).await,
+        Err(SenseiError::Config(_))
+    ));
+    assert!(
+        local
+            .add_document("Restores are manual.", unit(768))
+            .await
+            .is_err()
+    );
+
+    let report = reembed(&local, &UnitEmbedder { dimension: 768 })
+        .await
+        .unwrap();
+    assert_eq!(
+        (report.resized, report.documents, report.router_cache),
+        (2, 1, 1)
+    );
+    assert!(local.vector_index_status().await.unwrap().is_current());
+
+    assert_eq!(vector_hits(&local, 768).await, 1);
+    assert_eq!(
+        local.search_router_cache(unit(768), 0.1).await.unwrap(),
+        Some(("action".to_string(), "nmap 10.0.0.1".to_string()))
+    );
+
+    // Nothing is left to do on a second run.
+    let report = reembed(&local, &UnitEmbedder { dimension: 768 })
+        .await
+        .unwrap();
+    assert_eq!(report, Default::default());
+}
+
+#[tokio::test]
+async fn vectors_of_another_model_are_never_matched() {
+    let store = store_with_gemini_vectors().await;
+    assert_eq!(vector_hits(&store, 3072).await, 1);
+
+    // Same dimension, different model: the old vectors are ignored until re-embedded.
+    let other = store
+        .clone()
+        .with_embedding(EmbeddingConfig::new("text-embedding-3-large", 3072));
+    let status = other.vector_index_status().await.unwrap();
+    assert!(status.resize.is_empty());
+    assert_eq!((status.stale_documents, status.stale_router_cache), (1, 1));
+    assert_eq!(vector_hits(&other, 3072).await, 0);
+    assert!(
+        other
+            .search_router_cache(unit(3072), 0.1)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let report = reembed(&other, &UnitEmbedder { dimension: 3072 })
+        .await
+        .unwrap();
+    assert_eq!(
+        (report.resized, report.documents, report.router_cache),
+        (0, 1, 1)
+    );
+    assert_eq!(vector_hits(&other, 3072).await, 1);
+    // ...and now the original model's view is stale in turn.
+    assert_eq!(vector_hits(&store, 3072).await, 0);
+}
+
+#[tokio::test]
+async fn ollama_requires_an_embedding_model() {
+    let chat_only = OllamaClient::new("llama3");
+    assert!(matches!(
+        chat_only.embed("hello").await,
+        Err(SenseiError::Llm(_))
+    ));
+
+    let config = EmbeddingConfig::new("nomic-embed-text", 768);
+    assert!(!config.is_local());
+    assert!(EmbeddingConfig::new("ollama::nomic-embed-text", 768).is_local());
+}
+
+#[tokio::test]
+async fn ollama_is_reached_at_the_configured_url() {
+    let app = Router::new().route(
+        "/v1/chat/completions",
+        post(|Json(request): Json<Value>| async move {
+            Json(json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": request["model"],
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": "pong" },
+                    "finish_reason": "stop"
+                }],
+                "usage": { "prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2 }
+            }))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let client = OllamaClient::new("llama3").with_base_url(&url);
+    assert_eq!(client.generate("ping").await.unwrap(), "pong");
+}
@@ -0,0 +1,209 @@
+use sensei_common::Classification;
+use sensei_lib::memory::{MemoryStore, SearchConfig};
+
+/// Embedding at distance `d` from the zero vector.
+fn at_distance(d: f32) -> Vec<f32> {
+    let mut v = vec![0.0; 3072];
+    v[0] = d;
+    v
+}
+
+async fn store() -> MemoryStore {
+    let store = MemoryStore::new("sqlite::memory:").await.unwrap();
+    store.migrate().await.unwrap();
+    store
+}
+
+async fn search(store: &MemoryStore, query: &str) -> Vec<String> {
+    store
+        .search_documents(
+            query,
+            at_distance(0.0),
+            3,
+            Classification::TopSecret,
+            "test",
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|hit| hit.content)
+        .collect()
+}
+
+#[tokio::test]
+async fn exact_tokens_are_found_by_keyword() {
+    let store = store().await;
+    store
+        .add_document(
+            "Backdoor in xz-utils tracked as CVE-2024-3094.",
+            at_distance(1.5),
+        )
+        .await
+        .unwrap();
+    store
+        .add_document("Hardening guide for OpenSSH servers.", at_distance(1.2))
+        .await
+        .unwrap();
+
+    // Both documents are beyond the distance threshold; only the keyword match is relevant.
+    let hits = store
+        .search_documents(
+            "Is CVE-2024-3094 patched?",
+            at_distance(0.0),
+            3,
+            Classification::Unclassified,
+            "test",
+        )
+        .await
+        .unwrap();
+    assert_eq!(hits.len(), 1);
+    assert!(hits[0].content.contains("CVE-2024-3094"));
+    assert_eq!(hits[0].distance, None);
+    assert!(hits[0].bm25.unwrap() > 0.0);
+
+    // A different CVE sharing the year does not match the phrase.
+    assert!(search(&store, "CVE-2024-6387").await.is_empty());
+}
+
+#[tokio::test]
+async fn irrelevant_documents_are_not_returned() {
+    let store = store().await;
+    store
+        .add_document("Team lunch on friday.", at_distance(1.5))
+        .await
+        .unwrap();
+
+    assert!(
+        search(&store, "what is the capital of France?")
+            .await
+            .is_empty()
+    );
+
+    let lenient = store.clone().with_search_config(SearchConfig {
+        max_distance: 2.0,
+        ..SearchConfig::default()
+    });
+    assert_eq!(search(&lenient, "").await, vec!["Team lunch on friday."]);
+}
+
+#[tokio::test]
+async fn weak_keyword_matches_are_not_returned() {
+    let store = store().await;
+    store
+        .add_document("Team lunch on friday.", at_distance(1.5))
+        .await
+        .unwrap();
+    store
+        .add_document("The team rotates the on-call pager.", at_distance(1.5))
+        .await
+        .unwrap();
+    store
+        .add_document("Friday deploys need team lead approval.", at_distance(1.5))
+        .await
+        .unwrap();
+
+    // "team" occurs in a third of the documents: too common to be evidence on its own.
+    assert!(search(&store, "team building ideas").await.is_empty());
+    assert_eq!(search(&store, "pager").await.len(), 1);
+
+    let lenient = store.clone().with_search_config(SearchConfig {
+        min_bm25: 0.0,
+        ..SearchConfig::default()
+    });
+    assert_eq!(search(&lenient, "team building ideas").await.len(), 3);
+}
+
+#[tokio::test]
+async fn keyword_matches_do_not_depend_on_corpus_size() {
+    let store = store().await;
+    let advisories = [
+        "Advisory: CVE-2024-3094 affects xz-utils 5.6.0.",
+        "Patch status of CVE-2024-3094 on the build hosts.",
+        "CVE-2024-3094 was found in a code review.",
+        "Rollback plan for CVE-2024-3094.",
+    ];
+    for content in advisories {
+        store.add_document(content, at_distance(1.5)).await.unwrap();
+    }
+
+    // Every document names the CVE: a term that common scores next to nothing in BM25,
+    // but an exact identifier is still a match.
+    assert_eq!(search(&store, "CVE-2024-3094").await.len(), 3);
+
+    // In a corpus of two documents, a word found in one is as selective as it gets.
+    let store = self::store().await;
+    store
+        .add_document("The team rotates the on-call pager.", at_distance(1.5))
+        .await
+        .unwrap();
+    store
+        .add_document("Backups run nightly.", at_distance(1.5))
+        .await
+        .unwrap();
+    assert_eq!(
+        search(&store, "pager").await,
+        vec!["The team rotates the on-call pager."]
+    );
+}
+
+#[tokio::test]
+async fn documents_found_both_ways_rank_first() {
+    let store = store().await;
+    store
+        .add_document("Rotate the TLS certificates yearly.", at_distance(0.1))
+        .await
+        .unwrap();
+    store
+        .add_document("host db01.corp.local runs PostgreSQL 16.", at_distance(0.3))
+        .await
+        .unwrap();
+    store
+        .add_document("db01.corp.local is backed up nightly.", at_distance(1.8))
+        .await
+        .unwrap();
+
+    let hits = store
+        .search_documents(
+            "db01.corp.local",
+            at_distance(0.0),
+            3,
+            Classification::Unclassified,
+            "test",
+        )
+        .await
+        .unwrap();
+    assert_eq!(hits.len(), 3);
+    assert!(hits[0].content.starts_with("host db01"));
+    assert!(hits[0].distance.is_some() && hits[0].bm25.is_some());
+    assert!(hits[0].score > hits[1].score);
+    assert!(hits.windows(2).all(|w| w[0].score >= w[1].score));
+}
+
+#[tokio::test]
+async fn keyword_index_follows_updates_and_deletes() {
+    let store = store().await;
+    let id = store
+        .add_classified_document(
+            "Primary DNS is ns1.example.org.",
+            at_distance(1.5),
+            Classification::Unclassified,
+        )
+        .await
+        .unwrap();
+    assert_eq!(search(&store, "ns1.example.org").await.len(), 1);
+
+    store
+        .update_document(
+            id,
+            "Primary DNS moved to ns2.example.org.",
+            &store.find_document(id).await.unwrap().unwrap().metadata,
+            Some(at_distance(1.5)),
+        )
+        .await
+        .unwrap();
+    assert!(search(&store, "ns1.example.org").await.is_empty());
+    assert_eq!(search(&store, "ns2.example.org").await.len(), 1);
+
+    store.delete_document(id).await.unwrap();
+    assert!(search(&store, "ns2.example.org").await.is_empty());
+}
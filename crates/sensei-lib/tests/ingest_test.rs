@@ -0,0 +1,173 @@
+use async_trait::async_trait;
+use sensei_common::Classification;
+use sensei_lib::errors::SenseiError;
+use sensei_lib::ingest::{ChunkConfig, IngestRequest, chunk_markdown, ingest};
+use sensei_lib::llm::Llm;
+use sensei_lib::memory::MemoryStore;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts embedding calls and returns a constant vector.
+#[derive(Default)]
+struct CountingEmbedder {
+    calls: AtomicUsize,
+}
+
+#[async_trait]
+impl Llm for CountingEmbedder {
+    async fn generate(&self, _prompt: &str) -> Result<String, SenseiError> {
+        Ok(String::new())
+    }
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>, SenseiError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(vec![0.1; 3072])
+    }
+}
+
+const RUNBOOK: &str = r#"# Runbook
+
+Intro paragraph about the service.
+
+## Backup
+
+Backups run nightly from cron and are kept for thirty days on the NAS.
+
+```bash
+pg_dump -Fc sensei > /backup/sensei.dump
+```
+
+## Restore
+
+Stop the service first, then restore the latest dump.
+"#;
+
+#[test]
+fn chunks_follow_headings_and_keep_code_blocks_whole() {
+    let chunks = chunk_markdown(RUNBOOK, &ChunkConfig::default());
+
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(
+        chunks.iter().map(|c| c.index).collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+    assert!(
+        chunks[0]
+            .content
+            .starts_with("# Runbook\n\nIntro paragraph")
+    );
+    assert!(chunks[1].content.starts_with("# Runbook > ## Backup\n\n"));
+    assert!(
+        chunks[1]
+            .content
+            .contains("```bash\npg_dump -Fc sensei > /backup/sensei.dump\n```")
+    );
+    assert!(chunks[2].content.starts_with("# Runbook > ## Restore"));
+    assert!(!chunks[2].content.contains("pg_dump"));
+    assert_eq!(chunks[0].hash.len(), 64);
+}
+
+#[test]
+fn long_sections_are_split_with_overlap() {
+    let sentence = "The quick brown fox jumps over the lazy dog. ";
+    let text = format!("## Notes\n\n{}", sentence.repeat(40));
+    let config = ChunkConfig::new(300, 60);
+
+    let chunks = chunk_markdown(&text, &config);
+    assert!(chunks.len() > 3);
+    for chunk in &chunks {
+        assert!(chunk.content.starts_with("## Notes\n\n"));
+        assert!(chunk.content.len() <= "## Notes\n\n".len() + config.size + config.overlap);
+    }
+
+    // The start of each chunk repeats the end of the previous one.
+    let first_body = chunks[0].content.trim_start_matches("## Notes\n\n");
+    let second_body = chunks[1].content.trim_start_matches("## Notes\n\n");
+    let overlap: String = second_body.chars().take(20).collect();
+    assert!(first_body.contains(&overlap));
+}
+
+#[test]
+fn oversized_code_blocks_are_split_into_fenced_parts() {
+    let lines: Vec<String> = (0..60).map(|i| format!("echo line {}", i)).collect();
+    let text = format!("```sh\n{}\n```", lines.join("\n"));
+
+    let chunks = chunk_markdown(&text, &ChunkConfig::new(200, 50));
+    assert!(chunks.len() > 1);
+    for chunk in &chunks {
+        assert!(chunk.content.starts_with("```sh\n"));
+        assert!(chunk.content.ends_with("\n```"));
+    }
+}
+
+#[tokio::test]
+async fn reingest_skips_unchanged_and_replaces_new_versions() {
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+    let llm = CountingEmbedder::default();
+    let config = ChunkConfig::default();
+    let request = |content: &str| IngestRequest {
+        content: content.to_string(),
+        source: Some("/srv/docs/runbook.md".to_string()),
+        classification: Classification::Confidential,
+        tags: vec!["ops".to_string()],
+    };
+
+    let report = ingest(&memory, &llm, request(RUNBOOK), &config)
+        .await
+        .unwrap();
+    assert_eq!(report.chunks, 3);
+    assert!(!report.unchanged);
+    assert_eq!(memory.list_documents().await.unwrap().len(), 3);
+    assert_eq!(llm.calls.load(Ordering::SeqCst), 3);
+
+    // Same content again: nothing is embedded or stored.
+    let report = ingest(&memory, &llm, request(RUNBOOK), &config)
+        .await
+        .unwrap();
+    assert!(report.unchanged);
+    assert_eq!(report.relabeled, 0);
+    assert_eq!(llm.calls.load(Ordering::SeqCst), 3);
+    assert_eq!(memory.list_documents().await.unwrap().len(), 3);
+
+    // Same content with new labels: the chunks are relabeled, not embedded again.
+    let relabel = IngestRequest {
+        classification: Classification::Secret,
+        tags: vec!["red".to_string()],
+        ..request(RUNBOOK)
+    };
+    let report = ingest(&memory, &llm, relabel, &config).await.unwrap();
+    assert!(report.unchanged);
+    assert_eq!(report.relabeled, 3);
+    assert_eq!(llm.calls.load(Ordering::SeqCst), 3);
+    for (id, _) in memory.list_documents().await.unwrap() {
+        let metadata = memory.find_document(id).await.unwrap().unwrap().metadata;
+        assert_eq!(metadata.classification, Classification::Secret);
+        assert_eq!(metadata.tags, vec!["red".to_string()]);
+    }
+
+    // A new version replaces every chunk of the old one.
+    let report = ingest(
+        &memory,
+        &llm,
+        request("# Runbook\n\nEverything moved to the wiki."),
+        &config,
+    )
+    .await
+    .unwrap();
+    assert_eq!((report.chunks, report.replaced), (1, 3));
+    let documents = memory.list_documents().await.unwrap();
+    assert_eq!(documents.len(), 1);
+    assert!(
+        memory
+            .get_document(documents[0].0)
+            .await
+            .unwrap()
+            .contains("wiki")
+    );
+
+    // Every chunk keeps the classification of its document.
+    let results = memory
+        .search_documents("", vec![0.1; 3072], 5, Classification::Unclassified, "test")
+        .await
+        .unwrap();
+    assert!(results.is_empty());
+}
@@ -0,0 +1,224 @@
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, Sse},
+    },
+    routing::{get, post},
+};
+use futures::stream::{self, Stream};
+use sensei_lib::config::RemoteTransport;
+use sensei_lib::mcp_client::McpClient;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+const SESSION: &str = "session-1";
+
+/// Stand-in MCP server speaking both HTTP transports.
+#[derive(Clone, Default)]
+struct StandIn {
+    // Event stream of the legacy SSE client
+    events: Arc<Mutex<Option<mpsc::UnboundedSender<Event>>>>,
+    session_closed: Arc<AtomicBool>,
+}
+
+fn reply(request: &Value) -> Value {
+    let result = match request["method"].as_str() {
+        Some("initialize") => json!({ "protocolVersion": "2025-03-26", "capabilities": {} }),
+        Some("tools/list") => json!({ "tools": [{ "name": "ping" }] }),
+        _ => json!({ "content": [{ "type": "text", "text": "pong" }] }),
+    };
+    json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+}
+
+fn events(
+    events: Vec<Event>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>> + Send + 'static> {
+    Sse::new(stream::iter(events.into_iter().map(Ok)))
+}
+
+/// Streamable HTTP: tool calls are answered on an event stream, everything else with JSON.
+async fn streamable(
+    State(server): State<StandIn>,
+    headers: HeaderMap,
+    Json(request): Json<Value>,
+) -> Response {
+    if headers.get("x-api-key").is_none_or(|v| v != "secret") {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let session = headers.get("mcp-session-id").and_then(|v| v.to_str().ok());
+    if request["method"] == "initialize" {
+        return ([("mcp-session-id", SESSION)], Json(reply(&request))).into_response();
+    }
+    if session != Some(SESSION) || server.session_closed.load(Ordering::SeqCst) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    if request["method"] == "tools/call" {
+        let progress = json!({ "jsonrpc": "2.0", "method": "notifications/progress" });
+        return events(vec![
+            Event::default().data(progress.to_string()),
+            Event::default().data(reply(&request).to_string()),
+        ])
+        .into_response();
+    }
+    Json(reply(&request)).into_response()
+}
+
+async fn end_session(State(server): State<StandIn>, headers: HeaderMap) -> StatusCode {
+    if headers.get("mcp-session-id").is_some_and(|v| v == SESSION) {
+        server.session_closed.store(true, Ordering::SeqCst);
+    }
+    StatusCode::OK
+}
+
+/// Legacy SSE: announce the message endpoint, then stream the responses.
+async fn open_stream(
+    State(server): State<StandIn>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tx.send(
+        Event::default()
+            .event("endpoint")
+            .data("/messages?sessionId=1"),
+    )
+    .unwrap();
+    *server.events.lock().unwrap() = Some(tx);
+    Sse::new(stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|event| (Ok(event), rx))
+    }))
+}
+
+/// Legacy SSE announcing a message endpoint on another host.
+async fn redirect_stream() -> Sse<impl Stream<Item = Result<Event, Infallible>> + Send + 'static> {
+    events(vec![
+        Event::default()
+            .event("endpoint")
+            .data("http://collector.invalid/messages?sessionId=1"),
+    ])
+}
+
+/// Accepts requests and never answers them.
+async fn stalled() -> StatusCode {
+    std::future::pending().await
+}
+
+async fn message(State(server): State<StandIn>, Json(request): Json<Value>) -> StatusCode {
+    let events = server.events.lock().unwrap();
+    let Some(tx) = events.as_ref() else {
+        return StatusCode::NOT_FOUND;
+    };
+    tx.send(
+        Event::default()
+            .event("message")
+            .data(reply(&request).to_string()),
+    )
+    .unwrap();
+    StatusCode::ACCEPTED
+}
+
+async fn serve() -> (String, StandIn) {
+    let server = StandIn::default();
+    let app = Router::new()
+        .route("/mcp", post(streamable).delete(end_session))
+        .route("/sse", get(open_stream))
+        .route("/sse-redirect", get(redirect_stream))
+        .route("/stalled", post(stalled))
+        .route("/messages", post(message))
+        .with_state(server.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (url, server)
+}
+
+fn api_key() -> HashMap<String, String> {
+    HashMap::from([("X-Api-Key".to_string(), "secret".to_string())])
+}
+
+#[tokio::test]
+async fn streamable_http_keeps_the_session_and_sends_headers() {
+    let (url, server) = serve().await;
+    let client = McpClient::connect(
+        &format!("{}/mcp", url),
+        api_key(),
+        RemoteTransport::StreamableHttp,
+    )
+    .await
+    .unwrap();
+
+    client.initialize().await.unwrap();
+    let tools = client.list_tools().await.unwrap();
+    assert_eq!(tools[0]["name"], "ping");
+    // Answered on an event stream, after a notification
+    assert_eq!(client.call_tool("ping", json!({})).await.unwrap(), "pong");
+
+    client.shutdown().await;
+    assert!(server.session_closed.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn http_errors_are_reported() {
+    let (url, _) = serve().await;
+    let client = McpClient::connect(
+        &format!("{}/mcp", url),
+        HashMap::new(),
+        RemoteTransport::StreamableHttp,
+    )
+    .await
+    .unwrap();
+
+    let error = client.initialize().await.unwrap_err();
+    assert!(error.to_string().contains("401"), "{}", error);
+}
+
+#[tokio::test]
+async fn stalled_requests_time_out() {
+    let (url, _) = serve().await;
+    let client = McpClient::connect(
+        &format!("{}/stalled", url),
+        HashMap::new(),
+        RemoteTransport::StreamableHttp,
+    )
+    .await
+    .unwrap()
+    .with_timeout(Duration::from_millis(300));
+
+    let error = tokio::time::timeout(Duration::from_secs(5), client.initialize())
+        .await
+        .expect("the request timeout covers sending")
+        .unwrap_err();
+    assert!(error.to_string().contains("timed out"), "{}", error);
+}
+
+#[tokio::test]
+async fn legacy_sse_posts_to_the_announced_endpoint() {
+    let (url, _) = serve().await;
+    let client = McpClient::connect(&format!("{}/sse", url), api_key(), RemoteTransport::Sse)
+        .await
+        .unwrap();
+
+    client.initialize().await.unwrap();
+    assert_eq!(client.list_tools().await.unwrap().len(), 1);
+    assert_eq!(client.call_tool("ping", json!({})).await.unwrap(), "pong");
+}
+
+#[tokio::test]
+async fn legacy_sse_rejects_an_endpoint_on_another_origin() {
+    let (url, _) = serve().await;
+    let error = McpClient::connect(
+        &format!("{}/sse-redirect", url),
+        api_key(),
+        RemoteTransport::Sse,
+    )
+    .await
+    .err()
+    .unwrap();
+    assert!(error.to_string().contains("another origin"), "{}", error);
+}
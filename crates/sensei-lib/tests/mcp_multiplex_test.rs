@@ -0,0 +1,113 @@
+use sensei_lib::mcp_client::McpClient;
+use serde_json::json;
+use std::time::Duration;
+
+// "slow" is only answered after the next request; "hang" never is.
+// "chatty" sends a notification and two requests before it answers.
+const MOCK_PY: &str = r#"
+import sys, json
+
+def send(msg):
+    print(json.dumps(msg), flush=True)
+
+def answer(msgid, text):
+    send({"jsonrpc": "2.0", "id": msgid, "result": {"content": [{"type": "text", "text": text}]}})
+
+held, names, cancelled = None, {}, []
+while True:
+    line = sys.stdin.readline()
+    if not line:
+        break
+    req = json.loads(line)
+    method, msgid = req.get("method"), req.get("id")
+    if method == "notifications/cancelled":
+        params = req["params"]
+        cancelled.append(names.get(params["requestId"], "?") + ": " + params.get("reason", ""))
+        continue
+    if method != "tools/call":
+        send({"jsonrpc": "2.0", "id": msgid, "result": {}})
+        continue
+    name = req["params"]["name"]
+    names[msgid] = name
+    if name == "slow":
+        held = msgid
+    elif name == "fast":
+        answer(msgid, "fast")
+        if held is not None:
+            answer(held, "slow")
+    elif name == "chatty":
+        send({"jsonrpc": "2.0", "method": "notifications/progress", "params": {"progress": 1}})
+        send({"jsonrpc": "2.0", "id": "srv-1", "method": "ping"})
+        send({"jsonrpc": "2.0", "id": "srv-2", "method": "sampling/createMessage"})
+        replies = {}
+        for _ in range(2):
+            reply = json.loads(sys.stdin.readline())
+            replies[reply["id"]] = "ok" if "result" in reply else str(reply["error"]["code"])
+        answer(msgid, "ping:" + replies["srv-1"] + " sampling:" + replies["srv-2"])
+    elif name == "cancelled":
+        answer(msgid, "; ".join(cancelled))
+"#;
+
+async fn client() -> McpClient {
+    let client = McpClient::new("python3", &["-c", MOCK_PY], None)
+        .await
+        .unwrap()
+        .with_timeout(Duration::from_millis(300));
+    client.initialize().await.unwrap();
+    client
+}
+
+#[tokio::test]
+async fn concurrent_requests_get_their_own_responses() {
+    let client = client().await;
+    // "slow" is answered only after "fast" went out, and its response comes second.
+    let (slow, fast) = tokio::join!(client.call_tool("slow", json!({})), async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        client.call_tool("fast", json!({})).await
+    });
+    assert_eq!(slow.unwrap(), "slow");
+    assert_eq!(fast.unwrap(), "fast");
+}
+
+#[tokio::test]
+async fn server_notifications_and_requests_are_handled() {
+    let client = client().await;
+    let mut notifications = client.subscribe();
+
+    let output = client.call_tool("chatty", json!({})).await.unwrap();
+    assert_eq!(output, "ping:ok sampling:-32601");
+
+    let notification = notifications.recv().await.unwrap();
+    assert_eq!(notification.method, "notifications/progress");
+    assert_eq!(notification.params, Some(json!({ "progress": 1 })));
+}
+
+#[tokio::test]
+async fn timed_out_and_abandoned_requests_are_cancelled() {
+    let client = client().await;
+
+    let error = client.call_tool("hang", json!({})).await.unwrap_err();
+    assert!(error.to_string().contains("timed out"), "{}", error);
+    // The caller gives up before the timeout.
+    let abandoned = tokio::time::timeout(
+        Duration::from_millis(50),
+        client.call_tool("hang", json!({})),
+    )
+    .await;
+    assert!(abandoned.is_err());
+
+    let mut cancelled = String::new();
+    for _ in 0..20 {
+        cancelled = client.call_tool("cancelled", json!({})).await.unwrap();
+        if cancelled.contains("cancelled by the client") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(
+        cancelled,
+        "hang: Request timed out; hang: Request cancelled by the client"
+    );
+    // The server is still usable.
+    assert_eq!(client.call_tool("fast", json!({})).await.unwrap(), "fast");
+}
@@ -0,0 +1,188 @@
+use async_trait::async_trait;
+use sensei_common::Classification;
+use sensei_lib::agents::mcp_agent::McpAgent;
+use sensei_lib::errors::SenseiError;
+use sensei_lib::ingest::{ChunkConfig, ingest_mcp_resources};
+use sensei_lib::llm::Llm;
+use sensei_lib::mcp_client::McpClient;
+use sensei_lib::memory::{DocumentFilter, MemoryStore};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Tools are listed two per page; calling "grow" adds a tool and announces it.
+const MOCK_PY: &str = r##"
+import sys, json
+
+def send(msg):
+    print(json.dumps(msg), flush=True)
+
+tools = [{"name": "alpha"}, {"name": "beta"}, {"name": "grow"}]
+initialized = False
+while True:
+    line = sys.stdin.readline()
+    if not line:
+        break
+    req = json.loads(line)
+    method, msgid, params = req.get("method"), req.get("id"), req.get("params") or {}
+    if msgid is None:
+        initialized = initialized or method == "notifications/initialized"
+        continue
+    if method == "initialize":
+        result = {"protocolVersion": "2025-03-26", "serverInfo": {"name": "mock"},
+                  "capabilities": {"tools": {"listChanged": True}, "resources": {}, "prompts": {}}}
+    elif method == "tools/list":
+        start = int(params.get("cursor", "0"))
+        result = {"tools": tools[start:start + 2]}
+        if start + 2 < len(tools):
+            result["nextCursor"] = str(start + 2)
+    elif method == "tools/call":
+        name = params["name"]
+        if name == "grow":
+            tools.append({"name": "gamma"})
+        text = "initialized" if initialized else "not initialized"
+        result = {"content": [{"type": "text", "text": text}]}
+    elif method == "resources/list":
+        result = {"resources": [{"uri": "mock://runbook", "name": "Runbook"},
+                                {"uri": "mock://logo", "name": "Logo"}]}
+    elif method == "resources/read":
+        if params["uri"] == "mock://runbook":
+            contents = [{"uri": params["uri"], "text": "# Runbook\n\nRotate the keys every month."}]
+        else:
+            contents = [{"uri": params["uri"], "blob": "iVBORw0KGgo="}]
+        result = {"contents": contents}
+    elif method == "prompts/list":
+        result = {"prompts": [{"name": "triage", "arguments": [{"name": "host", "required": True}]}]}
+    elif method == "prompts/get":
+        host = params["arguments"]["host"]
+        result = {"messages": [{"role": "user", "content": {"type": "text", "text": "Triage " + host}}]}
+    else:
+        send({"jsonrpc": "2.0", "id": msgid, "error": {"code": -32601, "message": "Method not found"}})
+        continue
+    send({"jsonrpc": "2.0", "id": msgid, "result": result})
+    if method == "tools/call" and params["name"] == "grow":
+        send({"jsonrpc": "2.0", "method": "notifications/tools/list_changed"})
+"##;
+
+struct Embedder;
+
+#[async_trait]
+impl Llm for Embedder {
+    async fn generate(&self, _prompt: &str) -> Result<String, SenseiError> {
+        Ok(String::new())
+    }
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>, SenseiError> {
+        Ok(vec![0.1; 3072])
+    }
+}
+
+async fn client() -> Arc<McpClient> {
+    Arc::new(
+        McpClient::new("python3", &["-c", MOCK_PY], None)
+            .await
+            .unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn initialize_negotiates_capabilities_and_confirms() {
+    let client = client().await;
+    let info = client.initialize().await.unwrap();
+
+    assert_eq!(info.protocol_version, "2025-03-26");
+    assert_eq!(info.server_info, Some(json!({ "name": "mock" })));
+    assert!(client.supports("resources") && client.supports("prompts"));
+    assert!(!client.supports("logging"));
+    // notifications/initialized went out before the next request.
+    assert_eq!(
+        client.call_tool("alpha", json!({})).await.unwrap(),
+        "initialized"
+    );
+}
+
+#[tokio::test]
+async fn list_methods_follow_cursors() {
+    let client = client().await;
+    client.initialize().await.unwrap();
+
+    let names: Vec<_> = client
+        .list_tools()
+        .await
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(names, ["alpha", "beta", "grow"]);
+}
+
+#[tokio::test]
+async fn resources_and_prompts_are_available() {
+    let client = client().await;
+    client.initialize().await.unwrap();
+
+    assert_eq!(client.list_resources().await.unwrap().len(), 2);
+    let contents = client.read_resource("mock://runbook").await.unwrap();
+    assert!(contents[0]["text"].as_str().unwrap().contains("Rotate"));
+
+    assert_eq!(client.list_prompts().await.unwrap()[0]["name"], "triage");
+    let prompt = client
+        .get_prompt("triage", json!({ "host": "db01" }))
+        .await
+        .unwrap();
+    assert_eq!(prompt["messages"][0]["content"]["text"], "Triage db01");
+}
+
+#[tokio::test]
+async fn agent_tools_refresh_when_the_list_changes() {
+    let client = client().await;
+    let agent = McpAgent::new(client.clone(), Arc::new(Embedder), "mock")
+        .await
+        .unwrap();
+    assert_eq!(agent.tools().len(), 3);
+
+    client.call_tool("grow", json!({})).await.unwrap();
+    for _ in 0..100 {
+        if agent.tools().len() == 4 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(agent.tools().get("gamma").is_some());
+}
+
+#[tokio::test]
+async fn text_resources_are_ingested_as_knowledge() {
+    let client = client().await;
+    client.initialize().await.unwrap();
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+    let chunking = ChunkConfig::default();
+    let ingest = || {
+        ingest_mcp_resources(
+            &memory,
+            &Embedder,
+            &client,
+            "mock",
+            Classification::Secret,
+            &chunking,
+        )
+    };
+
+    // The binary logo is skipped.
+    let reports = ingest().await.unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].source.as_deref(), Some("mock://runbook"));
+
+    let filter = DocumentFilter {
+        source: None,
+        tag: Some("mock".to_string()),
+    };
+    let (documents, total) = memory
+        .list_documents_page(&filter, Classification::TopSecret, "test", 10, 0)
+        .await
+        .unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(documents[0].metadata.classification, Classification::Secret);
+
+    assert!(ingest().await.unwrap()[0].unchanged);
+}
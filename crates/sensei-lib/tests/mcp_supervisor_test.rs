@@ -0,0 +1,98 @@
+use sensei_lib::mcp_client::{McpClient, McpState};
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Answers tools/call with "pong", and exits on the "crash" tool.
+const MOCK_PY: &str = r#"
+import sys, json
+
+for line in sys.stdin:
+    req = json.loads(line)
+    method, msgid = req.get("method"), req.get("id")
+    if method == "tools/call" and req["params"]["name"] == "crash":
+        sys.exit(1)
+    if method == "initialize":
+        result = {"protocolVersion": "2024-11-05", "capabilities": {}}
+    elif method == "tools/list":
+        result = {"tools": [{"name": "ping"}, {"name": "crash"}]}
+    else:
+        result = {"content": [{"type": "text", "text": "pong"}]}
+    print(json.dumps({"jsonrpc": "2.0", "id": msgid, "result": result}), flush=True)
+"#;
+
+async fn supervised() -> Arc<McpClient> {
+    let client = McpClient::new("python3", &["-c", MOCK_PY], None)
+        .await
+        .unwrap()
+        .with_backoff(Duration::from_millis(20), Duration::from_millis(100));
+    let client = Arc::new(client);
+    client.initialize().await.unwrap();
+    client.supervise();
+    client
+}
+
+async fn wait_for(client: &McpClient, state: McpState) {
+    for _ in 0..200 {
+        if client.status().state == state {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!(
+        "MCP server never reached {:?}: {:?}",
+        state,
+        client.status()
+    );
+}
+
+#[tokio::test]
+async fn exited_servers_are_restarted() {
+    let client = supervised().await;
+    let pid = client.pid().await.unwrap();
+
+    assert!(client.call_tool("crash", json!({})).await.is_err());
+    wait_for(&client, McpState::Running).await;
+
+    let status = client.status();
+    assert_eq!(status.restarts, 1);
+    assert!(status.last_error.is_some());
+    assert_ne!(client.pid().await.unwrap(), pid);
+    assert_eq!(client.call_tool("ping", json!({})).await.unwrap(), "pong");
+}
+
+#[tokio::test]
+async fn killed_servers_are_detected_and_restarted() {
+    let client = supervised().await;
+    let pid = client.pid().await.unwrap();
+
+    std::process::Command::new("kill")
+        .args(["-9", &pid.to_string()])
+        .status()
+        .unwrap();
+    for _ in 0..200 {
+        if client.status().restarts == 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(client.status().state, McpState::Running);
+    assert_eq!(client.call_tool("ping", json!({})).await.unwrap(), "pong");
+}
+
+#[tokio::test]
+async fn shutdown_kills_and_reaps_the_server() {
+    let client = supervised().await;
+    let pid = client.pid().await.unwrap();
+
+    client.shutdown().await;
+    assert_eq!(client.status().state, McpState::Stopped);
+    // No zombie: the process entry is gone once it has been waited for.
+    assert!(!Path::new(&format!("/proc/{}", pid)).exists());
+
+    let error = client.call_tool("ping", json!({})).await.unwrap_err();
+    assert!(error.to_string().contains("stopped"));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(client.status().state, McpState::Stopped);
+}
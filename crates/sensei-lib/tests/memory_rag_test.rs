@@ -1,8 +1,16 @@
-use sensei_lib::memory::MemoryStore;
+use sensei_common::Classification;
+use sensei_lib::memory::{MemoryStore, SearchConfig};
 
 #[tokio::test]
 async fn memory_rag_workflow() {
-    let store = MemoryStore::new("sqlite::memory:").await.unwrap();
+    // A one-document corpus gives every keyword a BM25 close to 0: keep them all.
+    let store = MemoryStore::new("sqlite::memory:")
+        .await
+        .unwrap()
+        .with_search_config(SearchConfig {
+            min_bm25: 0.0,
+            ..SearchConfig::default()
+        });
     store.migrate().await.unwrap();
 
     // 1. Add Document
@@ -24,8 +32,19 @@ async fn memory_rag_workflow() {
     let fetched_content = store.get_document(doc_id).await.unwrap();
     assert_eq!(fetched_content, content);
 
-    // 4. Search (KNN)
-    let results = store.search_documents(embedding, 1).await.unwrap();
+    // 4. Search (KNN + keywords)
+    let results = store
+        .search_documents(
+            "secret code",
+            embedding,
+            1,
+            Classification::Unclassified,
+            "test",
+        )
+        .await
+        .unwrap();
     assert_eq!(results.len(), 1);
-    assert_eq!(results[0], content);
+    assert_eq!(results[0].content, content);
+    assert_eq!(results[0].distance, Some(0.0));
+    assert!(results[0].bm25.is_some());
 }
@@ -0,0 +1,179 @@
+use sensei_lib::tools::Tool;
+use sensei_lib::tools::nmap::{NmapArgs, NmapTool, parse_report};
+use serde_json::{Value, json};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+fn fixture() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/nmap_scan.xml")
+}
+
+#[test]
+fn report_is_parsed_from_xml() {
+    let report = parse_report(&fs::read_to_string(fixture()).unwrap()).unwrap();
+    assert_eq!(report.hosts.len(), 2);
+
+    let host = &report.hosts[0];
+    assert_eq!(host.address, "192.0.2.10");
+    assert_eq!(host.hostnames, vec!["scanme.example", "web01.example"]);
+    assert_eq!(host.status, "up");
+    assert_eq!(host.ports.len(), 3);
+
+    let ssh = &host.ports[0];
+    assert_eq!(
+        (ssh.port, ssh.protocol.as_str(), ssh.state.as_str()),
+        (22, "tcp", "open")
+    );
+    let service = ssh.service.as_ref().unwrap();
+    assert_eq!(service.name, "ssh");
+    assert_eq!(service.product.as_deref(), Some("OpenSSH"));
+    assert_eq!(ssh.scripts[0].id, "ssh-hostkey");
+    assert_eq!(ssh.scripts[0].output, "256 aa:bb:cc:dd (ECDSA)");
+
+    assert_eq!(host.ports[2].state, "filtered");
+    assert_eq!(report.hosts[1].status, "down");
+    assert!(report.hosts[1].ports.is_empty());
+}
+
+#[test]
+fn arguments_are_validated() {
+    let args = |value: Value| serde_json::from_value::<NmapArgs>(value).unwrap();
+
+    for target in [
+        "10.0.0.1",
+        "10.0.0.0/24",
+        "10.0.0.0/16",
+        "fe80::1",
+        "2001:db8::/64",
+        "scanme.example",
+        "localhost",
+    ] {
+        assert!(
+            args(json!({ "target": target })).validate().is_ok(),
+            "{}",
+            target
+        );
+    }
+    for target in ["-oN/tmp/x", "10.0.0.0/33", "bad_host!", "a..b", ""] {
+        assert!(
+            args(json!({ "target": target })).validate().is_err(),
+            "{}",
+            target
+        );
+    }
+    for target in ["0.0.0.0/0", "10.0.0.0/8", "::/0", "2001:db8::/48"] {
+        let err = args(json!({ "target": target })).validate().unwrap_err();
+        assert!(err.to_string().contains("too broad"), "{}", target);
+    }
+
+    for ports in ["22", "22,80,443", "1-1024,8080"] {
+        assert!(
+            args(json!({ "target": "10.0.0.1", "ports": ports }))
+                .validate()
+                .is_ok()
+        );
+    }
+    for ports in ["0", "70000", "100-10", "22;ls", "-p-"] {
+        assert!(
+            args(json!({ "target": "10.0.0.1", "ports": ports }))
+                .validate()
+                .is_err()
+        );
+    }
+
+    let err = args(json!({ "target": "10.0.0.1", "scripts": ["smb-brute"] }))
+        .validate()
+        .unwrap_err();
+    assert!(err.to_string().contains("not allowed"));
+    assert!(
+        args(json!({ "target": "10.0.0.1", "timing": 6 }))
+            .validate()
+            .is_err()
+    );
+}
+
+#[test]
+fn command_line_is_built_from_arguments() {
+    let args = NmapArgs {
+        target: "10.0.0.1".to_string(),
+        ports: Some("22,80".to_string()),
+        service_detection: true,
+        timing: Some(4),
+        scripts: vec!["http-title".to_string(), "ssl-cert".to_string()],
+    };
+    assert_eq!(
+        args.to_args(),
+        vec![
+            "-p",
+            "22,80",
+            "-sV",
+            "-T4",
+            "--script=http-title,ssl-cert",
+            "-oX",
+            "-",
+            "--",
+            "10.0.0.1"
+        ]
+    );
+
+    let fast = NmapArgs {
+        target: "10.0.0.1".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(fast.to_args()[0], "-F");
+}
+
+#[tokio::test]
+async fn tool_runs_fake_nmap_binary() {
+    let dir = tempfile::tempdir().unwrap();
+    let args_file = dir.path().join("args.txt");
+    let fake = dir.path().join("nmap");
+    fs::write(
+        &fake,
+        format!(
+            "#!/bin/sh\necho \"$@\" > '{}'\ncat '{}'\n",
+            args_file.display(),
+            fixture().display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&fake, fs::Permissions::from_mode(0o755)).unwrap();
+
+    // Only this test touches SYSTEM_NMAPPATH in this binary.
+    unsafe { std::env::set_var("SYSTEM_NMAPPATH", &fake) };
+
+    let output = NmapTool
+        .call(
+            &json!({ "target": "scanme.example", "ports": "22,80,443", "service_detection": true }),
+        )
+        .await
+        .unwrap();
+
+    let report: Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(report["hosts"][0]["address"], "192.0.2.10");
+    assert_eq!(
+        report["hosts"][0]["ports"][1]["service"]["product"],
+        "nginx"
+    );
+
+    let invoked = fs::read_to_string(&args_file).unwrap();
+    assert_eq!(invoked.trim(), "-p 22,80,443 -sV -oX - -- scanme.example");
+
+    // A scan that hangs is killed once the timeout expires.
+    fs::write(&fake, "#!/bin/sh\nexec sleep 30\n").unwrap();
+    let started = Instant::now();
+    let err = NmapTool
+        .scan_within(
+            &NmapArgs {
+                target: "scanme.example".to_string(),
+                ..Default::default()
+            },
+            Duration::from_millis(200),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("timed out"), "{}", err);
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
@@ -0,0 +1,134 @@
+use async_trait::async_trait;
+use sensei_common::AgentCategory;
+use sensei_lib::agents::Agent;
+use sensei_lib::agents::action::ToolExecutorAgent;
+use sensei_lib::agents::react::NoAction;
+use sensei_lib::errors::SenseiError;
+use sensei_lib::llm::Llm;
+use sensei_lib::tools::{Tool, ToolRegistry};
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Answers prompts from a script, in order.
+struct ScriptedLlm {
+    replies: Mutex<VecDeque<String>>,
+    prompts: Mutex<Vec<String>>,
+}
+
+impl ScriptedLlm {
+    fn new(replies: &[&str]) -> Self {
+        Self {
+            replies: Mutex::new(replies.iter().map(|r| r.to_string()).collect()),
+            prompts: Mutex::new(vec![]),
+        }
+    }
+}
+
+#[async_trait]
+impl Llm for ScriptedLlm {
+    async fn generate(&self, prompt: &str) -> Result<String, SenseiError> {
+        self.prompts.lock().unwrap().push(prompt.to_string());
+        self.replies
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| SenseiError::Llm("script exhausted".to_string()))
+    }
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>, SenseiError> {
+        Ok(vec![])
+    }
+}
+
+/// Echoes the probed port.
+struct ProbeTool;
+
+#[async_trait]
+impl Tool for ProbeTool {
+    fn name(&self) -> &str {
+        "probe"
+    }
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": { "port": { "type": "integer" } },
+            "required": ["port"]
+        })
+    }
+    async fn execute(&self, args: &str) -> Result<String, SenseiError> {
+        Ok(format!("port {} open", args))
+    }
+}
+
+fn agent(llm: Arc<ScriptedLlm>) -> ToolExecutorAgent {
+    let mut tools = ToolRegistry::new();
+    tools.register(Arc::new(ProbeTool));
+    ToolExecutorAgent::with_registry(llm, AgentCategory::new("action"), tools)
+}
+
+#[tokio::test]
+async fn react_loop_chains_steps_and_synthesizes() {
+    let llm = Arc::new(ScriptedLlm::new(&[
+        r#"{"tool_name": "probe", "arguments": {"port": 22}}"#,
+        r#"{"tool_name": "probe", "arguments": {"port": 80}}"#,
+        r#"{"tool_name": "none", "arguments": {"reason": "done"}}"#,
+        "SSH and HTTP are exposed.",
+    ]));
+
+    let outcome = agent(llm.clone()).run("Check ssh then http").await.unwrap();
+
+    assert_eq!(outcome.answer, "SSH and HTTP are exposed.");
+    assert_eq!(outcome.steps.len(), 2);
+    assert_eq!(outcome.steps[0].observation, "port 22 open");
+    assert_eq!(outcome.steps[1].observation, "port 80 open");
+    assert!(outcome.steps.iter().all(|s| s.success));
+
+    // Later planning and the synthesis see earlier observations.
+    let prompts = llm.prompts.lock().unwrap();
+    assert!(prompts[1].contains("port 22 open"));
+    assert!(prompts[3].contains("port 22 open") && prompts[3].contains("port 80 open"));
+}
+
+#[tokio::test]
+async fn react_loop_respects_step_budget() {
+    let llm = Arc::new(ScriptedLlm::new(&[
+        r#"{"tool_name": "probe", "arguments": {"port": 1}}"#,
+        r#"{"tool_name": "probe", "arguments": {"port": 2}}"#,
+        // The budget is spent, so the next prompt is the synthesis.
+        "Ports 1 and 2 are open.",
+    ]));
+
+    let agent = agent(llm).with_max_steps(2);
+    let response = agent.process("Probe everything").await;
+
+    assert!(response.starts_with("Ports 1 and 2 are open."));
+    assert!(response.contains("1. ✅ Tool 'probe' on action executed successfully"));
+    assert!(response.contains("2. ✅ Tool 'probe' on action executed successfully"));
+    assert!(!response.contains("3. "));
+}
+
+#[tokio::test]
+async fn react_loop_stops_on_repeated_call() {
+    let llm = Arc::new(ScriptedLlm::new(&[
+        r#"{"tool_name": "probe", "arguments": {"port": 443}}"#,
+        r#"{"tool_name": "probe", "arguments": {"port": 443}}"#,
+        "HTTPS is open.",
+    ]));
+
+    let outcome = agent(llm).run("Probe https").await.unwrap();
+    assert_eq!(outcome.steps.len(), 1);
+    assert_eq!(outcome.answer, "HTTPS is open.");
+}
+
+#[tokio::test]
+async fn react_loop_reports_declined_requests() {
+    let llm = Arc::new(ScriptedLlm::new(&[
+        r#"{"tool_name": "none", "arguments": {"reason": "nothing to probe"}}"#,
+    ]));
+
+    let result = agent(llm).run("Make coffee").await;
+    assert_eq!(
+        result.unwrap_err(),
+        NoAction::Declined("nothing to probe".to_string())
+    );
+}
@@ -0,0 +1,198 @@
+use async_trait::async_trait;
+use sensei_common::AgentCategory;
+use sensei_lib::errors::SenseiError;
+use sensei_lib::llm::Llm;
+use sensei_lib::memory::MemoryStore;
+use sensei_lib::runtime::{RuntimeConfig, SenseiRuntime};
+use sensei_lib::tools::ToolRegistry;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::{Builder, NamedTempFile};
+
+/// Answers every prompt with its name.
+struct NamedLlm(&'static str);
+
+#[async_trait]
+impl Llm for NamedLlm {
+    async fn generate(&self, _prompt: &str) -> Result<String, SenseiError> {
+        Ok(self.0.to_string())
+    }
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>, SenseiError> {
+        Ok(vec![0.1; 3072])
+    }
+}
+
+fn config_file(suffix: &str, contents: &str) -> NamedTempFile {
+    let mut file = Builder::new().suffix(suffix).tempfile().unwrap();
+    write!(file, "{}", contents).unwrap();
+    file
+}
+
+fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    move |name| vars.get(name).cloned()
+}
+
+#[test]
+fn config_is_read_from_yaml_or_toml_and_overridden_by_the_environment() {
+    let yaml = config_file(
+        ".yaml",
+        r#"
+database_url: "sqlite://from-file.db"
+llm:
+  smart_model: gemini-2.5-pro
+chunking:
+  size: 800
+  overlap: 100
+"#,
+    );
+    let toml = config_file(
+        ".toml",
+        r#"
+database_url = "sqlite://from-file.db"
+
+[llm]
+smart_model = "gemini-2.5-pro"
+
+[chunking]
+size = 800
+overlap = 100
+"#,
+    );
+    let from_yaml = RuntimeConfig::load(yaml.path()).unwrap();
+    assert_eq!(from_yaml, RuntimeConfig::load(toml.path()).unwrap());
+    assert_eq!(from_yaml.llm.smart_model, "gemini-2.5-pro");
+    // Unset fields keep their defaults.
+    assert_eq!(
+        from_yaml.llm.fast_model,
+        RuntimeConfig::default().llm.fast_model
+    );
+    assert_eq!(from_yaml.context, RuntimeConfig::default().context);
+
+    let config = from_yaml
+        .with_overrides(vars(&[
+            ("DATABASE_URL", "sqlite://from-env.db"),
+            ("SENSEI_CHUNK_SIZE", "1200"),
+            ("SENSEI_CONTEXT_SUMMARY", "true"),
+            ("OLLAMA_MODEL", "llama3"),
+        ]))
+        .unwrap();
+    assert_eq!(config.database_url, "sqlite://from-env.db");
+    assert_eq!((config.chunking.size, config.chunking.overlap), (1200, 100));
+    assert!(config.context.summary);
+    assert_eq!(config.llm.ollama_model.as_deref(), Some("llama3"));
+    config.validate().unwrap();
+}
+
+#[test]
+fn invalid_configs_are_rejected() {
+    let unknown_field = config_file(".yaml", "chunking:\n  sise: 800\n");
+    assert!(matches!(
+        RuntimeConfig::load(unknown_field.path()),
+        Err(SenseiError::Config(_))
+    ));
+    assert!(matches!(
+        RuntimeConfig::load(Path::new("/nonexistent/sensei.yaml")),
+        Err(SenseiError::Config(_))
+    ));
+    assert!(matches!(
+        RuntimeConfig::default().with_overrides(vars(&[("SENSEI_CHUNK_SIZE", "big")])),
+        Err(SenseiError::Config(_))
+    ));
+
+    for overrides in [
+        vec![("SENSEI_CHUNK_OVERLAP", "1500")],
+        vec![("SENSEI_EMBEDDING_MODEL", "ollama::nomic-embed-text")],
+        vec![("SENSEI_EMBEDDING_DIMENSION", "0")],
+        vec![("SENSEI_RAG_MAX_DISTANCE", "-1")],
+        vec![("SENSEI_RAG_MIN_BM25", "-0.5")],
+        vec![("SENSEI_REACT_MAX_STEPS", "0")],
+    ] {
+        let config = RuntimeConfig::default()
+            .with_overrides(vars(&overrides))
+            .unwrap();
+        assert!(
+            matches!(config.validate(), Err(SenseiError::Config(_))),
+            "{:?} should be rejected",
+            overrides
+        );
+    }
+
+    let local = RuntimeConfig::default()
+        .with_overrides(vars(&[
+            ("SENSEI_EMBEDDING_MODEL", "ollama::nomic-embed-text"),
+            ("SENSEI_EMBEDDING_DIMENSION", "768"),
+        ]))
+        .unwrap();
+    local.validate().unwrap();
+    assert_eq!(local.embedding_config().dimension, 768);
+}
+
+#[tokio::test]
+async fn builder_assembles_the_declared_swarm() {
+    let prompts = config_file(
+        ".yaml",
+        "agents:\n  forensics:\n    prompt: You are a Forensics Examiner.\n    tier: smart\n    rag: false\n",
+    );
+    let config = RuntimeConfig {
+        prompts_path: prompts.path().to_path_buf(),
+        mcp_config: "/nonexistent/mcp_settings.json".into(),
+        ..RuntimeConfig::default()
+    };
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+
+    let runtime = SenseiRuntime::builder(config)
+        .with_llms(Arc::new(NamedLlm("fast")), Arc::new(NamedLlm("smart")))
+        .with_memory(memory)
+        .build()
+        .await
+        .unwrap();
+
+    let categories = runtime.orchestrator.categories().await;
+    assert!(categories.contains(&AgentCategory::new("forensics")));
+    assert!(categories.contains(&AgentCategory::new("red")));
+    assert!(runtime.tools.get("nmap").is_some());
+
+    // Agents run on the client of their tier.
+    let answer = runtime
+        .orchestrator
+        .dispatch(AgentCategory::new("forensics"), "What happened?")
+        .await;
+    assert_eq!(answer, "smart");
+
+    // The store was migrated.
+    runtime.memory.list_sessions().await.unwrap();
+}
+
+#[tokio::test]
+async fn builder_rejects_an_invalid_swarm() {
+    let prompts = config_file(".yaml", "agents:\n  forensics:\n    tier: smart\n");
+    let config = RuntimeConfig {
+        prompts_path: prompts.path().to_path_buf(),
+        mcp_config: "/nonexistent/mcp_settings.json".into(),
+        ..RuntimeConfig::default()
+    };
+    let result = SenseiRuntime::builder(config.clone())
+        .with_llms(Arc::new(NamedLlm("fast")), Arc::new(NamedLlm("smart")))
+        .with_memory(MemoryStore::new("sqlite::memory:").await.unwrap())
+        .build()
+        .await;
+    assert!(matches!(result, Err(SenseiError::Config(_))));
+
+    // Agents must only use tools of the registry.
+    let no_tools = SenseiRuntime::builder(RuntimeConfig {
+        prompts_path: "/nonexistent/prompts.yaml".into(),
+        ..config
+    })
+    .with_llms(Arc::new(NamedLlm("fast")), Arc::new(NamedLlm("smart")))
+    .with_memory(MemoryStore::new("sqlite::memory:").await.unwrap())
+    .with_tools(ToolRegistry::new())
+    .build()
+    .await;
+    assert!(matches!(no_tools, Err(SenseiError::Config(_))));
+}
@@ -1,9 +1,9 @@
 use async_trait::async_trait;
-use sensei_common::AgentCategory;
+use sensei_common::{AgentCategory, AskStreamEvent};
 use sensei_lib::agents::Agent;
 use sensei_lib::agents::specialists::SpecializedAgent;
 use sensei_lib::errors::SenseiError;
-use sensei_lib::llm::Llm;
+use sensei_lib::llm::{ChatTurn, Llm};
 use std::sync::{Arc, Mutex};
 
 struct MockLlm {
@@ -61,3 +61,57 @@ async fn specialist_uses_master_prompt_in_raw_mode() {
     assert!(prompt.contains("MASTER PROMPT"));
     assert!(!prompt.contains("SYSTEM PROMPT"));
 }
+
+#[tokio::test]
+async fn specialist_streams_tokens() {
+    let llm = Arc::new(MockLlm {
+        last_prompt: Mutex::new(String::new()),
+    });
+    let agent = SpecializedAgent::new(
+        llm.clone(),
+        AgentCategory::new("blue"),
+        "SYSTEM PROMPT",
+        None,
+    );
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let response = agent.process_stream("Query", &[], &tx).await;
+    drop(tx);
+
+    let mut streamed = String::new();
+    while let Some(event) = rx.recv().await {
+        if let AskStreamEvent::Token { content } = event {
+            streamed.push_str(&content);
+        }
+    }
+
+    assert_eq!(response, "Response");
+    assert_eq!(streamed, response);
+}
+
+#[tokio::test]
+async fn specialist_replays_conversation_history() {
+    let llm = Arc::new(MockLlm {
+        last_prompt: Mutex::new(String::new()),
+    });
+    let agent = SpecializedAgent::new(
+        llm.clone(),
+        AgentCategory::new("blue"),
+        "SYSTEM PROMPT",
+        None,
+    );
+
+    let history = vec![
+        ChatTurn::user("Explain Kerberoasting"),
+        ChatTurn::assistant("It abuses service tickets."),
+    ];
+    agent
+        .process_with_history("and how do I detect that?", &history)
+        .await;
+
+    let prompt = llm.last_prompt.lock().unwrap().clone();
+    assert!(prompt.contains("SYSTEM PROMPT"));
+    assert!(prompt.contains("User: Explain Kerberoasting"));
+    assert!(prompt.contains("Assistant: It abuses service tickets."));
+    assert!(prompt.ends_with("User Query: and how do I detect that?"));
+}
@@ -1,6 +1,12 @@
 use async_trait::async_trait;
-use sensei_common::AgentCategory;
-use sensei_lib::agents::{Agent, Orchestrator};
+use sensei_common::{AgentCategory, AskStreamEvent, Classification};
+use sensei_lib::agents::swarm::{build_agent, category_list};
+use sensei_lib::agents::{Agent, EventSender, Orchestrator};
+use sensei_lib::config::PromptsConfig;
+use sensei_lib::errors::SenseiError;
+use sensei_lib::llm::{ChatTurn, Llm};
+use sensei_lib::tools::ToolRegistry;
+use std::sync::{Arc, Mutex};
 
 struct MockRedAgent;
 
@@ -33,3 +39,273 @@ async fn swarm_routing_works() {
         .await;
     assert!(response_blue.contains("No agent") || response_blue.is_empty());
 }
+
+struct DelegatingAgent;
+
+#[async_trait]
+impl Agent for DelegatingAgent {
+    async fn process(&self, input: &str) -> String {
+        if input.contains("[OBSERVATION") {
+            return "Plan ready".to_string();
+        }
+        "[DELEGATE: red] Find the weak spot".to_string()
+    }
+    fn category(&self) -> AgentCategory {
+        AgentCategory::new("casual")
+    }
+}
+
+#[tokio::test]
+async fn swarm_streams_delegation_events() {
+    let orch = Orchestrator::new();
+    orch.register(Box::new(MockRedAgent)).await;
+    orch.register(Box::new(DelegatingAgent)).await;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let response = orch
+        .dispatch_stream(AgentCategory::new("casual"), "Audit the wifi", &[], &tx)
+        .await;
+    drop(tx);
+
+    let mut events = Vec::new();
+    while let Some(event) = rx.recv().await {
+        events.push(event);
+    }
+
+    assert_eq!(response, "Plan ready");
+    assert!(events.contains(&AskStreamEvent::Delegation {
+        from: AgentCategory::new("casual"),
+        to: AgentCategory::new("red"),
+    }));
+    assert_eq!(
+        events.last(),
+        Some(&AskStreamEvent::Token {
+            content: "Plan ready".to_string()
+        })
+    );
+}
+
+/// Streams its answer three characters at a time.
+struct ChunkedAgent(&'static str, fn(&str) -> String);
+
+#[async_trait]
+impl Agent for ChunkedAgent {
+    async fn process(&self, input: &str) -> String {
+        (self.1)(input)
+    }
+    async fn process_stream(
+        &self,
+        input: &str,
+        _history: &[ChatTurn],
+        events: &EventSender,
+    ) -> String {
+        let response = self.process(input).await;
+        let chars: Vec<char> = response.chars().collect();
+        for chunk in chars.chunks(3) {
+            let _ = events.send(AskStreamEvent::Token {
+                content: chunk.iter().collect(),
+            });
+        }
+        response
+    }
+    fn category(&self) -> AgentCategory {
+        AgentCategory::new(self.0)
+    }
+}
+
+#[tokio::test]
+async fn streamed_tokens_add_up_to_the_final_answer() {
+    let orch = Orchestrator::new();
+    orch.register(Box::new(ChunkedAgent("casual", |input| {
+        if input.contains("[OBSERVATION") {
+            "The weak spot is WPS.".to_string()
+        } else {
+            "Asking red.\n[DELEGATE: red] Find the weak spot".to_string()
+        }
+    })))
+    .await;
+    orch.register(Box::new(ChunkedAgent("red", |_| "WPS".to_string())))
+        .await;
+    orch.register(Box::new(ChunkedAgent("novice", |_| "Hi".to_string())))
+        .await;
+
+    for (category, expected) in [
+        ("casual", "Asking red.\nThe weak spot is WPS."),
+        ("novice", "Hi"),
+    ] {
+        let (response, tokens) = streamed(&orch, category).await;
+        assert_eq!(response, expected);
+        assert_eq!(tokens, response);
+    }
+}
+
+async fn streamed(orch: &Orchestrator, category: &str) -> (String, String) {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let response = orch
+        .dispatch_stream(AgentCategory::new(category), "Audit the wifi", &[], &tx)
+        .await;
+    drop(tx);
+    let mut streamed = String::new();
+    while let Some(event) = rx.recv().await {
+        if let AskStreamEvent::Token { content } = event {
+            streamed.push_str(&content);
+        }
+    }
+    (response, streamed)
+}
+
+#[tokio::test]
+async fn streamed_errors_add_up_to_the_final_answer() {
+    let orch = Orchestrator::new();
+    let (response, tokens) = streamed(&orch, "red").await;
+    assert!(response.contains("No agent found"), "{}", response);
+    assert_eq!(tokens, response);
+
+    orch.register(Box::new(ChunkedAgent("casual", |_| {
+        "[DELEGATE: casual] Again".to_string()
+    })))
+    .await;
+    let (response, tokens) = streamed(&orch, "casual").await;
+    assert!(response.contains("recursion limit"), "{}", response);
+    assert_eq!(tokens, response);
+}
+
+/// Agent with a clearance that records the inputs it receives.
+struct ClearedAgent {
+    name: &'static str,
+    clearance: Classification,
+    reply: fn(&str) -> String,
+    inputs: Arc<Mutex<Vec<String>>>,
+}
+
+impl ClearedAgent {
+    fn new(name: &'static str, clearance: Classification, reply: fn(&str) -> String) -> Self {
+        Self {
+            name,
+            clearance,
+            reply,
+            inputs: Arc::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl Agent for ClearedAgent {
+    async fn process(&self, input: &str) -> String {
+        self.inputs.lock().unwrap().push(input.to_string());
+        (self.reply)(input)
+    }
+    fn category(&self) -> AgentCategory {
+        AgentCategory::new(self.name)
+    }
+    fn clearance(&self) -> Classification {
+        self.clearance
+    }
+}
+
+/// Delegates to `osint` until it gets an observation, then answers with it.
+fn delegate_to_osint(input: &str) -> String {
+    match input.split_once("[OBSERVATION from osint]\n") {
+        Some((_, observation)) => observation.to_string(),
+        None => "[DELEGATE: osint] Who owns the target?".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn delegation_does_not_write_down() {
+    let osint = ClearedAgent::new("osint", Classification::Unclassified, |_| {
+        "ACME Corp".to_string()
+    });
+    let reached_osint = osint.inputs.clone();
+    let orch = Orchestrator::new();
+    orch.register(Box::new(osint)).await;
+    orch.register(Box::new(ClearedAgent::new(
+        "red",
+        Classification::Secret,
+        delegate_to_osint,
+    )))
+    .await;
+    orch.register(Box::new(ClearedAgent::new(
+        "casual",
+        Classification::Unclassified,
+        delegate_to_osint,
+    )))
+    .await;
+
+    // Red may hold secret context: handing its payload to osint is refused, streamed or not.
+    let response = orch.dispatch(AgentCategory::new("red"), "Audit ACME").await;
+    assert!(response.contains("refused"), "{}", response);
+    let (response, tokens) = streamed(&orch, "red").await;
+    assert!(response.contains("refused"), "{}", response);
+    assert_eq!(tokens, response);
+    assert!(reached_osint.lock().unwrap().is_empty());
+
+    // Peers at the same level still cooperate.
+    let response = orch
+        .dispatch(AgentCategory::new("casual"), "Audit ACME")
+        .await;
+    assert_eq!(response, "ACME Corp");
+    assert_eq!(*reached_osint.lock().unwrap(), vec!["Who owns the target?"]);
+}
+
+struct SilentLlm;
+
+#[async_trait]
+impl Llm for SilentLlm {
+    async fn generate(&self, _prompt: &str) -> Result<String, SenseiError> {
+        Ok(String::new())
+    }
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>, SenseiError> {
+        Ok(vec![])
+    }
+}
+
+#[tokio::test]
+async fn declared_agents_are_built_and_registered() {
+    let config: PromptsConfig = serde_yaml::from_str(
+        r#"
+agents:
+  forensics:
+    prompt: "You are a Forensics Examiner."
+    rag: false
+    clearance: confidential
+  pentest:
+    prompt: "You are a Pentester."
+    tools: [nmap]
+"#,
+    )
+    .unwrap();
+    let definitions = config.agent_definitions().unwrap();
+    let tools = ToolRegistry::builtin();
+
+    let orch = Orchestrator::new();
+    for definition in &definitions {
+        let agent = build_agent(definition, Arc::new(SilentLlm), &tools, 3).unwrap();
+        orch.register(agent).await;
+    }
+
+    let forensics = AgentCategory::new("forensics");
+    assert!(!orch.uses_rag(&forensics).await);
+    assert_eq!(
+        orch.clearance(&forensics).await,
+        Classification::Confidential
+    );
+    assert!(orch.uses_rag(&AgentCategory::new("pentest")).await);
+    assert!(category_list(&definitions).contains("FORENSICS, NOVICE, OSINT, PENTEST"));
+}
+
+#[test]
+fn unknown_tools_are_rejected() {
+    let config: PromptsConfig =
+        serde_yaml::from_str("agents:\n  pentest:\n    prompt: P\n    tools: [metasploit]\n")
+            .unwrap();
+    let definitions = config.agent_definitions().unwrap();
+    let pentest = definitions
+        .iter()
+        .find(|d| d.category.as_str() == "pentest")
+        .unwrap();
+    assert!(matches!(
+        build_agent(pentest, Arc::new(SilentLlm), &ToolRegistry::builtin(), 3),
+        Err(SenseiError::Config(_))
+    ));
+}
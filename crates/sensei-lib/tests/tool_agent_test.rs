@@ -4,7 +4,8 @@ use sensei_lib::agents::Agent;
 use sensei_lib::agents::action::ToolExecutorAgent;
 use sensei_lib::errors::SenseiError;
 use sensei_lib::llm::Llm;
-use sensei_lib::tools::Tool;
+use sensei_lib::tools::{Tool, ToolInvocation, ToolRegistry, ToolSpec};
+use serde_json::{Value, json};
 use std::sync::{Arc, Mutex};
 
 struct MockTool {
@@ -12,6 +13,63 @@ struct MockTool {
     was_called: Arc<Mutex<bool>>,
 }
 
+/// Records the structured arguments it receives.
+struct ScanTool {
+    received: Arc<Mutex<Option<Value>>>,
+}
+
+#[async_trait]
+impl Tool for ScanTool {
+    fn name(&self) -> &str {
+        "scan"
+    }
+    fn description(&self) -> &str {
+        "Scan a target"
+    }
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": { "target": { "type": "string" } },
+            "required": ["target"]
+        })
+    }
+    async fn execute(&self, args: &str) -> Result<String, SenseiError> {
+        self.call(&json!({ "target": args })).await
+    }
+    async fn call(&self, arguments: &Value) -> Result<String, SenseiError> {
+        *self.received.lock().unwrap() = Some(arguments.clone());
+        Ok("Scanned".to_string())
+    }
+}
+
+/// LLM with native function calling that always picks `scan`.
+struct FunctionCallingLlm {
+    offered: Arc<Mutex<Vec<ToolSpec>>>,
+}
+
+#[async_trait]
+impl Llm for FunctionCallingLlm {
+    async fn generate(&self, _prompt: &str) -> Result<String, SenseiError> {
+        Err(SenseiError::Llm(
+            "prompt path should not be used".to_string(),
+        ))
+    }
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>, SenseiError> {
+        Ok(vec![])
+    }
+    async fn select_tool(
+        &self,
+        _prompt: &str,
+        tools: &[ToolSpec],
+    ) -> Result<Option<ToolInvocation>, SenseiError> {
+        *self.offered.lock().unwrap() = tools.to_vec();
+        Ok(Some(ToolInvocation {
+            tool_name: "scan".to_string(),
+            arguments: json!({ "target": "10.0.0.1" }),
+        }))
+    }
+}
+
 #[async_trait]
 impl Tool for MockTool {
     fn name(&self) -> &str {
@@ -75,3 +133,82 @@ async fn tool_agent_handles_unknown_tool() {
 
     assert!(response.contains("not found in registry"));
 }
+
+#[tokio::test]
+async fn tool_agent_uses_native_function_calling() {
+    let received = Arc::new(Mutex::new(None));
+    let offered = Arc::new(Mutex::new(vec![]));
+
+    let mut registry = ToolRegistry::new();
+    registry.register(Arc::new(ScanTool {
+        received: received.clone(),
+    }));
+
+    let llm = Arc::new(FunctionCallingLlm {
+        offered: offered.clone(),
+    });
+    let agent = ToolExecutorAgent::with_registry(llm, AgentCategory::new("action"), registry);
+    let response = agent.process("Scan 10.0.0.1").await;
+
+    assert!(response.contains("Scanned"));
+    assert_eq!(
+        *received.lock().unwrap(),
+        Some(json!({ "target": "10.0.0.1" }))
+    );
+
+    let offered = offered.lock().unwrap();
+    assert_eq!(offered.len(), 1);
+    assert_eq!(offered[0].description, "Scan a target");
+    assert_eq!(offered[0].input_schema["required"][0], "target");
+}
+
+#[tokio::test]
+async fn tool_agent_falls_back_to_prompted_json_arguments() {
+    let received = Arc::new(Mutex::new(None));
+
+    let llm = Arc::new(MockLlm {
+        response: r#"Sure: {"tool_name": "scan", "arguments": {"target": "example.com"}}"#
+            .to_string(),
+    });
+    let mut agent = ToolExecutorAgent::new(llm, AgentCategory::new("action"));
+    agent.register_tool(Box::new(ScanTool {
+        received: received.clone(),
+    }));
+
+    let response = agent.process("Scan example.com").await;
+
+    assert!(response.contains("Scanned"));
+    assert_eq!(
+        *received.lock().unwrap(),
+        Some(json!({ "target": "example.com" }))
+    );
+}
+
+#[tokio::test]
+async fn tool_agent_explains_when_no_tool_fits() {
+    let llm = Arc::new(MockLlm {
+        response: r#"{"tool_name": "none", "arguments": {"reason": "no target given"}}"#
+            .to_string(),
+    });
+    let agent = ToolExecutorAgent::with_registry(
+        llm,
+        AgentCategory::new("action"),
+        ToolRegistry::builtin(),
+    );
+
+    let response = agent.process("Scan something").await;
+    assert_eq!(response, "I cannot perform this action: no target given");
+}
+
+#[tokio::test]
+async fn tool_agent_survives_a_closing_brace_before_the_json() {
+    for response in ["} then {", "no braces at all", "{ unterminated"] {
+        let llm = Arc::new(MockLlm {
+            response: response.to_string(),
+        });
+        let agent = ToolExecutorAgent::new(llm, AgentCategory::new("action"));
+
+        let response = agent.process("Run something").await;
+        assert!(response.contains("LLM Decision Failed"), "{}", response);
+    }
+}
@@ -1,6 +1,6 @@
-use sensei_lib::tools::Tool;
 use sensei_lib::tools::nmap::NmapTool;
 use sensei_lib::tools::system::SystemTool;
+use sensei_lib::tools::{Tool, ToolRegistry};
 
 #[tokio::test]
 async fn nmap_sanitization_works() {
@@ -43,3 +43,34 @@ async fn system_allowlist_works() {
             .contains("Unknown or disallowed")
     );
 }
+
+#[test]
+fn builtin_registry_describes_its_tools() {
+    let registry = ToolRegistry::builtin();
+    assert_eq!(registry.names(), vec!["nmap", "system_diagnostic"]);
+
+    for spec in registry.specs() {
+        assert!(
+            !spec.description.is_empty(),
+            "{} has no description",
+            spec.name
+        );
+        assert_eq!(spec.input_schema["type"], "object");
+    }
+
+    let system = registry.subset(&["system_diagnostic"]);
+    assert_eq!(system.len(), 1);
+    assert!(system.get("nmap").is_none());
+}
+
+#[tokio::test]
+async fn system_tool_accepts_structured_arguments() {
+    let res = SystemTool
+        .call(&serde_json::json!({ "command": "reboot" }))
+        .await;
+    assert!(
+        res.unwrap_err()
+            .to_string()
+            .contains("Unknown or disallowed")
+    );
+}
@@ -0,0 +1,302 @@
+use crate::{JsonRpcError, JsonRpcRequest, JsonRpcResponse, McpServer};
+use axum::{
+    Json, Router,
+    extract::{Query, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::{self, Next},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::{get, post},
+};
+use futures::Stream;
+use sensei_lib::auth::hash_key;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Session header of the Streamable HTTP transport.
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Streamable HTTP sessions unused for this long are forgotten.
+pub const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    /// Browser origins allowed to connect besides localhost, e.g. `https://ide.example.com`.
+    pub allowed_origins: Vec<String>,
+    /// Required as `Authorization: Bearer <token>` on every request when set.
+    pub token: Option<String>,
+    /// Idle time after which a Streamable HTTP session expires.
+    pub session_ttl: Duration,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            token: None,
+            session_ttl: DEFAULT_SESSION_TTL,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// The tools run scans and write to the knowledge base: without a token,
+    /// only loopback addresses may be listened on.
+    pub fn check_listener(&self, addr: &SocketAddr) -> anyhow::Result<()> {
+        if self.token.is_none() && !addr.ip().is_loopback() {
+            anyhow::bail!(
+                "Refusing to serve MCP on {} without a token: set SENSEI_MCP_TOKEN or listen on a loopback address",
+                addr
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct HttpState {
+    server: Arc<McpServer>,
+    config: Arc<HttpConfig>,
+    // Hash of the bearer token, when one is required
+    token_hash: Option<String>,
+    // Streamable HTTP sessions, with when they were last used
+    sessions: Arc<Mutex<HashMap<String, Instant>>>,
+    // Legacy SSE sessions, with the event stream their responses are sent on
+    streams: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Event>>>>,
+}
+
+/// MCP over HTTP for any number of concurrent clients:
+/// Streamable HTTP on `/mcp`, and the legacy HTTP+SSE transport on `/sse` and `/messages`.
+pub fn router(server: Arc<McpServer>, config: HttpConfig) -> Router {
+    let state = HttpState {
+        server,
+        token_hash: config.token.as_deref().map(hash_key),
+        config: Arc::new(config),
+        sessions: Arc::default(),
+        streams: Arc::default(),
+    };
+    Router::new()
+        .route("/mcp", post(post_message).delete(end_session))
+        .route("/sse", get(open_stream))
+        .route("/messages", post(post_stream_message))
+        .layer(middleware::from_fn_with_state(state.clone(), check_token))
+        .layer(middleware::from_fn_with_state(state.clone(), check_origin))
+        .with_state(state)
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Reject browsers on other sites (DNS rebinding). Clients that send no `Origin` are not browsers.
+async fn check_origin(State(state): State<HttpState>, request: Request, next: Next) -> Response {
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        let allowed = origin.to_str().is_ok_and(|origin| {
+            is_local(origin) || state.config.allowed_origins.iter().any(|o| o == origin)
+        });
+        if !allowed {
+            return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+        }
+    }
+    next.run(request).await
+}
+
+/// Require the bearer token when one is configured. Hashes are compared, not the tokens.
+async fn check_token(State(state): State<HttpState>, request: Request, next: Next) -> Response {
+    if let Some(expected) = &state.token_hash {
+        let token = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        if token.map(hash_key).as_ref() != Some(expected) {
+            return (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                "Missing or invalid bearer token",
+            )
+                .into_response();
+        }
+    }
+    next.run(request).await
+}
+
+fn is_local(origin: &str) -> bool {
+    let authority = origin.split_once("://").map_or(origin, |(_, rest)| rest);
+    let host = match authority.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+        None => authority.split([':', '/']).next().unwrap_or_default(),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// A JSON-RPC message or batch, and whether it was a batch.
+fn parse(body: &str) -> Result<(Vec<JsonRpcRequest>, bool), serde_json::Error> {
+    let value: Value = serde_json::from_str(body)?;
+    let batch = value.is_array();
+    let messages = match value {
+        Value::Array(batch) => batch,
+        message => vec![message],
+    };
+    let requests = messages
+        .into_iter()
+        .map(serde_json::from_value)
+        .collect::<Result<_, _>>()?;
+    Ok((requests, batch))
+}
+
+/// Bodies that do not parse are answered with a JSON-RPC parse error.
+fn parse_error(e: serde_json::Error) -> Response {
+    let response = JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id: None,
+        result: None,
+        error: Some(JsonRpcError {
+            code: -32700,
+            message: e.to_string(),
+        }),
+    };
+    (StatusCode::BAD_REQUEST, Json(response)).into_response()
+}
+
+/// Handle the messages concurrently. Notifications (no id) get no response.
+async fn handle(server: &McpServer, requests: Vec<JsonRpcRequest>) -> Vec<JsonRpcResponse> {
+    let responses = requests.into_iter().map(|r| server.handle_message(r));
+    futures::future::join_all(responses)
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+// --- Streamable HTTP ---
+
+async fn post_message(
+    State(state): State<HttpState>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let (requests, batch) = match parse(&body) {
+        Ok(parsed) => parsed,
+        Err(e) => return parse_error(e),
+    };
+
+    // initialize starts a session; everything else must carry a live one.
+    let ttl = state.config.session_ttl;
+    let session = if requests.iter().any(|r| r.method == "initialize") {
+        let session = Uuid::new_v4().simple().to_string();
+        let mut sessions = lock(&state.sessions);
+        // Clients rarely end their sessions: sweep the idle ones as new ones start.
+        sessions.retain(|_, used| used.elapsed() < ttl);
+        sessions.insert(session.clone(), Instant::now());
+        session
+    } else {
+        let Some(session) = headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok()) else {
+            return (StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header").into_response();
+        };
+        let mut sessions = lock(&state.sessions);
+        match sessions.get_mut(session) {
+            Some(used) if used.elapsed() < ttl => *used = Instant::now(),
+            _ => {
+                sessions.remove(session);
+                return (StatusCode::NOT_FOUND, "Unknown or expired session").into_response();
+            }
+        }
+        session.to_string()
+    };
+
+    let mut responses = handle(&state.server, requests).await;
+    let mut response = match (responses.len(), batch) {
+        (0, _) => StatusCode::ACCEPTED.into_response(),
+        (_, true) => Json(responses).into_response(),
+        (_, false) => Json(responses.remove(0)).into_response(),
+    };
+    if let Ok(value) = HeaderValue::from_str(&session) {
+        response.headers_mut().insert(SESSION_HEADER, value);
+    }
+    response
+}
+
+async fn end_session(State(state): State<HttpState>, headers: HeaderMap) -> StatusCode {
+    let session = headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok());
+    match session {
+        Some(session) if lock(&state.sessions).remove(session).is_some() => StatusCode::OK,
+        _ => StatusCode::NOT_FOUND,
+    }
+}
+
+// --- Legacy HTTP+SSE ---
+
+/// Removes a legacy session when its event stream is dropped (client disconnected).
+struct StreamSession {
+    id: String,
+    streams: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Event>>>>,
+}
+
+impl Drop for StreamSession {
+    fn drop(&mut self) {
+        lock(&self.streams).remove(&self.id);
+    }
+}
+
+async fn open_stream(
+    State(state): State<HttpState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let id = Uuid::new_v4().simple().to_string();
+    let (tx, rx) = mpsc::unbounded_channel();
+    let _ = tx.send(
+        Event::default()
+            .event("endpoint")
+            .data(format!("/messages?sessionId={}", id)),
+    );
+    lock(&state.streams).insert(id.clone(), tx);
+
+    let session = StreamSession {
+        id,
+        streams: state.streams.clone(),
+    };
+    let events = futures::stream::unfold((rx, session), |(mut rx, session)| async move {
+        rx.recv().await.map(|event| (Ok(event), (rx, session)))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[derive(Deserialize)]
+struct StreamQuery {
+    #[serde(rename = "sessionId")]
+    session_id: String,
+}
+
+async fn post_stream_message(
+    State(state): State<HttpState>,
+    Query(query): Query<StreamQuery>,
+    body: String,
+) -> Response {
+    let Some(events) = lock(&state.streams).get(&query.session_id).cloned() else {
+        return (StatusCode::NOT_FOUND, "Unknown session").into_response();
+    };
+    let (requests, _) = match parse(&body) {
+        Ok(parsed) => parsed,
+        Err(e) => return parse_error(e),
+    };
+
+    // Responses go out on the event stream; the POST is only acknowledged.
+    let server = state.server.clone();
+    tokio::spawn(async move {
+        for response in handle(&server, requests).await {
+            if let Ok(data) = serde_json::to_string(&response) {
+                let _ = events.send(Event::default().event("message").data(data));
+            }
+        }
+    });
+    StatusCode::ACCEPTED.into_response()
+}
@@ -1,11 +1,19 @@
 use anyhow::Result;
-use sensei_lib::memory::MemoryStore;
-use sensei_lib::tools::Tool;
-use sensei_lib::tools::nmap::NmapTool;
-use sensei_lib::tools::system::SystemTool;
+use sensei_common::{Classification, KNOWLEDGE_URI_PREFIX, knowledge_uri};
+use sensei_lib::config::{AgentDefinition, PromptArgument, PromptsConfig};
+use sensei_lib::memory::{DocumentFilter, MemoryStore};
+use sensei_lib::reload::read_prompts;
+use sensei_lib::runtime::SenseiRuntime;
+use sensei_lib::tools::ToolRegistry;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use tracing::debug;
+
+pub mod http;
+pub mod stdio;
+pub mod swarm;
 
 // --- JSON-RPC Types ---
 
@@ -33,27 +41,57 @@ pub struct JsonRpcError {
     pub message: String,
 }
 
-// --- MCP Types ---
+impl JsonRpcError {
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self {
+            code: -32602,
+            message: message.into(),
+        }
+    }
 
-#[derive(Serialize)]
-struct ToolDescription {
-    name: String,
-    description: String,
-    input_schema: Value,
+    fn internal(message: impl Into<String>) -> Self {
+        Self {
+            code: -32603,
+            message: message.into(),
+        }
+    }
 }
 
+// --- MCP Types ---
+
+/// Protocol revisions this server speaks, newest first.
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Items per page of the list methods.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
 #[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 struct ResourceDescription {
     uri: String,
     name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     mime_type: Option<String>,
 }
 
+#[derive(Serialize)]
+struct PromptDescription<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    arguments: &'a [PromptArgument],
+}
+
 // --- Server State ---
 
 pub struct McpServer {
     memory: MemoryStore,
-    tools: HashMap<String, Box<dyn Tool>>,
+    tools: ToolRegistry,
+    page_size: usize,
+    // Agents published as prompts; the built-in ones without a file
+    prompts_path: Option<PathBuf>,
+    // Highest classification of the knowledge resources served
+    clearance: Classification,
 }
 
 impl McpServer {
@@ -61,29 +99,88 @@ impl McpServer {
         let memory = MemoryStore::new(db_url).await?;
         memory.migrate().await?;
 
-        let mut tools: HashMap<String, Box<dyn Tool>> = HashMap::new();
+        Ok(Self::from_memory(memory))
+    }
 
-        let nmap = NmapTool;
-        tools.insert(nmap.name().to_string(), Box::new(nmap));
+    /// Serve an already migrated store. Only unclassified knowledge is served
+    /// unless [`with_clearance`](McpServer::with_clearance) says otherwise.
+    pub fn from_memory(memory: MemoryStore) -> Self {
+        Self {
+            memory,
+            tools: ToolRegistry::builtin(),
+            page_size: DEFAULT_PAGE_SIZE,
+            prompts_path: None,
+            clearance: Classification::Unclassified,
+        }
+    }
+
+    /// Serve knowledge resources up to `clearance`; documents above it are neither listed nor read.
+    pub fn with_clearance(mut self, clearance: Classification) -> Self {
+        self.clearance = clearance;
+        self
+    }
+
+    /// Publish the agents of a `prompts.yaml` as prompts. The file is read on every request.
+    pub fn with_prompts(mut self, path: impl Into<PathBuf>) -> Self {
+        self.prompts_path = Some(path.into());
+        self
+    }
 
-        let system = SystemTool;
-        tools.insert(system.name().to_string(), Box::new(system));
+    /// Serve the runtime's knowledge base, tools and agents, with the swarm tools.
+    /// `clearance` is the highest classification the client reads, through resources or tools.
+    pub fn from_runtime(runtime: &SenseiRuntime, clearance: Classification) -> Self {
+        Self {
+            tools: runtime.tools.clone(),
+            ..Self::from_memory(runtime.memory.clone())
+        }
+        .with_swarm(swarm::SwarmTools::new(runtime, clearance))
+        .with_prompts(&runtime.config.prompts_path)
+    }
 
-        Ok(Self { memory, tools })
+    /// Also expose the swarm as the `ask`, `search_knowledge`, `add_knowledge` and `classify` tools.
+    /// Resources are then served up to the swarm's clearance as well.
+    pub fn with_swarm(mut self, swarm: swarm::SwarmTools) -> Self {
+        self.clearance = swarm.clearance;
+        swarm.register(&mut self.tools);
+        self
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Handle any incoming message. Notifications (no id) are never answered.
+    pub async fn handle_message(&self, req: JsonRpcRequest) -> Option<JsonRpcResponse> {
+        if req.id.is_none() {
+            self.handle_notification(&req);
+            return None;
+        }
+        Some(self.handle_request(req).await)
     }
 
     pub async fn handle_request(&self, req: JsonRpcRequest) -> JsonRpcResponse {
         let id = req.id.clone();
-        let result = match req.method.as_str() {
-            "initialize" => self.handle_initialize(req.params.clone()).await,
-            "tools/list" => self.handle_tools_list().await,
-            "tools/call" => self.handle_tools_call(req.params.clone()).await,
-            "resources/list" => self.handle_resources_list().await,
-            "resources/read" => self.handle_resources_read(req.params.clone()).await,
-            _ => Err(JsonRpcError {
-                code: -32601,
-                message: format!("Method '{}' not found", req.method),
-            }),
+        let result = if req.jsonrpc != "2.0" {
+            Err(JsonRpcError {
+                code: -32600,
+                message: "Invalid Request: jsonrpc must be \"2.0\"".into(),
+            })
+        } else {
+            match req.method.as_str() {
+                "initialize" => self.handle_initialize(req.params.clone()).await,
+                "ping" => Ok(json!({})),
+                "tools/list" => self.handle_tools_list(req.params.clone()).await,
+                "tools/call" => self.handle_tools_call(req.params.clone()).await,
+                "resources/list" => self.handle_resources_list(req.params.clone()).await,
+                "resources/read" => self.handle_resources_read(req.params.clone()).await,
+                "prompts/list" => self.handle_prompts_list(req.params.clone()).await,
+                "prompts/get" => self.handle_prompts_get(req.params.clone()).await,
+                _ => Err(JsonRpcError {
+                    code: -32601,
+                    message: format!("Method '{}' not found", req.method),
+                }),
+            }
         };
 
         match result {
@@ -102,151 +199,203 @@ impl McpServer {
         }
     }
 
-    async fn handle_initialize(&self, _params: Option<Value>) -> Result<Value, JsonRpcError> {
-        Ok(
-            json!({ "protocolVersion": "0.1.0", "server": { "name": "sensei-mcp", "version": "0.1.0" }, "capabilities": { "tools": {}, "resources": {} } }),
-        )
+    fn handle_notification(&self, req: &JsonRpcRequest) {
+        // Requests are answered as soon as they are done, so there is nothing to cancel or
+        // set up; other notifications are ignored as the spec requires.
+        debug!("Notification {}", req.method);
     }
 
-    async fn handle_tools_list(&self) -> Result<Value, JsonRpcError> {
-        let tools_list = vec![
-            ToolDescription {
-                name: "nmap".to_string(),
-                description: "Run a network scan on a target.".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "target": { "type": "string", "description": "IP or Hostname" }
-                    },
-                    "required": ["target"]
-                }),
-            },
-            ToolDescription {
-                name: "system_diagnostic".to_string(),
-                description: "Run system checks (uptime, df, free).".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "command": {
-                            "type": "string",
-                            "enum": ["uptime", "disk", "memory", "whoami", "date"],
-                            "description": "Diagnostic command to run"
-                        }
-                    },
-                    "required": ["command"]
-                }),
-            },
-        ];
+    async fn handle_initialize(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        // Echo the client's version when we speak it, otherwise offer our latest.
+        let requested = params
+            .as_ref()
+            .and_then(|p| p.get("protocolVersion"))
+            .and_then(|v| v.as_str());
+        let version = requested
+            .filter(|v| SUPPORTED_PROTOCOL_VERSIONS.contains(v))
+            .unwrap_or(SUPPORTED_PROTOCOL_VERSIONS[0]);
+
+        Ok(json!({
+            "protocolVersion": version,
+            "serverInfo": { "name": "sensei-mcp", "version": env!("CARGO_PKG_VERSION") },
+            "capabilities": { "tools": {}, "resources": {}, "prompts": {} }
+        }))
+    }
 
-        Ok(json!({ "tools": tools_list }))
+    /// Offset encoded in the `cursor` param; absent means the first page.
+    fn cursor(params: &Option<Value>) -> Result<usize, JsonRpcError> {
+        match params.as_ref().and_then(|p| p.get("cursor")) {
+            None | Some(Value::Null) => Ok(0),
+            Some(cursor) => cursor
+                .as_str()
+                .and_then(|c| c.parse().ok())
+                .ok_or_else(|| JsonRpcError::invalid_params("Invalid cursor")),
+        }
+    }
+
+    /// Add `nextCursor` to a page result when more items follow.
+    fn page(mut result: Value, next: usize, total: usize) -> Value {
+        if next < total {
+            result["nextCursor"] = json!(next.to_string());
+        }
+        result
+    }
+
+    async fn handle_tools_list(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let offset = Self::cursor(&params)?;
+        let specs = self.tools.specs();
+        let tools: Vec<_> = specs.iter().skip(offset).take(self.page_size).collect();
+        let next = offset + tools.len();
+        Ok(Self::page(json!({ "tools": tools }), next, specs.len()))
     }
 
     async fn handle_tools_call(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
-        let params = params.ok_or(JsonRpcError {
-            code: -32602,
-            message: "Missing params".into(),
-        })?;
+        let params = params.ok_or(JsonRpcError::invalid_params("Missing params"))?;
 
         let name = params
             .get("name")
             .and_then(|v| v.as_str())
-            .ok_or(JsonRpcError {
-                code: -32602,
-                message: "Missing tool name".into(),
-            })?;
+            .ok_or(JsonRpcError::invalid_params("Missing tool name"))?;
 
         let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
 
-        if let Some(tool) = self.tools.get(name) {
-            let arg_str = if name == "nmap" {
-                arguments
-                    .get("target")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("")
-                    .to_string()
-            } else if name == "system_diagnostic" {
-                arguments
-                    .get("command")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("")
-                    .to_string()
-            } else {
-                return Err(JsonRpcError {
-                    code: -32602,
-                    message: "Unknown tool signature".into(),
-                });
-            };
-
-            match tool.execute(&arg_str).await {
-                Ok(output) => Ok(json!({ "content": [{ "type": "text", "text": output }] })),
-                Err(e) => Err(JsonRpcError {
-                    code: -32000,
-                    message: e.to_string(),
-                }),
+        let tool = self
+            .tools
+            .get(name)
+            .ok_or_else(|| JsonRpcError::invalid_params(format!("Unknown tool: {}", name)))?;
+
+        // A failing tool is a result the model can see and react to, not a protocol error.
+        Ok(match tool.call(&arguments).await {
+            Ok(output) => json!({ "content": [{ "type": "text", "text": output }] }),
+            Err(e) => {
+                json!({ "content": [{ "type": "text", "text": e.to_string() }], "isError": true })
             }
-        } else {
-            Err(JsonRpcError {
-                code: -32601,
-                message: format!("Tool {} not found", name),
+        })
+    }
+
+    fn agents(&self) -> Result<Vec<AgentDefinition>, JsonRpcError> {
+        let prompts = match &self.prompts_path {
+            Some(path) => read_prompts(path),
+            None => Ok(PromptsConfig::default()),
+        };
+        prompts
+            .and_then(|p| p.agent_definitions())
+            .map_err(|e| JsonRpcError::internal(e.to_string()))
+    }
+
+    async fn handle_prompts_list(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let offset = Self::cursor(&params)?;
+        let agents = self.agents()?;
+        let prompts: Vec<PromptDescription> = agents
+            .iter()
+            .skip(offset)
+            .take(self.page_size)
+            .map(|agent| PromptDescription {
+                name: agent.category.as_str(),
+                description: agent.description.as_deref(),
+                arguments: &agent.arguments,
             })
+            .collect();
+        let next = offset + prompts.len();
+        Ok(Self::page(
+            json!({ "prompts": prompts }),
+            next,
+            agents.len(),
+        ))
+    }
+
+    async fn handle_prompts_get(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let params = params.ok_or(JsonRpcError::invalid_params("Missing params"))?;
+        let name = params
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or(JsonRpcError::invalid_params("Missing prompt name"))?;
+        let arguments: HashMap<String, String> = match params.get("arguments") {
+            None | Some(Value::Null) => HashMap::new(),
+            Some(arguments) => serde_json::from_value(arguments.clone())
+                .map_err(|_| JsonRpcError::invalid_params("Prompt arguments must be strings"))?,
+        };
+
+        let agents = self.agents()?;
+        let agent = agents
+            .iter()
+            .find(|agent| agent.category.as_str() == name)
+            .ok_or_else(|| JsonRpcError::invalid_params(format!("Unknown prompt: {}", name)))?;
+        let text = agent
+            .render(&arguments)
+            .map_err(|e| JsonRpcError::invalid_params(e.to_string()))?;
+
+        let mut result = json!({
+            "messages": [{ "role": "user", "content": { "type": "text", "text": text } }]
+        });
+        if let Some(description) = &agent.description {
+            result["description"] = json!(description);
         }
+        Ok(result)
     }
 
-    async fn handle_resources_list(&self) -> Result<Value, JsonRpcError> {
-        let docs = self
+    async fn handle_resources_list(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
+        let offset = Self::cursor(&params)?;
+        let (docs, total) = self
             .memory
-            .list_documents()
+            .list_documents_page(
+                &DocumentFilter::default(),
+                self.clearance,
+                "mcp",
+                self.page_size as i64,
+                offset as i64,
+            )
             .await
-            .map_err(|e| JsonRpcError {
-                code: -32603,
-                message: e.to_string(),
-            })?;
+            .map_err(|e| JsonRpcError::internal(e.to_string()))?;
 
+        let next = offset + docs.len();
         let resources: Vec<ResourceDescription> = docs
             .into_iter()
-            .map(|(id, snippet)| ResourceDescription {
-                uri: format!("sensei://knowledge/{}", id),
-                name: format!("Document #{} - {}...", id, snippet.replace('\n', " ")),
-                mime_type: Some("text/plain".to_string()),
+            .map(|doc| {
+                let snippet: String = doc.snippet.chars().take(50).collect();
+                ResourceDescription {
+                    uri: knowledge_uri(doc.id),
+                    name: format!("Document #{} - {}...", doc.id, snippet.replace('\n', " ")),
+                    mime_type: Some("text/plain".to_string()),
+                }
             })
             .collect();
 
-        Ok(json!({ "resources": resources }))
+        Ok(Self::page(
+            json!({ "resources": resources }),
+            next,
+            total as usize,
+        ))
     }
 
     async fn handle_resources_read(&self, params: Option<Value>) -> Result<Value, JsonRpcError> {
-        let params = params.ok_or(JsonRpcError {
-            code: -32602,
-            message: "Missing params".into(),
-        })?;
+        let params = params.ok_or(JsonRpcError::invalid_params("Missing params"))?;
         let uri = params
             .get("uri")
             .and_then(|v| v.as_str())
-            .ok_or(JsonRpcError {
-                code: -32602,
-                message: "Missing uri".into(),
-            })?;
+            .ok_or(JsonRpcError::invalid_params("Missing uri"))?;
 
-        if let Some(id_str) = uri.strip_prefix("sensei://knowledge/")
-            && let Ok(id) = id_str.parse::<i64>()
+        let document = match uri
+            .strip_prefix(KNOWLEDGE_URI_PREFIX)
+            .and_then(|id| id.parse::<i64>().ok())
         {
-            let content = self
+            // Documents above the clearance are reported as missing
+            Some(id) => self
                 .memory
-                .get_document(id)
+                .read_document(id, self.clearance, "mcp")
                 .await
-                .map_err(|e| JsonRpcError {
-                    code: -32603,
-                    message: format!("Failed to read doc: {}", e),
-                })?;
-
-            return Ok(
-                json!({ "contents": [{ "uri": uri, "mimeType": "text/plain", "text": content }] }),
-            );
-        }
+                .map_err(|e| JsonRpcError::internal(format!("Failed to read doc: {}", e)))?,
+            None => None,
+        };
 
-        Err(JsonRpcError {
-            code: -32602,
-            message: "Resource not found or invalid URI".into(),
-        })
+        match document {
+            Some(document) => Ok(
+                json!({ "contents": [{ "uri": uri, "mimeType": "text/plain", "text": document.content }] }),
+            ),
+            None => Err(JsonRpcError {
+                code: -32002,
+                message: format!("Resource not found: {}", uri),
+            }),
+        }
     }
 }
@@ -1,48 +1,140 @@
 use anyhow::Result;
+use clap::{Parser, ValueEnum};
 use dotenvy::dotenv;
-use sensei_mcp::{JsonRpcRequest, McpServer};
+use sensei_common::Classification;
+use sensei_lib::memory::MemoryStore;
+use sensei_lib::runtime::{RuntimeConfig, SenseiRuntime};
+use sensei_mcp::McpServer;
+use sensei_mcp::http::{self, HttpConfig};
+use sensei_mcp::stdio::serve_stdio;
 use std::env;
-use std::io::{self, BufRead};
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::{info, warn};
+
+/// Set for MCP servers started by this one, so a sensei-mcp listed in `mcp_settings.json`
+/// does not start another one in turn.
+const NESTED_ENV: &str = "SENSEI_MCP_NESTED";
+
+/// Bearer token HTTP clients must send; required to listen beyond loopback.
+const TOKEN_ENV: &str = "SENSEI_MCP_TOKEN";
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Transport {
+    /// One client over stdin/stdout (IDE integrations)
+    Stdio,
+    /// Concurrent clients over Streamable HTTP and legacy SSE
+    Http,
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Sensei MCP server", long_about = None)]
+struct Cli {
+    #[arg(long, value_enum, default_value_t = Transport::Stdio)]
+    transport: Transport,
+
+    /// Address of the HTTP listener
+    #[arg(long, default_value = "127.0.0.1:3001")]
+    listen: String,
+
+    /// Browser origin allowed to connect besides localhost (repeatable)
+    #[arg(long = "allowed-origin")]
+    allowed_origins: Vec<String>,
+
+    /// Only serve the built-in tools and knowledge resources, without the swarm tools
+    #[arg(long)]
+    no_swarm: bool,
+
+    /// Highest classification served to clients: knowledge resources, search_knowledge and ask
+    #[arg(long, default_value = "unclassified")]
+    clearance: Classification,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
+    let cli = Cli::parse();
+
+    // Keep stdout for the protocol before anything can print to it.
+    #[cfg(unix)]
+    let protocol_out = match cli.transport {
+        Transport::Stdio => Some(protocol_stdout()?),
+        Transport::Http => None,
+    };
 
     // Init Logging to STDERR (Crucial for MCP!)
     tracing_subscriber::fmt()
         .with_writer(std::io::stderr)
         .init();
 
-    let db_url = env::var("DATABASE_URL").unwrap_or("sqlite://sensei.db?mode=rwc".to_string());
-    let server = Arc::new(McpServer::new(&db_url).await?);
-
-    info!("🚀 Sensei MCP Server started. Listening on Stdio.");
+    let server = Arc::new(build_server(&cli).await?);
 
-    let stdin = io::stdin();
-    let mut lines = stdin.lock().lines();
+    match cli.transport {
+        Transport::Stdio => {
+            info!("🚀 Sensei MCP Server started. Listening on Stdio.");
+            #[cfg(unix)]
+            if let Some(out) = protocol_out {
+                return serve_stdio(server, tokio::io::stdin(), out).await;
+            }
+            serve_stdio(server, tokio::io::stdin(), tokio::io::stdout()).await
+        }
+        Transport::Http => {
+            let config = HttpConfig {
+                allowed_origins: cli.allowed_origins,
+                token: env::var(TOKEN_ENV).ok().filter(|token| !token.is_empty()),
+                ..HttpConfig::default()
+            };
+            let listener = tokio::net::TcpListener::bind(&cli.listen).await?;
+            config.check_listener(&listener.local_addr()?)?;
+            info!(
+                "🚀 Sensei MCP Server started. Listening on http://{}/mcp (legacy SSE on /sse).",
+                listener.local_addr()?
+            );
+            axum::serve(listener, http::router(server, config)).await?;
+            Ok(())
+        }
+    }
+}
 
-    while let Some(Ok(line)) = lines.next() {
-        if line.trim().is_empty() {
-            continue;
+/// The swarm tools need an LLM; without `GEMINI_API_KEY` only the built-in tools are served.
+async fn build_server(cli: &Cli) -> Result<McpServer> {
+    let mut config = RuntimeConfig::from_env()?;
+    if cli.no_swarm || env::var("GEMINI_API_KEY").is_err() {
+        if !cli.no_swarm {
+            warn!("⚠️ GEMINI_API_KEY is not set: serving the built-in tools without the swarm.");
         }
+        let memory = MemoryStore::new(&config.database_url).await?;
+        memory.migrate().await?;
+        return Ok(McpServer::from_memory(memory)
+            .with_clearance(cli.clearance)
+            .with_prompts(&config.prompts_path));
+    }
 
-        let req: JsonRpcRequest = match serde_json::from_str(&line) {
-            Ok(r) => r,
-            Err(e) => {
-                error!("Failed to parse JSON: {}", e);
-                continue;
-            }
-        };
-
-        let server_clone = server.clone();
-        tokio::spawn(async move {
-            let res = server_clone.handle_request(req).await;
-            let json = serde_json::to_string(&res).unwrap();
-            println!("{}", json); // Send to STDOUT
-        });
+    if env::var_os(NESTED_ENV).is_some() {
+        config.mcp_config = Default::default();
+        warn!("⚠️ Started by another sensei-mcp: its MCP servers are not started again.");
+    }
+    let runtime = SenseiRuntime::builder(config)
+        .with_mcp_env(NESTED_ENV, "1")
+        .build()
+        .await?;
+    if let Err(e) = runtime.reloader.watch() {
+        warn!("⚠️ {}. Restart to apply configuration changes.", e);
     }
+    Ok(McpServer::from_runtime(&runtime, cli.clearance))
+}
 
-    Ok(())
+/// Move the real stdout aside for the protocol and point fd 1 at stderr,
+/// so logs printed by the swarm cannot corrupt the JSON-RPC stream.
+#[cfg(unix)]
+fn protocol_stdout() -> std::io::Result<tokio::fs::File> {
+    use std::os::fd::FromRawFd;
+
+    // SAFETY: Called at startup before other threads write; both descriptors are valid.
+    unsafe {
+        let fd = libc::dup(libc::STDOUT_FILENO);
+        if fd < 0 || libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(tokio::fs::File::from_std(std::fs::File::from_raw_fd(fd)))
+    }
 }
@@ -0,0 +1,55 @@
+use crate::{JsonRpcRequest, JsonRpcResponse, McpServer};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tracing::error;
+
+/// Serve newline-delimited JSON-RPC: requests read from `input` are handled concurrently and
+/// their responses go through a single writer task, so lines on `output` never interleave.
+/// Returns once `input` ends and every request in flight has been answered.
+pub async fn serve_stdio<R, W>(
+    server: Arc<McpServer>,
+    input: R,
+    mut output: W,
+) -> anyhow::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::unbounded_channel::<JsonRpcResponse>();
+    let writer = tokio::spawn(async move {
+        while let Some(response) = rx.recv().await {
+            let mut json = serde_json::to_string(&response)?;
+            json.push('\n');
+            output.write_all(json.as_bytes()).await?;
+            output.flush().await?;
+        }
+        anyhow::Ok(())
+    });
+
+    let mut lines = BufReader::new(input).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let req: JsonRpcRequest = match serde_json::from_str(&line) {
+            Ok(r) => r,
+            Err(e) => {
+                error!("Failed to parse JSON: {}", e);
+                continue;
+            }
+        };
+
+        let (server, tx) = (server.clone(), tx.clone());
+        tokio::spawn(async move {
+            if let Some(response) = server.handle_message(req).await {
+                let _ = tx.send(response);
+            }
+        });
+    }
+
+    // The writer stops once the last handler has sent its response.
+    drop(tx);
+    writer.await?
+}
@@ -0,0 +1,325 @@
+use async_trait::async_trait;
+use sensei_common::{AgentCategory, Classification};
+use sensei_lib::agents::{Orchestrator, router::RouterAgent};
+use sensei_lib::errors::SenseiError;
+use sensei_lib::ingest::{ChunkConfig, IngestRequest, ingest};
+use sensei_lib::llm::Llm;
+use sensei_lib::memory::MemoryStore;
+use sensei_lib::rag::{citation, enrich_with_rag_capped, retrieve};
+use sensei_lib::runtime::SenseiRuntime;
+use sensei_lib::tools::{Tool, ToolRegistry, string_argument};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// Results `search_knowledge` returns by default, and at most.
+const DEFAULT_SEARCH_LIMIT: i64 = 5;
+const MAX_SEARCH_LIMIT: i64 = 20;
+
+/// The swarm behind the `ask`, `search_knowledge`, `add_knowledge` and `classify` tools.
+#[derive(Clone)]
+pub struct SwarmTools {
+    pub orchestrator: Arc<Orchestrator>,
+    pub router: Arc<RouterAgent>,
+    pub memory: MemoryStore,
+    /// Embeds queries and documents.
+    pub llm: Arc<dyn Llm>,
+    pub chunking: ChunkConfig,
+    /// Highest classification the MCP client may read, through any tool.
+    pub clearance: Classification,
+}
+
+impl SwarmTools {
+    pub fn new(runtime: &SenseiRuntime, clearance: Classification) -> Self {
+        Self {
+            orchestrator: runtime.orchestrator.clone(),
+            router: runtime.router.clone(),
+            memory: runtime.memory.clone(),
+            llm: runtime.smart_llm.clone(),
+            chunking: runtime.chunking,
+            clearance,
+        }
+    }
+
+    pub fn register(self, registry: &mut ToolRegistry) {
+        let swarm = Arc::new(self);
+        registry.register(Arc::new(AskTool(swarm.clone())));
+        registry.register(Arc::new(SearchKnowledgeTool(swarm.clone())));
+        registry.register(Arc::new(AddKnowledgeTool(swarm.clone())));
+        registry.register(Arc::new(ClassifyTool(swarm)));
+    }
+}
+
+fn required(arguments: &Value, key: &str) -> Result<String, SenseiError> {
+    let value = string_argument(arguments, key);
+    if value.trim().is_empty() {
+        return Err(SenseiError::Tool(format!("'{}' is required", key)));
+    }
+    Ok(value)
+}
+
+fn to_json(value: &impl serde::Serialize) -> Result<String, SenseiError> {
+    serde_json::to_string_pretty(value)
+        .map_err(|e| SenseiError::Tool(format!("Failed to encode result: {}", e)))
+}
+
+/// Route a question through the swarm, like `POST /v1/ask`.
+struct AskTool(Arc<SwarmTools>);
+
+#[async_trait]
+impl Tool for AskTool {
+    fn name(&self) -> &str {
+        "ask"
+    }
+
+    fn description(&self) -> &str {
+        "Ask the Sensei swarm. The router picks the specialist agent unless a category is given; the answer cites the knowledge base."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "prompt": { "type": "string", "description": "The question or task" },
+                "category": {
+                    "type": "string",
+                    "description": "Agent to answer instead of the routed one, e.g. RED, BLUE, ACTION"
+                }
+            },
+            "required": ["prompt"]
+        })
+    }
+
+    async fn call(&self, arguments: &Value) -> Result<String, SenseiError> {
+        let swarm = &self.0;
+        let prompt = required(arguments, "prompt")?;
+        let forced = arguments.get("category").and_then(|c| c.as_str());
+
+        let (category, query) = match forced {
+            Some(category) => {
+                let category = AgentCategory::new(category);
+                let categories = swarm.orchestrator.categories().await;
+                if !categories.contains(&category) {
+                    let known: Vec<String> = categories.iter().map(|c| c.to_string()).collect();
+                    return Err(SenseiError::Tool(format!(
+                        "Unknown category '{}' (available: {})",
+                        category,
+                        known.join(", ")
+                    )));
+                }
+                (category, prompt)
+            }
+            None => {
+                let decision = swarm.router.classify(&prompt).await;
+                (decision.category, decision.query)
+            }
+        };
+
+        // The agent may be cleared higher than the client: its answer must not leak it.
+        let (prompt, sources) = enrich_with_rag_capped(
+            &swarm.orchestrator,
+            &swarm.memory,
+            swarm.llm.as_ref(),
+            &category,
+            query,
+            swarm.clearance,
+        )
+        .await;
+        let mut answer = swarm.orchestrator.dispatch(category, &prompt).await;
+        if !sources.is_empty() {
+            answer.push_str("\n\nSources:");
+            for source in &sources {
+                answer.push_str(&format!("\n{}", source));
+            }
+        }
+        Ok(answer)
+    }
+
+    async fn execute(&self, args: &str) -> Result<String, SenseiError> {
+        self.call(&json!({ "prompt": args })).await
+    }
+}
+
+/// Ranked knowledge base chunks for a query.
+struct SearchKnowledgeTool(Arc<SwarmTools>);
+
+#[async_trait]
+impl Tool for SearchKnowledgeTool {
+    fn name(&self) -> &str {
+        "search_knowledge"
+    }
+
+    fn description(&self) -> &str {
+        "Search the Sensei knowledge base (hybrid vector and keyword search) and return the best matching chunks."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "limit": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_SEARCH_LIMIT,
+                    "default": DEFAULT_SEARCH_LIMIT
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    async fn call(&self, arguments: &Value) -> Result<String, SenseiError> {
+        let swarm = &self.0;
+        let query = required(arguments, "query")?;
+        let limit = arguments
+            .get("limit")
+            .and_then(|l| l.as_i64())
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT);
+
+        let hits = retrieve(
+            &swarm.memory,
+            swarm.llm.as_ref(),
+            &query,
+            limit,
+            swarm.clearance,
+            "mcp",
+        )
+        .await?;
+        let results: Vec<Value> = hits
+            .iter()
+            .enumerate()
+            .map(|(i, hit)| {
+                let citation = citation(i + 1, hit);
+                json!({
+                    "rank": citation.index,
+                    "uri": citation.uri,
+                    "source": citation.source,
+                    "classification": hit.metadata.classification,
+                    "score": hit.score,
+                    "distance": hit.distance,
+                    "bm25": hit.bm25,
+                    "content": hit.content,
+                })
+            })
+            .collect();
+        to_json(&results)
+    }
+
+    async fn execute(&self, args: &str) -> Result<String, SenseiError> {
+        self.call(&json!({ "query": args })).await
+    }
+}
+
+#[derive(Deserialize)]
+struct AddKnowledgeArgs {
+    content: String,
+    source: Option<String>,
+    #[serde(default)]
+    classification: Classification,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Chunk, embed and store a document, like `POST /v1/knowledge/add`.
+struct AddKnowledgeTool(Arc<SwarmTools>);
+
+#[async_trait]
+impl Tool for AddKnowledgeTool {
+    fn name(&self) -> &str {
+        "add_knowledge"
+    }
+
+    fn description(&self) -> &str {
+        "Add a document to the Sensei knowledge base. Re-adding a source replaces its previous version."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "content": { "type": "string", "description": "Markdown or plain text" },
+                "source": { "type": "string", "description": "Identifies the document across versions, e.g. a file path" },
+                "classification": {
+                    "type": "string",
+                    "enum": Classification::ALL.map(|c| c.as_str()),
+                    "default": "unclassified"
+                },
+                "tags": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["content"]
+        })
+    }
+
+    async fn call(&self, arguments: &Value) -> Result<String, SenseiError> {
+        let swarm = &self.0;
+        let args: AddKnowledgeArgs = serde_json::from_value(arguments.clone())
+            .map_err(|e| SenseiError::Tool(format!("Invalid add_knowledge arguments: {}", e)))?;
+        if args.content.trim().is_empty() {
+            return Err(SenseiError::Tool("'content' is required".to_string()));
+        }
+        // A new version replaces or relabels every chunk of the source.
+        if let Some(source) = &args.source
+            && !swarm
+                .memory
+                .may_rewrite_source(source, swarm.clearance, "mcp")
+                .await?
+        {
+            return Err(SenseiError::Tool(format!(
+                "Source '{}' holds documents above your clearance",
+                source
+            )));
+        }
+
+        let request = IngestRequest {
+            content: args.content,
+            source: args.source,
+            classification: args.classification,
+            tags: args.tags,
+        };
+        let report = ingest(&swarm.memory, swarm.llm.as_ref(), request, &swarm.chunking).await?;
+        to_json(&report)
+    }
+
+    async fn execute(&self, args: &str) -> Result<String, SenseiError> {
+        self.call(&json!({ "content": args })).await
+    }
+}
+
+/// The router's decision for a prompt, without answering it.
+struct ClassifyTool(Arc<SwarmTools>);
+
+#[async_trait]
+impl Tool for ClassifyTool {
+    fn name(&self) -> &str {
+        "classify"
+    }
+
+    fn description(&self) -> &str {
+        "Show which Sensei agent the router would send a prompt to, and the optimized query."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "prompt": { "type": "string" }
+            },
+            "required": ["prompt"]
+        })
+    }
+
+    async fn call(&self, arguments: &Value) -> Result<String, SenseiError> {
+        let prompt = required(arguments, "prompt")?;
+        let decision = self.0.router.classify(&prompt).await;
+        to_json(&json!({
+            "category": decision.category.to_string(),
+            "query": decision.query,
+        }))
+    }
+
+    async fn execute(&self, args: &str) -> Result<String, SenseiError> {
+        self.call(&json!({ "prompt": args })).await
+    }
+}
@@ -0,0 +1,216 @@
+use sensei_lib::memory::MemoryStore;
+use sensei_mcp::stdio::serve_stdio;
+use sensei_mcp::{JsonRpcRequest, JsonRpcResponse, McpServer, SUPPORTED_PROTOCOL_VERSIONS};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+async fn server() -> McpServer {
+    McpServer::new("sqlite::memory:").await.unwrap()
+}
+
+fn request(method: &str, params: Value) -> JsonRpcRequest {
+    JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(1)),
+        method: method.to_string(),
+        params: Some(params),
+    }
+}
+
+fn notification(method: &str) -> JsonRpcRequest {
+    JsonRpcRequest {
+        id: None,
+        ..request(method, json!({}))
+    }
+}
+
+fn result(response: JsonRpcResponse) -> Value {
+    assert!(response.error.is_none(), "{:?}", response.error);
+    response.result.unwrap()
+}
+
+fn error_code(response: JsonRpcResponse) -> i32 {
+    response.error.expect("expected an error").code
+}
+
+#[tokio::test]
+async fn initialize_negotiates_the_protocol_version() {
+    let server = server().await;
+    let initialize = |version: &str| request("initialize", json!({ "protocolVersion": version }));
+
+    let result = result(server.handle_request(initialize("2025-03-26")).await);
+    assert_eq!(result["protocolVersion"], "2025-03-26");
+    assert_eq!(result["serverInfo"]["name"], "sensei-mcp");
+    assert!(result["serverInfo"]["version"].is_string());
+    assert!(result["capabilities"]["tools"].is_object());
+    assert!(result["capabilities"]["resources"].is_object());
+
+    // Unknown versions are answered with the latest one we speak.
+    let result = server.handle_request(initialize("1999-01-01")).await;
+    assert_eq!(
+        result.result.unwrap()["protocolVersion"],
+        SUPPORTED_PROTOCOL_VERSIONS[0]
+    );
+}
+
+#[tokio::test]
+async fn notifications_are_never_answered() {
+    let server = server().await;
+    for method in [
+        "notifications/initialized",
+        "notifications/cancelled",
+        "notifications/unknown",
+    ] {
+        assert!(server.handle_message(notification(method)).await.is_none());
+    }
+
+    // Over stdio, only the ping produces a line.
+    let (client, server_io) = tokio::io::duplex(1024);
+    let (input, output) = tokio::io::split(server_io);
+    let serving = tokio::spawn(serve_stdio(Arc::new(server), input, output));
+    let (mut reader, mut writer) = tokio::io::split(client);
+    let lines = [
+        json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+        json!({ "jsonrpc": "2.0", "id": 7, "method": "ping" }),
+    ];
+    for line in lines {
+        writer
+            .write_all(format!("{}\n", line).as_bytes())
+            .await
+            .unwrap();
+    }
+    writer.shutdown().await.unwrap();
+    serving.await.unwrap().unwrap();
+
+    let mut output = String::new();
+    reader.read_to_string(&mut output).await.unwrap();
+    let responses: Vec<Value> = output
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(
+        responses,
+        [json!({ "jsonrpc": "2.0", "id": 7, "result": {} })]
+    );
+}
+
+#[tokio::test]
+async fn ping_returns_an_empty_result() {
+    let server = server().await;
+    assert_eq!(
+        result(server.handle_request(request("ping", json!({}))).await),
+        json!({})
+    );
+}
+
+#[tokio::test]
+async fn requests_must_be_json_rpc_2() {
+    let server = server().await;
+    let req = JsonRpcRequest {
+        jsonrpc: "1.0".to_string(),
+        ..request("ping", json!({}))
+    };
+    assert_eq!(error_code(server.handle_request(req).await), -32600);
+}
+
+#[tokio::test]
+async fn tools_are_described_in_camel_case() {
+    let server = server().await;
+    let result = result(
+        server
+            .handle_request(request("tools/list", json!({})))
+            .await,
+    );
+    for tool in result["tools"].as_array().unwrap() {
+        assert_eq!(tool["inputSchema"]["type"], "object", "{}", tool);
+        assert!(tool.get("input_schema").is_none());
+    }
+}
+
+#[tokio::test]
+async fn list_methods_paginate_with_cursors() {
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+    for i in 0..3 {
+        memory
+            .add_document(&format!("Document {}", i), vec![0.1; 3072])
+            .await
+            .unwrap();
+    }
+    let server = McpServer::from_memory(memory).with_page_size(2);
+
+    for (method, key, total) in [
+        ("tools/list", "tools", 2),
+        ("resources/list", "resources", 3),
+    ] {
+        let mut seen = 0;
+        let mut params = json!({});
+        loop {
+            let page = result(server.handle_request(request(method, params)).await);
+            let items = page[key].as_array().unwrap().len();
+            assert!((1..=2).contains(&items), "{}", page);
+            seen += items;
+            match page.get("nextCursor") {
+                Some(cursor) => params = json!({ "cursor": cursor }),
+                None => break,
+            }
+        }
+        assert_eq!(seen, total, "{}", method);
+    }
+
+    let invalid = request("resources/list", json!({ "cursor": "not-a-cursor" }));
+    assert_eq!(error_code(server.handle_request(invalid).await), -32602);
+}
+
+#[tokio::test]
+async fn tool_failures_are_results_marked_as_errors() {
+    let server = server().await;
+
+    let call = request(
+        "tools/call",
+        json!({ "name": "nmap", "arguments": { "target": 42 } }),
+    );
+    let result = result(server.handle_request(call).await);
+    assert_eq!(result["isError"], true);
+    assert!(
+        result["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("Invalid nmap arguments")
+    );
+
+    // An unknown tool is a protocol error.
+    let unknown = request("tools/call", json!({ "name": "nope" }));
+    assert_eq!(error_code(server.handle_request(unknown).await), -32602);
+}
+
+#[tokio::test]
+async fn resources_use_mime_type_and_missing_ones_are_errors() {
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+    memory
+        .add_document("Rotate the keys every month.", vec![0.1; 3072])
+        .await
+        .unwrap();
+    let server = McpServer::from_memory(memory);
+
+    let list = result(
+        server
+            .handle_request(request("resources/list", json!({})))
+            .await,
+    );
+    let resource = &list["resources"][0];
+    assert_eq!(resource["mimeType"], "text/plain");
+    assert!(resource.get("mime_type").is_none());
+
+    let read = request("resources/read", json!({ "uri": resource["uri"] }));
+    let contents = result(server.handle_request(read).await);
+    assert_eq!(
+        contents["contents"][0]["text"],
+        "Rotate the keys every month."
+    );
+
+    let missing = request("resources/read", json!({ "uri": "sensei://knowledge/999" }));
+    assert_eq!(error_code(server.handle_request(missing).await), -32002);
+}
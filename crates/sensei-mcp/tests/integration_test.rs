@@ -19,7 +19,7 @@ async fn mcp_server_initializes_and_lists_tools() {
     assert!(init_res.result.is_some());
 
     let result = init_res.result.unwrap();
-    assert_eq!(result["server"]["name"], "sensei-mcp");
+    assert_eq!(result["serverInfo"]["name"], "sensei-mcp");
 
     // 3. Test Tools List
     let tools_req = JsonRpcRequest {
@@ -0,0 +1,136 @@
+use sensei_mcp::{JsonRpcRequest, JsonRpcResponse, McpServer};
+use serde_json::{Value, json};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+fn request(method: &str, params: Value) -> JsonRpcRequest {
+    JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(1)),
+        method: method.to_string(),
+        params: Some(params),
+    }
+}
+
+fn result(response: JsonRpcResponse) -> Value {
+    assert!(response.error.is_none(), "{:?}", response.error);
+    response.result.unwrap()
+}
+
+fn prompts_file(yaml: &str) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", yaml).unwrap();
+    file
+}
+
+#[tokio::test]
+async fn agents_are_listed_as_prompts() {
+    let file = prompts_file(
+        r#"
+agents:
+  forensics:
+    prompt: "You are a Forensics Examiner."
+    description: "Incident timelines."
+"#,
+    );
+    let server = McpServer::new("sqlite::memory:")
+        .await
+        .unwrap()
+        .with_prompts(file.path());
+
+    let initialized = result(
+        server
+            .handle_request(request("initialize", json!({})))
+            .await,
+    );
+    assert!(initialized["capabilities"]["prompts"].is_object());
+
+    let listed = result(
+        server
+            .handle_request(request("prompts/list", json!({})))
+            .await,
+    );
+    let prompts = listed["prompts"].as_array().unwrap();
+    let red = prompts.iter().find(|p| p["name"] == "red").unwrap();
+    assert_eq!(red["arguments"][0]["name"], "target");
+    assert_eq!(red["arguments"][0]["required"], true);
+    assert_eq!(red["arguments"][1]["name"], "scope");
+    assert_eq!(red["arguments"][1]["required"], false);
+
+    let forensics = prompts.iter().find(|p| p["name"] == "forensics").unwrap();
+    assert_eq!(forensics["description"], "Incident timelines.");
+    assert_eq!(
+        forensics["arguments"],
+        json!([{
+            "name": "task", "description": "What to ask the agent", "required": true
+        }])
+    );
+}
+
+#[tokio::test]
+async fn prompts_are_rendered_from_their_arguments() {
+    let server = McpServer::new("sqlite::memory:").await.unwrap();
+
+    let red = result(
+        server
+            .handle_request(request(
+                "prompts/get",
+                json!({ "name": "red", "arguments": { "target": "10.0.0.0/24", "scope": "No DoS" } }),
+            ))
+            .await,
+    );
+    let message = &red["messages"][0];
+    assert_eq!(message["role"], "user");
+    assert_eq!(
+        message["content"]["text"],
+        "SYSTEM: You are a Red Team Operator.\n\nPlan an offensive assessment of 10.0.0.0/24.\nScope: No DoS"
+    );
+    assert!(red["description"].is_string());
+
+    for invalid in [
+        json!({ "name": "red", "arguments": { "scope": "No DoS" } }),
+        json!({ "name": "red", "arguments": { "target": 42 } }),
+        json!({ "name": "purple" }),
+    ] {
+        let response = server.handle_request(request("prompts/get", invalid)).await;
+        assert_eq!(response.error.expect("expected an error").code, -32602);
+    }
+}
+
+#[tokio::test]
+async fn prompt_file_changes_apply_on_the_next_request() {
+    let file = prompts_file("agents: {}\n");
+    let server = McpServer::new("sqlite::memory:")
+        .await
+        .unwrap()
+        .with_prompts(file.path());
+    let names = |listed: Value| -> Vec<String> {
+        listed["prompts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let before = result(
+        server
+            .handle_request(request("prompts/list", json!({})))
+            .await,
+    );
+    assert!(!names(before).contains(&"forensics".to_string()));
+
+    std::fs::write(file.path(), "agents:\n  forensics:\n    prompt: Examine.\n").unwrap();
+    let after = result(
+        server
+            .handle_request(request("prompts/list", json!({})))
+            .await,
+    );
+    assert!(names(after).contains(&"forensics".to_string()));
+
+    std::fs::write(file.path(), "agents: [").unwrap();
+    let broken = server
+        .handle_request(request("prompts/list", json!({})))
+        .await;
+    assert_eq!(broken.error.expect("expected an error").code, -32603);
+}
@@ -0,0 +1,323 @@
+use async_trait::async_trait;
+use sensei_common::{AgentCategory, Classification, knowledge_uri};
+use sensei_lib::agents::{Agent, Orchestrator, router::RouterAgent};
+use sensei_lib::errors::SenseiError;
+use sensei_lib::ingest::ChunkConfig;
+use sensei_lib::llm::Llm;
+use sensei_lib::memory::MemoryStore;
+use sensei_mcp::swarm::SwarmTools;
+use sensei_mcp::{JsonRpcRequest, McpServer};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// Routes everything to BLUE with an optimized query.
+struct RouterLlm;
+
+#[async_trait]
+impl Llm for RouterLlm {
+    async fn generate(&self, _prompt: &str) -> Result<String, SenseiError> {
+        Ok(r#"{"category": "blue", "enhanced_query": "harden ssh"}"#.to_string())
+    }
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>, SenseiError> {
+        Ok(vec![0.1; 3072])
+    }
+}
+
+/// Answers with its category and the prompt it was given.
+struct EchoAgent(&'static str, Classification);
+
+#[async_trait]
+impl Agent for EchoAgent {
+    async fn process(&self, input: &str) -> String {
+        format!("{} got: {}", self.0, input)
+    }
+    fn category(&self) -> AgentCategory {
+        AgentCategory::new(self.0)
+    }
+    fn clearance(&self) -> Classification {
+        self.1
+    }
+}
+
+async fn memory() -> MemoryStore {
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+    memory
+}
+
+async fn server() -> McpServer {
+    server_with(memory().await).await
+}
+
+/// The swarm tools for a client cleared up to confidential, with RED cleared higher.
+async fn server_with(memory: MemoryStore) -> McpServer {
+    let orchestrator = Arc::new(Orchestrator::new());
+    orchestrator
+        .register(Box::new(EchoAgent("blue", Classification::Unclassified)))
+        .await;
+    orchestrator
+        .register(Box::new(EchoAgent("red", Classification::TopSecret)))
+        .await;
+    let llm: Arc<dyn Llm> = Arc::new(RouterLlm);
+
+    let swarm = SwarmTools {
+        orchestrator,
+        router: Arc::new(RouterAgent::new(llm.clone(), None, "")),
+        memory: memory.clone(),
+        llm,
+        chunking: ChunkConfig::default(),
+        clearance: Classification::Confidential,
+    };
+    McpServer::from_memory(memory).with_swarm(swarm)
+}
+
+async fn request(server: &McpServer, method: &str, params: Value) -> sensei_mcp::JsonRpcResponse {
+    server
+        .handle_request(JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: method.to_string(),
+            params: Some(params),
+        })
+        .await
+}
+
+/// Call a tool and return its text and whether it failed.
+async fn call(server: &McpServer, name: &str, arguments: Value) -> (String, bool) {
+    let response = request(
+        server,
+        "tools/call",
+        json!({ "name": name, "arguments": arguments }),
+    )
+    .await;
+    let result = response.result.expect("tool calls return results");
+    let text = result["content"][0]["text"].as_str().unwrap().to_string();
+    (text, result["isError"] == true)
+}
+
+#[tokio::test]
+async fn swarm_tools_are_listed_next_to_the_builtin_ones() {
+    let server = server().await;
+    let response = server
+        .handle_request(JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "tools/list".to_string(),
+            params: None,
+        })
+        .await;
+    let result = response.result.unwrap();
+    let names: Vec<&str> = result["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap())
+        .collect();
+    for name in [
+        "ask",
+        "search_knowledge",
+        "add_knowledge",
+        "classify",
+        "nmap",
+    ] {
+        assert!(names.contains(&name), "{} missing from {:?}", name, names);
+    }
+}
+
+#[tokio::test]
+async fn classify_reports_the_router_decision() {
+    let server = server().await;
+    let (text, failed) = call(&server, "classify", json!({ "prompt": "secure my ssh" })).await;
+    assert!(!failed);
+    let decision: Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(
+        decision,
+        json!({ "category": "BLUE", "query": "harden ssh" })
+    );
+}
+
+#[tokio::test]
+async fn ask_routes_or_uses_the_forced_category() {
+    let server = server().await;
+
+    let (routed, _) = call(&server, "ask", json!({ "prompt": "secure my ssh" })).await;
+    assert_eq!(routed, "blue got: harden ssh");
+
+    let (forced, _) = call(
+        &server,
+        "ask",
+        json!({ "prompt": "attack my ssh", "category": "RED" }),
+    )
+    .await;
+    assert_eq!(forced, "red got: attack my ssh");
+
+    let (unknown, failed) = call(
+        &server,
+        "ask",
+        json!({ "prompt": "hi", "category": "purple" }),
+    )
+    .await;
+    assert!(failed);
+    assert!(unknown.contains("available: BLUE, RED"), "{}", unknown);
+}
+
+#[tokio::test]
+async fn ask_cites_the_knowledge_it_used() {
+    let server = server().await;
+    call(
+        &server,
+        "add_knowledge",
+        json!({ "content": "Disable password authentication for ssh.", "source": "ssh.md" }),
+    )
+    .await;
+
+    let (answer, _) = call(&server, "ask", json!({ "prompt": "secure my ssh" })).await;
+    assert!(answer.contains("RELEVANT KNOWLEDGE"), "{}", answer);
+    assert!(answer.contains("Sources:\n[1]"), "{}", answer);
+}
+
+#[tokio::test]
+async fn added_knowledge_is_searchable_within_clearance() {
+    let server = server().await;
+    let documents = [
+        (
+            "Rotate the ssh host keys yearly.",
+            "keys.md",
+            "confidential",
+        ),
+        ("The ssh root password is hunter2.", "secrets.md", "secret"),
+    ];
+    for (content, source, classification) in documents {
+        let (report, failed) = call(
+            &server,
+            "add_knowledge",
+            json!({ "content": content, "source": source, "classification": classification, "tags": ["ssh"] }),
+        )
+        .await;
+        assert!(!failed, "{}", report);
+        let report: Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(report["chunks"], 1);
+    }
+
+    let (results, failed) = call(
+        &server,
+        "search_knowledge",
+        json!({ "query": "ssh keys", "limit": 10 }),
+    )
+    .await;
+    assert!(!failed, "{}", results);
+    let results: Vec<Value> = serde_json::from_str(&results).unwrap();
+    // The secret document is above the configured clearance.
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["rank"], 1);
+    assert_eq!(results[0]["source"], "keys.md");
+    assert_eq!(results[0]["classification"], "confidential");
+    assert!(
+        results[0]["uri"]
+            .as_str()
+            .unwrap()
+            .starts_with("sensei://knowledge/")
+    );
+
+    let (error, failed) = call(&server, "add_knowledge", json!({ "content": " " })).await;
+    assert!(failed);
+    assert!(error.contains("'content' is required"));
+}
+
+#[tokio::test]
+async fn ask_only_uses_knowledge_the_client_is_cleared_for() {
+    let server = server().await;
+    for (content, classification) in [
+        ("Rotate the ssh host keys yearly.", "confidential"),
+        ("The ssh root password is hunter2.", "secret"),
+    ] {
+        call(
+            &server,
+            "add_knowledge",
+            json!({ "content": content, "classification": classification }),
+        )
+        .await;
+    }
+
+    // RED is cleared for secrets, the client only for confidential knowledge.
+    let (answer, _) = call(
+        &server,
+        "ask",
+        json!({ "prompt": "ssh root password", "category": "RED" }),
+    )
+    .await;
+    assert!(answer.contains("host keys"), "{}", answer);
+    assert!(!answer.contains("hunter2"), "{}", answer);
+}
+
+#[tokio::test]
+async fn sources_above_the_clearance_are_not_rewritten() {
+    let server = server().await;
+    let secret = json!({
+        "content": "The ssh root password is hunter2.",
+        "source": "vault.md",
+        "classification": "secret"
+    });
+    let (_, failed) = call(&server, "add_knowledge", secret).await;
+    assert!(!failed);
+
+    // Relabelling or replacing it would declassify knowledge the client cannot read.
+    for content in ["The ssh root password is hunter2.", "Nothing to see here."] {
+        let (error, failed) = call(
+            &server,
+            "add_knowledge",
+            json!({ "content": content, "source": "vault.md", "classification": "unclassified" }),
+        )
+        .await;
+        assert!(failed);
+        assert!(error.contains("above your clearance"), "{}", error);
+    }
+}
+
+#[tokio::test]
+async fn resources_are_served_within_clearance() {
+    let memory = memory().await;
+    let embedding = vec![0.1; 3072];
+    let confidential = memory
+        .add_classified_document(
+            "Rotate the ssh host keys yearly.",
+            embedding.clone(),
+            Classification::Confidential,
+        )
+        .await
+        .unwrap();
+    let secret = memory
+        .add_classified_document(
+            "The ssh root password is hunter2.",
+            embedding,
+            Classification::Secret,
+        )
+        .await
+        .unwrap();
+    let server = server_with(memory).await;
+
+    let listed = request(&server, "resources/list", json!({})).await;
+    let resources = listed.result.unwrap()["resources"].clone();
+    let uris: Vec<&str> = resources
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["uri"].as_str().unwrap())
+        .collect();
+    assert_eq!(uris, [knowledge_uri(confidential)]);
+
+    let read = |id: i64| {
+        request(
+            &server,
+            "resources/read",
+            json!({ "uri": knowledge_uri(id) }),
+        )
+    };
+    let allowed = read(confidential).await.result.unwrap();
+    assert_eq!(
+        allowed["contents"][0]["text"],
+        "Rotate the ssh host keys yearly."
+    );
+    // Above the clearance: as if it did not exist.
+    assert_eq!(read(secret).await.error.unwrap().code, -32002);
+}
@@ -0,0 +1,239 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use sensei_lib::config::RemoteTransport;
+use sensei_lib::mcp_client::McpClient;
+use sensei_mcp::McpServer;
+use sensei_mcp::http::{HttpConfig, SESSION_HEADER, router};
+use sensei_mcp::stdio::serve_stdio;
+use serde_json::{Value, json};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tower::ServiceExt;
+
+async fn server() -> Arc<McpServer> {
+    Arc::new(McpServer::new("sqlite::memory:").await.unwrap())
+}
+
+async fn serve(config: HttpConfig) -> String {
+    let app = router(server().await, config);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+fn post() -> axum::http::request::Builder {
+    Request::post("/mcp").header("content-type", "application/json")
+}
+
+fn message(method: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": {} })
+}
+
+#[tokio::test]
+async fn concurrent_http_clients_get_their_own_sessions() {
+    let base = serve(HttpConfig::default()).await;
+    let connect = |path: &str, transport| {
+        let url = format!("{}{}", base, path);
+        async move {
+            McpClient::connect(&url, HashMap::new(), transport)
+                .await
+                .unwrap()
+        }
+    };
+    let clients = [
+        connect("/mcp", RemoteTransport::StreamableHttp).await,
+        connect("/mcp", RemoteTransport::StreamableHttp).await,
+        connect("/sse", RemoteTransport::Sse).await,
+    ];
+    let lists = futures::future::join_all(clients.iter().map(|c| async move {
+        c.initialize().await.unwrap();
+        c.list_tools().await.unwrap()
+    }))
+    .await;
+    for tools in lists {
+        assert!(tools.iter().any(|t| t["name"] == "nmap"));
+    }
+}
+
+#[tokio::test]
+async fn requests_need_a_live_session() {
+    let app = router(server().await, HttpConfig::default());
+    let send = |request: Request<Body>| app.clone().oneshot(request);
+    let body = |value: Value| Body::from(value.to_string());
+
+    let missing = send(post().body(body(message("tools/list"))).unwrap());
+    assert_eq!(missing.await.unwrap().status(), StatusCode::BAD_REQUEST);
+    let unknown = post()
+        .header(SESSION_HEADER, "nope")
+        .body(body(message("tools/list")))
+        .unwrap();
+    assert_eq!(send(unknown).await.unwrap().status(), StatusCode::NOT_FOUND);
+
+    let init = send(post().body(body(message("initialize"))).unwrap());
+    let init = init.await.unwrap();
+    assert_eq!(init.status(), StatusCode::OK);
+    let session = init.headers()[SESSION_HEADER].to_str().unwrap().to_string();
+
+    let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+    let accepted = post()
+        .header(SESSION_HEADER, &session)
+        .body(body(notification))
+        .unwrap();
+    assert_eq!(send(accepted).await.unwrap().status(), StatusCode::ACCEPTED);
+
+    let end = Request::delete("/mcp")
+        .header(SESSION_HEADER, &session)
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(send(end).await.unwrap().status(), StatusCode::OK);
+    let expired = post()
+        .header(SESSION_HEADER, &session)
+        .body(body(message("tools/list")))
+        .unwrap();
+    assert_eq!(send(expired).await.unwrap().status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn idle_sessions_expire() {
+    let config = HttpConfig {
+        session_ttl: Duration::from_millis(100),
+        ..HttpConfig::default()
+    };
+    let app = router(server().await, config);
+    let send = |request: Request<Body>| app.clone().oneshot(request);
+    let body = |value: Value| Body::from(value.to_string());
+
+    let init = send(post().body(body(message("initialize"))).unwrap());
+    let init = init.await.unwrap();
+    let session = init.headers()[SESSION_HEADER].to_str().unwrap().to_string();
+    let list = || {
+        post()
+            .header(SESSION_HEADER, &session)
+            .body(body(message("tools/list")))
+            .unwrap()
+    };
+    assert_eq!(send(list()).await.unwrap().status(), StatusCode::OK);
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(send(list()).await.unwrap().status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn a_configured_token_is_required() {
+    let config = HttpConfig {
+        token: Some("s3cret".to_string()),
+        ..HttpConfig::default()
+    };
+    let app = router(server().await, config);
+    let status = |authorization: Option<&'static str>| {
+        let app = app.clone();
+        async move {
+            let mut request = post();
+            if let Some(authorization) = authorization {
+                request = request.header("authorization", authorization);
+            }
+            let request = request
+                .body(Body::from(message("initialize").to_string()))
+                .unwrap();
+            app.oneshot(request).await.unwrap().status()
+        }
+    };
+
+    assert_eq!(status(None).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(status(Some("Bearer wrong")).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(status(Some("Bearer s3cret")).await, StatusCode::OK);
+}
+
+#[test]
+fn only_loopback_is_served_without_a_token() {
+    let open = HttpConfig::default();
+    assert!(
+        open.check_listener(&"127.0.0.1:3001".parse().unwrap())
+            .is_ok()
+    );
+    assert!(open.check_listener(&"[::1]:3001".parse().unwrap()).is_ok());
+    assert!(
+        open.check_listener(&"0.0.0.0:3001".parse().unwrap())
+            .is_err()
+    );
+    assert!(
+        open.check_listener(&"10.0.0.5:3001".parse().unwrap())
+            .is_err()
+    );
+
+    let protected = HttpConfig {
+        token: Some("s3cret".to_string()),
+        ..HttpConfig::default()
+    };
+    assert!(
+        protected
+            .check_listener(&"0.0.0.0:3001".parse().unwrap())
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn foreign_origins_are_rejected() {
+    let config = HttpConfig {
+        allowed_origins: vec!["https://ide.example.com".to_string()],
+        ..HttpConfig::default()
+    };
+    let app = router(server().await, config);
+    let status = |origin: &'static str| {
+        let app = app.clone();
+        async move {
+            let request = post()
+                .header("origin", origin)
+                .body(Body::from(message("initialize").to_string()))
+                .unwrap();
+            app.oneshot(request).await.unwrap().status()
+        }
+    };
+
+    assert_eq!(status("https://evil.example").await, StatusCode::FORBIDDEN);
+    assert_eq!(
+        status("http://localhost.evil.example").await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(status("http://localhost:5173").await, StatusCode::OK);
+    assert_eq!(status("http://[::1]:8080").await, StatusCode::OK);
+    assert_eq!(status("https://ide.example.com").await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn stdio_responses_are_written_whole_lines() {
+    let (client, server_io) = tokio::io::duplex(256);
+    let (input, output) = tokio::io::split(server_io);
+    let serving = tokio::spawn(serve_stdio(server().await, input, output));
+
+    let (reader, mut writer) = tokio::io::split(client);
+    let requests: String = (0..50)
+        .map(|id| {
+            let method = if id % 2 == 0 {
+                "tools/list"
+            } else {
+                "initialize"
+            };
+            format!(
+                "{}\n",
+                json!({ "jsonrpc": "2.0", "id": id, "method": method })
+            )
+        })
+        .collect();
+    writer.write_all(requests.as_bytes()).await.unwrap();
+
+    let mut lines = BufReader::new(reader).lines();
+    let mut ids = HashSet::new();
+    while ids.len() < 50 {
+        let line = lines.next_line().await.unwrap().unwrap();
+        let response: Value = serde_json::from_str(&line).unwrap();
+        assert!(response["result"].is_object(), "{}", line);
+        ids.insert(response["id"].as_i64().unwrap());
+    }
+    // End of input: the server returns once everything is answered.
+    writer.shutdown().await.unwrap();
+    serving.await.unwrap().unwrap();
+}
@@ -0,0 +1,157 @@
+use crate::AppState;
+use axum::{
+    Json, Router,
+    extract::{Path, Request, State},
+    http::{HeaderMap, StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+};
+use sensei_common::Classification;
+use sensei_lib::auth::{Caller, Scope};
+use sensei_lib::errors::SenseiError;
+use serde::Deserialize;
+use serde_json::json;
+
+/// Require an API key carrying `scope` on every route of `router`.
+/// The caller identity and the verified key are added to the request extensions for handlers.
+pub(crate) fn protect(
+    router: Router<AppState>,
+    state: &AppState,
+    scope: Scope,
+) -> Router<AppState> {
+    router.route_layer(middleware::from_fn_with_state(
+        state.clone(),
+        move |state: State<AppState>, request: Request, next: Next| {
+            authorize(state, scope, request, next)
+        },
+    ))
+}
+
+/// Key management over HTTP, for keys with the `admin` scope.
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/v1/admin/keys",
+            get(list_keys_handler).post(create_key_handler),
+        )
+        .route("/v1/admin/keys/{id}", delete(revoke_key_handler))
+}
+
+/// Read the key from `Authorization: Bearer <key>` or `x-api-key`.
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    if let Some(value) = headers.get(header::AUTHORIZATION)
+        && let Some(token) = value.to_str().ok()?.strip_prefix("Bearer ")
+    {
+        return Some(token.trim());
+    }
+    headers.get("x-api-key")?.to_str().ok().map(str::trim)
+}
+
+fn reject(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+async fn authorize(
+    State(state): State<AppState>,
+    scope: Scope,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if !state.auth_required {
+        // Local peers of the Unix socket are identified by their uid instead.
+        #[cfg(unix)]
+        if let Some(axum::extract::ConnectInfo(peer)) = request
+            .extensions()
+            .get::<axum::extract::ConnectInfo<crate::peer::PeerIdentity>>()
+            .cloned()
+        {
+            request.extensions_mut().insert(peer.caller());
+        }
+        return next.run(request).await;
+    }
+
+    let Some(key) = presented_key(request.headers()) else {
+        return reject(
+            StatusCode::UNAUTHORIZED,
+            "Missing API key (use 'Authorization: Bearer <key>' or 'x-api-key')".to_string(),
+        );
+    };
+
+    match state.memory.verify_api_key(key).await {
+        Ok(Some(api_key)) if api_key.allows(scope) => {
+            request.extensions_mut().insert(Caller::api_key(&api_key));
+            request.extensions_mut().insert(api_key);
+            next.run(request).await
+        }
+        Ok(Some(api_key)) => reject(
+            StatusCode::FORBIDDEN,
+            format!(
+                "API key '{}' does not have the '{}' scope",
+                api_key.name, scope
+            ),
+        ),
+        Ok(None) => reject(
+            StatusCode::UNAUTHORIZED,
+            "Invalid or revoked API key".to_string(),
+        ),
+        Err(e) => {
+            eprintln!("Auth Error: {}", e);
+            reject(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to verify API key".to_string(),
+            )
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CreateKeyRequest {
+    name: String,
+    scopes: Vec<Scope>,
+    /// Highest classification of documents the key may read.
+    #[serde(default)]
+    clearance: Classification,
+}
+
+async fn list_keys_handler(State(state): State<AppState>) -> Response {
+    match state.memory.list_api_keys().await {
+        Ok(keys) => Json(json!({ "keys": keys })).into_response(),
+        Err(e) => reject(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+async fn create_key_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateKeyRequest>,
+) -> Response {
+    if payload.name.trim().is_empty() || payload.scopes.is_empty() {
+        return reject(
+            StatusCode::BAD_REQUEST,
+            "A key needs a name and at least one scope".to_string(),
+        );
+    }
+
+    match state
+        .memory
+        .create_cleared_api_key(payload.name.trim(), &payload.scopes, payload.clearance)
+        .await
+    {
+        // The plaintext key is only ever returned here.
+        Ok((api_key, key)) => (
+            StatusCode::CREATED,
+            Json(json!({ "key": key, "api_key": api_key })),
+        )
+            .into_response(),
+        Err(SenseiError::InvalidInput(message)) => reject(StatusCode::CONFLICT, message),
+        Err(e) => reject(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+async fn revoke_key_handler(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    match state.memory.revoke_api_key(&id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => reject(StatusCode::NOT_FOUND, format!("No active API key '{}'", id)),
+        Err(e) => reject(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
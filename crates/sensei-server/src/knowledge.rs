@@ -0,0 +1,231 @@
+use crate::AppState;
+use crate::sessions::Pagination;
+use axum::{
+    Json, Router,
+    extract::{FromRequestParts, Path, Query, State},
+    http::{StatusCode, request::Parts},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use sensei_common::Classification;
+use sensei_lib::auth::{ApiKey, Caller};
+use sensei_lib::errors::SenseiError;
+use sensei_lib::ingest::{DocumentUpdate, update_document};
+use sensei_lib::memory::DocumentFilter;
+use serde::Deserialize;
+use serde_json::json;
+use std::convert::Infallible;
+
+/// Knowledge base management. Ingestion itself is `POST /v1/knowledge/add`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/v1/knowledge",
+            get(list_documents_handler).delete(delete_source_handler),
+        )
+        .route(
+            "/v1/knowledge/{id}",
+            get(get_document_handler)
+                .patch(update_document_handler)
+                .delete(delete_document_handler),
+        )
+}
+
+/// Who reads the knowledge base: the clearance of the API key, or no restriction
+/// when the server runs without keys. Withheld documents are audited under `subject`.
+pub(crate) struct Reader {
+    pub(crate) clearance: Classification,
+    pub(crate) subject: String,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Reader {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let reader = match parts.extensions.get::<ApiKey>() {
+            Some(key) => Self {
+                clearance: key.effective_clearance(),
+                subject: Caller::api_key(key).0,
+            },
+            None => Self {
+                clearance: Classification::TopSecret,
+                subject: parts
+                    .extensions
+                    .get::<Caller>()
+                    .map_or_else(|| "anonymous".to_string(), |caller| caller.0.clone()),
+            },
+        };
+        Ok(reader)
+    }
+}
+
+#[derive(Deserialize)]
+struct SourceQuery {
+    source: Option<String>,
+}
+
+fn not_found(id: i64) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": format!("Document {} not found", id) })),
+    )
+        .into_response()
+}
+
+fn storage_error(e: impl std::fmt::Display) -> Response {
+    eprintln!("Storage Error: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": "Failed to access the knowledge base" })),
+    )
+        .into_response()
+}
+
+/// Resolve a document `reader` is cleared for, or produce the matching error response.
+/// Documents above the clearance are reported as missing.
+async fn readable_document(state: &AppState, reader: &Reader, id: i64) -> Result<(), Response> {
+    match state
+        .memory
+        .read_document(id, reader.clearance, &reader.subject)
+        .await
+    {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(not_found(id)),
+        Err(e) => Err(storage_error(e)),
+    }
+}
+
+async fn list_documents_handler(
+    State(state): State<AppState>,
+    reader: Reader,
+    Query(filter): Query<DocumentFilter>,
+    Query(page): Query<Pagination>,
+) -> Response {
+    match state
+        .memory
+        .list_documents_page(
+            &filter,
+            reader.clearance,
+            &reader.subject,
+            page.limit(),
+            page.offset(),
+        )
+        .await
+    {
+        Ok((documents, total)) => Json(json!({
+            "documents": documents,
+            "total": total,
+            "limit": page.limit(),
+            "offset": page.offset(),
+        }))
+        .into_response(),
+        Err(e) => storage_error(e),
+    }
+}
+
+async fn get_document_handler(
+    State(state): State<AppState>,
+    reader: Reader,
+    Path(id): Path<i64>,
+) -> Response {
+    match state
+        .memory
+        .read_document(id, reader.clearance, &reader.subject)
+        .await
+    {
+        Ok(Some(document)) => Json(document).into_response(),
+        Ok(None) => not_found(id),
+        Err(e) => storage_error(e),
+    }
+}
+
+async fn update_document_handler(
+    State(state): State<AppState>,
+    reader: Reader,
+    Path(id): Path<i64>,
+    Json(update): Json<DocumentUpdate>,
+) -> Response {
+    if update
+        .content
+        .as_deref()
+        .is_some_and(|c| c.trim().is_empty())
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Content must not be empty" })),
+        )
+            .into_response();
+    }
+    // The updated document is returned, so only readers cleared for it may change it.
+    if let Err(response) = readable_document(&state, &reader, id).await {
+        return response;
+    }
+
+    match update_document(&state.memory, state.llm.as_ref(), id, update).await {
+        Ok(Some(document)) => Json(document).into_response(),
+        Ok(None) => not_found(id),
+        Err(e @ SenseiError::Llm(_)) => {
+            eprintln!("Embedding Error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to generate embedding" })),
+            )
+                .into_response()
+        }
+        Err(e) => storage_error(e),
+    }
+}
+
+async fn delete_document_handler(
+    State(state): State<AppState>,
+    reader: Reader,
+    Path(id): Path<i64>,
+) -> Response {
+    if let Err(response) = readable_document(&state, &reader, id).await {
+        return response;
+    }
+
+    match state.memory.delete_document(id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => not_found(id),
+        Err(e) => storage_error(e),
+    }
+}
+
+/// `DELETE /v1/knowledge?source=...` removes every chunk of a source,
+/// provided `reader` is cleared for all of them.
+async fn delete_source_handler(
+    State(state): State<AppState>,
+    reader: Reader,
+    Query(query): Query<SourceQuery>,
+) -> Response {
+    let Some(source) = query.source.filter(|s| !s.is_empty()) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Missing 'source' query parameter" })),
+        )
+            .into_response();
+    };
+    match state
+        .memory
+        .may_rewrite_source(&source, reader.clearance, &reader.subject)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::FORBIDDEN,
+                Json(json!({
+                    "error": format!("Source '{}' holds documents above your clearance", source)
+                })),
+            )
+                .into_response();
+        }
+        Err(e) => return storage_error(e),
+    }
+
+    match state.memory.delete_source(&source).await {
+        Ok(deleted) => Json(json!({ "source": source, "deleted": deleted })).into_response(),
+        Err(e) => storage_error(e),
+    }
+}
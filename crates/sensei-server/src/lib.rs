@@ -1,18 +1,41 @@
 use axum::{
-    Json, Router,
+    Extension, Json, Router,
     extract::State,
     http::{HeaderMap, StatusCode},
-    response::IntoResponse,
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
     routing::{get, post},
 };
-use sensei_common::{AgentCategory, AskRequest, AskResponse, Health};
+use futures::stream::{self, Stream};
+use knowledge::Reader;
+mod auth;
+mod knowledge;
+#[cfg(unix)]
+pub mod peer;
+pub mod reload;
+mod sessions;
+
+use sensei_common::{
+    AgentCategory, AskRequest, AskResponse, AskStreamEvent, Citation, Classification, Health,
+};
 use sensei_lib::agents::Orchestrator;
 use sensei_lib::agents::router::RouterAgent;
+use sensei_lib::auth::{Caller, Scope};
+use sensei_lib::context::{History, generate_session_title};
+use sensei_lib::errors::SenseiError;
+use sensei_lib::ingest::{ChunkConfig, IngestRequest, ingest};
 use sensei_lib::llm::Llm;
 use sensei_lib::memory::MemoryStore;
+use sensei_lib::rag;
+use sensei_lib::reload::Reloader;
 use serde::Deserialize;
 use serde_json::{Value, json};
+use sessions::SessionScope;
+use std::convert::Infallible;
 use std::sync::Arc;
+use tokio::sync::mpsc;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -20,15 +43,35 @@ pub struct AppState {
     pub router: Arc<RouterAgent>,
     pub memory: MemoryStore,
     pub llm: Arc<dyn Llm>,
+    /// Require an API key with the right scope on every `/v1` route.
+    pub auth_required: bool,
+    /// How ingested documents are split before embedding.
+    pub chunking: ChunkConfig,
+    /// Applies configuration changes to the running swarm (`None` disables `/v1/admin/reload`).
+    pub reloader: Option<Arc<Reloader>>,
 }
 
 pub fn app(state: AppState) -> Router {
-    Router::new()
-        .route("/health", get(health_check))
+    let ask = Router::new()
         .route("/v1/ask", post(ask_handler))
+        .route("/v1/ask/stream", post(ask_stream_handler))
         .route("/v1/debug/classify", post(debug_classify_handler))
+        .merge(sessions::routes());
+    let ingest = Router::new()
         .route("/v1/knowledge/add", post(add_document_handler))
-        .route("/v1/feedback/correct", post(correct_routing_handler)) // New Endpoint
+        .merge(knowledge::routes());
+    let feedback = Router::new().route("/v1/feedback/correct", post(correct_routing_handler));
+
+    Router::new()
+        .route("/health", get(health_check))
+        .merge(auth::protect(ask, &state, Scope::Ask))
+        .merge(auth::protect(ingest, &state, Scope::Ingest))
+        .merge(auth::protect(feedback, &state, Scope::Feedback))
+        .merge(auth::protect(
+            auth::routes().merge(reload::routes()),
+            &state,
+            Scope::Admin,
+        ))
         .with_state(state)
 }
 
@@ -41,6 +84,13 @@ async fn health_check() -> Json<Health> {
 #[derive(Deserialize)]
 struct AddDocumentRequest {
     content: String,
+    #[serde(default)]
+    classification: Classification,
+    /// Identifies the document across versions (e.g. its file path).
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -68,33 +118,84 @@ async fn correct_routing_handler(
 
 async fn add_document_handler(
     State(state): State<AppState>,
+    reader: Reader,
     Json(payload): Json<AddDocumentRequest>,
 ) -> impl IntoResponse {
-    // 1. Generate Embedding
-    let embedding = match state.llm.embed(&payload.content).await {
-        Ok(vec) => vec,
-        Err(e) => {
+    // A new version replaces or relabels every chunk of the source.
+    if let Some(source) = &payload.source {
+        match state
+            .memory
+            .may_rewrite_source(source, reader.clearance, &reader.subject)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => {
+                return (
+                    StatusCode::FORBIDDEN,
+                    Json(json!({
+                        "error": format!("Source '{}' holds documents above your clearance", source)
+                    })),
+                );
+            }
+            Err(e) => {
+                eprintln!("Storage Error: {}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({"error": "Failed to store document"})),
+                );
+            }
+        }
+    }
+
+    let request = IngestRequest {
+        content: payload.content,
+        source: payload.source,
+        classification: payload.classification,
+        tags: payload.tags,
+    };
+
+    // Chunk, embed and store (or skip an unchanged source)
+    match ingest(&state.memory, state.llm.as_ref(), request, &state.chunking).await {
+        Ok(report) => {
+            let status = if report.relabeled > 0 {
+                "Document unchanged, labels updated"
+            } else if report.unchanged {
+                "Document unchanged, skipped"
+            } else {
+                "Document ingested successfully"
+            };
+            println!(
+                "📥 Ingest {}: {} chunks stored, {} replaced",
+                report.source.as_deref().unwrap_or("(inline)"),
+                report.chunks,
+                report.replaced
+            );
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": status,
+                    "chunks": report.chunks,
+                    "replaced": report.replaced,
+                    "unchanged": report.unchanged,
+                    "relabeled": report.relabeled
+                })),
+            )
+        }
+        Err(e @ SenseiError::Llm(_)) => {
             eprintln!("Embedding Error: {}", e);
-            return (
+            (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(json!({"error": "Failed to generate embedding"})),
-            );
+            )
+        }
+        Err(e) => {
+            eprintln!("Storage Error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": "Failed to store document"})),
+            )
         }
-    };
-
-    // 2. Store Document
-    if let Err(e) = state.memory.add_document(&payload.content, embedding).await {
-        eprintln!("Storage Error: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": "Failed to store document"})),
-        );
     }
-
-    (
-        StatusCode::OK,
-        Json(json!({"status": "Document ingested successfully"})),
-    )
 }
 
 async fn debug_classify_handler(
@@ -109,91 +210,299 @@ async fn debug_classify_handler(
     }))
 }
 
+/// Reuse the caller's `x-session-id` or open a new session owned by the caller.
+/// Unknown sessions and sessions of other callers are refused as not found.
+async fn resolve_session(
+    state: &AppState,
+    scope: &SessionScope,
+    caller: Option<&str>,
+    headers: &HeaderMap,
+) -> Result<String, (StatusCode, String)> {
+    let init_failed = || {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to init session".to_string(),
+        )
+    };
+
+    let Some(header_val) = headers.get("x-session-id") else {
+        return state
+            .memory
+            .create_session_as(None, caller)
+            .await
+            .map_err(|e| {
+                eprintln!("DB Error (Session): {}", e);
+                init_failed()
+            });
+    };
+
+    let session_id = header_val.to_str().unwrap_or("").to_string();
+    if session_id.is_empty() {
+        return Err(init_failed());
+    }
+    match state.memory.find_session(&session_id).await {
+        Ok(Some(session)) if scope.allows(&session) => Ok(session_id),
+        Ok(_) => Err((
+            StatusCode::NOT_FOUND,
+            format!("Session '{}' not found", session_id),
+        )),
+        Err(e) => {
+            eprintln!("DB Error (Session): {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to load session".to_string(),
+            ))
+        }
+    }
+}
+
+/// Give untitled sessions a generated title once their first exchange is stored.
+/// Runs in the background so it never delays the answer.
+fn spawn_auto_title(state: &AppState, session_id: &str, prompt: &str, answer: &str) {
+    let state = state.clone();
+    let (session_id, prompt, answer) = (
+        session_id.to_string(),
+        prompt.to_string(),
+        answer.to_string(),
+    );
+
+    tokio::spawn(async move {
+        let first_exchange = matches!(state.memory.count_messages(&session_id).await, Ok(2));
+        let untitled = matches!(
+            state.memory.find_session(&session_id).await,
+            Ok(Some(ref session)) if session.title.is_none()
+        );
+        if !first_exchange || !untitled {
+            return;
+        }
+
+        if let Some(title) = generate_session_title(state.llm.as_ref(), &prompt, &answer).await
+            && let Err(e) = state.memory.update_session_title(&session_id, &title).await
+        {
+            eprintln!("DB Error (Session Title): {}", e);
+        }
+    });
+}
+
+/// Label of an answer: the highest classification of the history and knowledge it was built from.
+fn answer_classification(history: &History, sources: &[Citation]) -> Classification {
+    sources
+        .iter()
+        .map(|source| source.classification)
+        .fold(history.classification, Classification::max)
+}
+
+/// Knowledge for the agent serving `category`, limited to what `reader` may read,
+/// see `sensei_lib::rag::enrich_with_rag_capped`.
+async fn enrich_with_rag(
+    state: &AppState,
+    reader: &Reader,
+    category: &AgentCategory,
+    query: String,
+) -> (String, Vec<Citation>) {
+    rag::enrich_with_rag_capped(
+        &state.orchestrator,
+        &state.memory,
+        state.llm.as_ref(),
+        category,
+        query,
+        reader.clearance,
+    )
+    .await
+}
+
 async fn ask_handler(
     State(state): State<AppState>,
+    caller: Option<Extension<Caller>>,
+    reader: Reader,
+    scope: SessionScope,
     headers: HeaderMap,
     Json(payload): Json<AskRequest>,
 ) -> impl IntoResponse {
+    let caller = caller.map(|Extension(c)| c.0);
     // 1. Session ID
-    let session_id = if let Some(header_val) = headers.get("x-session-id") {
-        header_val.to_str().unwrap_or("").to_string()
-    } else {
-        state.memory.create_session(None).await.unwrap_or_default()
+    let session_id = match resolve_session(&state, &scope, caller.as_deref(), &headers).await {
+        Ok(session_id) => session_id,
+        Err((status, content)) => {
+            return (
+                status,
+                Json(AskResponse {
+                    content,
+                    sources: Vec::new(),
+                }),
+            )
+                .into_response();
+        }
     };
 
-    if session_id.is_empty() {
-        return (
-            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-            Json(AskResponse {
-                content: "Failed to init session".to_string(),
-            }),
-        )
-            .into_response();
-    }
+    // 2. Route Query
+    let decision = state.router.classify(&payload.prompt).await;
+    println!(
+        "🧠 Routing query '{}' from {} to {:?} (Optimized: '{}')",
+        payload.prompt,
+        caller.as_deref().unwrap_or("anonymous"),
+        decision.category,
+        decision.query
+    );
 
-    // 2. Persist User Message
+    // 3. Load the prior turns the agent is cleared for, then persist User Message
+    let history = state
+        .orchestrator
+        .load_history(&session_id, &decision.category)
+        .await;
     if let Err(e) = state
         .memory
-        .add_message(&session_id, "user", &payload.prompt)
+        .add_message_as(
+            &session_id,
+            "user",
+            &payload.prompt,
+            caller.as_deref(),
+            Classification::Unclassified,
+        )
         .await
     {
         eprintln!("DB Error (User Msg): {}", e);
     }
 
-    // 3. Route Query
-    let decision = state.router.classify(&payload.prompt).await;
-    println!(
-        "🧠 Routing query '{}' to {:?} (Optimized: '{}')",
-        payload.prompt, decision.category, decision.query
-    );
-
     // 3.5. RAG Retrieval
-    let context_docs = match state.llm.embed(&decision.query).await {
-        Ok(embedding) => state
-            .memory
-            .search_documents(embedding, 3)
-            .await
-            .unwrap_or_default(),
-        Err(e) => {
-            eprintln!("RAG Embedding Failed: {}", e);
-            vec![]
-        }
-    };
-
-    let final_prompt = if !context_docs.is_empty() {
-        println!("📚 RAG: Found {} relevant documents.", context_docs.len());
-        format!(
-            "RELEVANT KNOWLEDGE:\n{}
-
-USER QUERY:
-{}",
-            context_docs.join("\n---\n"),
-            decision.query
-        )
-    } else {
-        decision.query
-    };
+    let (final_prompt, sources) =
+        enrich_with_rag(&state, &reader, &decision.category, decision.query).await;
 
     // 4. Dispatch to Agent using context-enriched query
     let content = state
         .orchestrator
-        .dispatch(decision.category, &final_prompt)
+        .dispatch_with_history(decision.category, &final_prompt, &history.turns)
         .await;
 
     // 5. Persist AI Message
     if let Err(e) = state
         .memory
-        .add_message(&session_id, "assistant", &content)
+        .add_message_as(
+            &session_id,
+            "assistant",
+            &content,
+            caller.as_deref(),
+            answer_classification(&history, &sources),
+        )
         .await
     {
         eprintln!("DB Error (AI Msg): {}", e);
     }
+    spawn_auto_title(&state, &session_id, &payload.prompt, &content);
 
     // 6. Response
-    let mut response = Json(AskResponse { content }).into_response();
+    let mut response = Json(AskResponse { content, sources }).into_response();
     if let Ok(header_val) = axum::http::HeaderValue::from_str(&session_id) {
         response.headers_mut().insert("x-session-id", header_val);
     }
 
     response
 }
+
+/// Same pipeline as `ask_handler`, but emits routing, RAG, delegation and token events
+/// as Server-Sent Events. The final `done` frame carries the persisted answer.
+async fn ask_stream_handler(
+    State(state): State<AppState>,
+    caller: Option<Extension<Caller>>,
+    reader: Reader,
+    scope: SessionScope,
+    headers: HeaderMap,
+    Json(payload): Json<AskRequest>,
+) -> impl IntoResponse {
+    let caller = caller.map(|Extension(c)| c.0);
+    // Refuse before the stream opens, so the status code tells the client what went wrong.
+    let session_id = match resolve_session(&state, &scope, caller.as_deref(), &headers).await {
+        Ok(session_id) => session_id,
+        Err((status, message)) => {
+            return (status, Json(json!({ "error": message }))).into_response();
+        }
+    };
+    let session_header = axum::http::HeaderValue::from_str(&session_id).ok();
+    let (tx, rx) = mpsc::unbounded_channel::<AskStreamEvent>();
+
+    tokio::spawn(async move {
+        let decision = state.router.classify(&payload.prompt).await;
+        println!(
+            "🧠 Streaming query '{}' from {} to {:?} (Optimized: '{}')",
+            payload.prompt,
+            caller.as_deref().unwrap_or("anonymous"),
+            decision.category,
+            decision.query
+        );
+
+        let history = state
+            .orchestrator
+            .load_history(&session_id, &decision.category)
+            .await;
+        if let Err(e) = state
+            .memory
+            .add_message_as(
+                &session_id,
+                "user",
+                &payload.prompt,
+                caller.as_deref(),
+                Classification::Unclassified,
+            )
+            .await
+        {
+            eprintln!("DB Error (User Msg): {}", e);
+        }
+        let _ = tx.send(AskStreamEvent::Routing {
+            category: decision.category.clone(),
+            query: decision.query.clone(),
+        });
+
+        let (final_prompt, sources) =
+            enrich_with_rag(&state, &reader, &decision.category, decision.query).await;
+        let classification = answer_classification(&history, &sources);
+        let _ = tx.send(AskStreamEvent::Rag {
+            documents: sources.len(),
+            sources,
+        });
+
+        let content = state
+            .orchestrator
+            .dispatch_stream(decision.category, &final_prompt, &history.turns, &tx)
+            .await;
+
+        // Persist even if the client went away mid-stream.
+        if let Err(e) = state
+            .memory
+            .add_message_as(
+                &session_id,
+                "assistant",
+                &content,
+                caller.as_deref(),
+                classification,
+            )
+            .await
+        {
+            eprintln!("DB Error (AI Msg): {}", e);
+        }
+        spawn_auto_title(&state, &session_id, &payload.prompt, &content);
+
+        let _ = tx.send(AskStreamEvent::Done { content });
+    });
+
+    let mut response = Sse::new(sse_frames(rx))
+        .keep_alive(KeepAlive::default())
+        .into_response();
+    if let Some(header_val) = session_header {
+        response.headers_mut().insert("x-session-id", header_val);
+    }
+
+    response
+}
+
+fn sse_frames(
+    rx: mpsc::UnboundedReceiver<AskStreamEvent>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(rx, |mut rx| async move {
+        let event = rx.recv().await?;
+        let frame = Event::default()
+            .event(event.name())
+            .json_data(&event)
+            .unwrap_or_else(|_| Event::default().event("error"));
+        Some((Ok(frame), rx))
+    })
+}
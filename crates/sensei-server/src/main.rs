@@ -1,21 +1,57 @@
 use anyhow::Context;
+use clap::{Parser, Subcommand};
 use dotenvy::dotenv;
-use sensei_common::AgentCategory;
-use sensei_lib::agents::{
-    Orchestrator, action::ToolExecutorAgent, router::RouterAgent, specialists::SpecializedAgent,
-};
-use sensei_lib::config::load_prompts;
-use sensei_lib::llm::{
-    GeminiClient, Llm, MODEL_CHAT_FAST, MODEL_CHAT_SMART, OllamaClient, TieredLlmClient,
-};
+use sensei_common::Classification;
+use sensei_lib::auth::{format_scopes, parse_scopes};
+use sensei_lib::ingest::reembed;
 use sensei_lib::memory::MemoryStore;
+use sensei_lib::runtime::{RuntimeConfig, SenseiRuntime};
+#[cfg(unix)]
+use sensei_server::peer::{PeerCredListener, PeerIdentity, PeerPolicy};
 use sensei_server::{AppState, app};
 use std::env;
-use std::sync::Arc;
 use tokio::net::TcpListener;
 use tracing::{info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+#[derive(Parser, Debug)]
+#[command(version, about = "Sensei API server", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Run the API server (default)
+    Serve,
+    /// Manage API keys
+    #[command(subcommand)]
+    Keys(KeyCommands),
+    /// Rebuild the vector tables after changing the embedding model or dimension
+    Reembed,
+}
+
+#[derive(Subcommand, Debug)]
+enum KeyCommands {
+    /// Create a key and print it (it is shown only once)
+    Create {
+        /// Who or what uses the key (recorded on messages)
+        #[arg(long)]
+        name: String,
+        /// Comma separated scopes: ask, ingest, feedback, admin
+        #[arg(long, default_value = "ask")]
+        scopes: String,
+        /// Highest classification of documents the key may read (admin keys read everything)
+        #[arg(long, default_value = "unclassified")]
+        clearance: Classification,
+    },
+    /// List keys
+    List,
+    /// Revoke a key by id or prefix
+    Revoke { id: String },
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenv().ok();
@@ -25,345 +61,158 @@ async fn main() -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .try_init()?;
 
-    // 1. Load Configuration (Prompts)
-    let prompts_path =
-        env::var("SENSEI_PROMPTS_PATH").unwrap_or_else(|_| "prompts.yaml".to_string());
-    let prompts_config = match load_prompts(&prompts_path) {
-        Ok(c) => {
-            info!("✅ Loaded prompts from {}", prompts_path);
-            Some(c)
-        }
-        Err(e) => {
-            warn!(
-                "⚠️ Failed to load prompts from '{}': {}. Using default prompts.",
-                prompts_path, e
-            );
-            None
-        }
-    };
-
-    let get_prompt = |key: &str, default: &str| -> String {
-        prompts_config
-            .as_ref()
-            .and_then(|config| config.agents.get(key))
-            .map(|agent_conf| agent_conf.prompt.clone())
-            .unwrap_or_else(|| default.to_string())
-    };
-
-    // 2. Init LLM Clients
-    let _api_key = env::var("GEMINI_API_KEY").context("GEMINI_API_KEY must be set")?;
-
-    // Support standard OLLAMA_URL env var
-    if let Ok(url) = env::var("OLLAMA_URL") {
-        // SAFETY: Safe because called at startup before threads
-        unsafe {
-            env::set_var("OLLAMA_API_BASE_URL", url);
-        }
+    match Cli::parse().command {
+        None | Some(Commands::Serve) => serve().await,
+        Some(Commands::Keys(command)) => manage_keys(command).await,
+        Some(Commands::Reembed) => reembed_all().await,
     }
+}
 
-    let ollama_model = env::var("OLLAMA_MODEL").ok();
+async fn reembed_all() -> anyhow::Result<()> {
+    let config = RuntimeConfig::from_env()?;
+    let embedding = config.embedding_config();
+    let memory = MemoryStore::new(&config.database_url)
+        .await
+        .context("Failed to connect to database")?
+        .with_embedding(embedding.clone());
+    memory
+        .migrate()
+        .await
+        .context("Failed to migrate database")?;
 
-    if let Some(ref m) = ollama_model {
-        info!("🦙 Ollama fallback enabled with model: {}", m);
+    let status = memory.vector_index_status().await?;
+    println!(
+        "🧮 Re-embedding with '{}' ({} dimensions): {} documents, {} router cache entries",
+        embedding.model, embedding.dimension, status.stale_documents, status.stale_router_cache
+    );
+    for (table, dimension) in &status.resize {
+        println!(
+            "   Recreating {} ({} -> {} dimensions)",
+            table, dimension, embedding.dimension
+        );
     }
 
-    // Helper builder for Tiered Architecture
-    let build_llm = |model_gemini: &str| -> Arc<dyn Llm> {
-        let primary = Box::new(GeminiClient::new(model_gemini));
-
-        let secondary: Option<Box<dyn Llm>> = if let Some(ref m) = ollama_model {
-            Some(Box::new(OllamaClient::new(m)))
-        } else {
-            None
-        };
-
-        Arc::new(TieredLlmClient::new(primary, secondary))
-    };
-
-    // ⚡ Fast Tier
-    let fast_llm = build_llm(MODEL_CHAT_FAST);
-
-    // 🧠 Smart Tier
-    let smart_llm = build_llm(MODEL_CHAT_SMART);
-
-    // 3. Init Memory
-    let db_url = env::var("DATABASE_URL").unwrap_or("sqlite://sensei.db?mode=rwc".to_string());
-    info!("📦 Connecting to database: {}", db_url);
+    let report = reembed(&memory, config.embedder().as_ref()).await?;
+    println!(
+        "✅ Re-embedded {} documents and {} router cache entries",
+        report.documents, report.router_cache
+    );
+    Ok(())
+}
 
-    let memory = MemoryStore::new(&db_url)
+async fn manage_keys(command: KeyCommands) -> anyhow::Result<()> {
+    let config = RuntimeConfig::from_env()?;
+    let memory = MemoryStore::new(&config.database_url)
         .await
         .context("Failed to connect to database")?;
-
     memory
         .migrate()
         .await
         .context("Failed to migrate database")?;
 
-    // 4. Init Swarm
-    let orchestrator = Orchestrator::new();
-
-    // Specialists -> Smart LLM
-    orchestrator
-        .register(Box::new(SpecializedAgent::new(
-            smart_llm.clone(),
-            AgentCategory::new("red"),
-            &get_prompt("red_team", "SYSTEM: You are a Red Team Operator."),
-            Some(get_prompt("master", "SYSTEM: You are SENSEI.")),
-        )))
-        .await;
-
-    orchestrator
-        .register(Box::new(SpecializedAgent::new(
-            smart_llm.clone(),
-            AgentCategory::new("blue"),
-            &get_prompt("blue_team", "SYSTEM: You are a Blue Team Analyst."),
-            None,
-        )))
-        .await;
-
-    orchestrator
-        .register(Box::new(SpecializedAgent::new(
-            smart_llm.clone(),
-            AgentCategory::new("cloud"),
-            &get_prompt("cloud", "SYSTEM: You are a Cloud Security Architect."),
-            None,
-        )))
-        .await;
-
-    orchestrator
-        .register(Box::new(SpecializedAgent::new(
-            smart_llm.clone(),
-            AgentCategory::new("crypto"),
-            &get_prompt("crypto", "SYSTEM: You are a Cryptographer."),
-            None,
-        )))
-        .await;
-
-    orchestrator
-        .register(Box::new(SpecializedAgent::new(
-            smart_llm.clone(),
-            AgentCategory::new("osint"),
-            &get_prompt("osint", "SYSTEM: You are an Intelligence Officer."),
-            None,
-        )))
-        .await;
-
-    orchestrator
-        .register(Box::new(SpecializedAgent::new(
-            smart_llm.clone(),
-            AgentCategory::new("system"),
-            &get_prompt("system", "SYSTEM: You are Root."),
-            None,
-        )))
-        .await;
-
-    // Casual/Novice -> Fast LLM
-    orchestrator
-        .register(Box::new(SpecializedAgent::new(
-            fast_llm.clone(),
-            AgentCategory::new("casual"),
-            &get_prompt("casual", "SYSTEM: You are Sensei."),
-            None,
-        )))
-        .await;
-
-    orchestrator
-        .register(Box::new(SpecializedAgent::new(
-            fast_llm.clone(),
-            AgentCategory::new("novice"),
-            &get_prompt("novice", "SYSTEM: You are a Teacher."),
-            None,
-        )))
-        .await;
-
-    // Register Tool Agents (Action & System Tools)
-    let mut action_agent = ToolExecutorAgent::new(fast_llm.clone(), AgentCategory::new("action"));
-    action_agent.register_tool(Box::new(sensei_lib::tools::nmap::NmapTool));
-    orchestrator.register(Box::new(action_agent)).await;
-
-    let mut system_tool_agent =
-        ToolExecutorAgent::new(fast_llm.clone(), AgentCategory::new("system"));
-    system_tool_agent.register_tool(Box::new(sensei_lib::tools::system::SystemTool));
-    orchestrator.register(Box::new(system_tool_agent)).await;
-
-    // 4.5 Init MCP Agents (Dynamic)
-    let mut dynamic_extensions = Vec::new();
-    let mcp_path = env::var("SENSEI_MCP_CONFIG").unwrap_or("mcp_settings.json".to_string());
-
-    if let Ok(mcp_config) = sensei_lib::config::load_mcp_settings(&mcp_path) {
-        info!("🔌 Loading MCP Servers from {}", mcp_path);
-        for (name, conf) in mcp_config.mcp_servers {
-            let envs = conf.env;
-            let args_str: Vec<&str> = conf.args.iter().map(|s| s.as_str()).collect();
-
-            info!("   - Connecting to {}...", name);
-            match sensei_lib::mcp_client::McpClient::new(&conf.command, &args_str, envs).await {
-                Ok(client) => {
-                    let client_arc = Arc::new(client);
-                    match sensei_lib::agents::mcp_agent::McpAgent::new(
-                        client_arc,
-                        fast_llm.clone(),
-                        &name,
-                    )
-                    .await
-                    {
-                        Ok(agent) => {
-                            info!("   ✅ MCP Agent '{}' registered", name);
-                            orchestrator.register(Box::new(agent)).await;
-                            dynamic_extensions.push(name.to_uppercase());
-                        }
-                        Err(e) => warn!("   ❌ Failed to init MCP Agent '{}': {}", name, e),
-                    }
-                }
-                Err(e) => warn!("   ❌ Failed to spawn MCP Server '{}': {}", name, e),
+    match command {
+        KeyCommands::Create {
+            name,
+            scopes,
+            clearance,
+        } => {
+            let scopes = parse_scopes(&scopes)?;
+            if scopes.is_empty() {
+                anyhow::bail!("At least one scope is required");
+            }
+            let (api_key, key) = memory
+                .create_cleared_api_key(&name, &scopes, clearance)
+                .await?;
+            println!("🔑 Created key '{}' ({})", api_key.name, api_key.id);
+            println!("   Scopes: {}", format_scopes(&api_key.scopes));
+            println!("   Clearance: {}", api_key.effective_clearance());
+            println!("\n{}\n", key);
+            println!("Store it now: it cannot be shown again.");
+        }
+        KeyCommands::List => {
+            let keys = memory.list_api_keys().await?;
+            if keys.is_empty() {
+                println!("No API keys. Create one with `sensei-server keys create --name <name>`.");
+            }
+            for key in keys {
+                println!(
+                    "{}  {:<16} {:<18} {:<28} {:<13} created {}  last used {}{}",
+                    key.id,
+                    key.prefix,
+                    key.name,
+                    format_scopes(&key.scopes),
+                    key.effective_clearance().as_str(),
+                    key.created_at,
+                    key.last_used_at
+                        .map(|t| t.to_string())
+                        .unwrap_or_else(|| "never".to_string()),
+                    if key.is_revoked() { "  [revoked]" } else { "" }
+                );
+            }
+        }
+        KeyCommands::Revoke { id } => {
+            if memory.revoke_api_key(&id).await? {
+                println!("🚫 Revoked key '{}'", id);
+            } else {
+                anyhow::bail!("No active API key matches '{}'", id);
             }
         }
     }
 
-    // 5. Init Router -> Fast LLM
-    let mut router_prompt = get_prompt(
-        "router",
-        r#"
-        You are a Query Optimizer.
-        STANDARD CATEGORIES: RED, BLUE, OSINT, CLOUD, CRYPTO, SYSTEM, ACTION, CASUAL, NOVICE.
-        ACTIVE EXTENSIONS: {EXTENSIONS}
-        RULES:
-        1. If the user asks to EXECUTE a specific tool or command (e.g. "scan IP", "check uptime"), classify as ACTION (or SYSTEM).
-        2. If the user asks for EXPLANATIONS or PLANS, classify as RED/BLUE/etc.
-        Classify user input into one of the above categories.
-        If the input requires an extension tool, output the extension name (e.g. FILESYSTEM) as the category.
-        Output strictly JSON format: {"category": "CategoryName", "enhanced_query": "Query"}
-        "#,
-    );
-
-    let extensions_str = if dynamic_extensions.is_empty() {
-        "NONE".to_string()
-    } else {
-        dynamic_extensions.join(", ")
-    };
+    Ok(())
+}
 
-    router_prompt = router_prompt.replace("{EXTENSIONS}", &extensions_str);
-    info!(
-        "🧠 Router Prompt Configured with Extensions: {}",
-        extensions_str
-    );
+async fn serve() -> anyhow::Result<()> {
+    // 1-4. LLMs, memory and the swarm declared in prompts.yaml and mcp_settings.json
+    let SenseiRuntime {
+        config,
+        orchestrator,
+        router,
+        memory,
+        smart_llm,
+        chunking,
+        reloader,
+        ..
+    } = SenseiRuntime::from_env().await?;
 
-    let router = Arc::new(RouterAgent::new(
-        fast_llm.clone(),
-        Some(memory.clone()),
-        &router_prompt,
-    ));
+    // 5.5 Authentication: API keys on TCP by default, the Unix socket is owner-only
+    let listen_target = env::var("SENSEI_LISTEN_ADDR").unwrap_or("0.0.0.0:3000".to_string());
+    let auth_required = match env::var("SENSEI_AUTH").as_deref() {
+        Ok("off") | Ok("false") | Ok("0") => false,
+        Ok(_) => true,
+        Err(_) => !listen_target.starts_with("unix://"),
+    };
+    if auth_required {
+        info!("🔐 API key authentication enabled");
+        if memory.count_api_keys().await.unwrap_or(0) == 0 {
+            warn!(
+                "⚠️ No API keys exist: every request will be rejected. Create one with `sensei-server keys create --name <name> --scopes ask`."
+            );
+        }
+    } else if !listen_target.starts_with("unix://") {
+        warn!("⚠️ SENSEI_AUTH=off: the HTTP API is open to anyone who can reach it.");
+    }
 
     // 6. Build State
     let state = AppState {
-        orchestrator: Arc::new(orchestrator),
+        orchestrator,
         router,
         memory,
         llm: smart_llm.clone(),
+        auth_required,
+        chunking,
+        reloader: Some(reloader.clone()),
     };
 
-    // 6.5 Hot Reloading Watcher
-    let orchestrator_clone = state.orchestrator.clone();
-    let mcp_path_clone = mcp_path.clone();
-    let fast_llm_clone = fast_llm.clone();
-
-    let mut current_known_servers: std::collections::HashSet<String> =
-        dynamic_extensions.iter().cloned().collect();
-
-    tokio::spawn(async move {
-        use std::time::Duration;
-        let mut last_mtime = std::fs::metadata(&mcp_path_clone)
-            .and_then(|m| m.modified())
-            .ok();
-
-        loop {
-            tokio::time::sleep(Duration::from_secs(5)).await;
-            info!("DEBUG: Watcher checking {}...", mcp_path_clone);
-
-            let current_mtime = std::fs::metadata(&mcp_path_clone)
-                .and_then(|m| m.modified())
-                .ok();
-
-            if current_mtime.is_some() && current_mtime != last_mtime {
-                info!(
-                    "🔄 Configuration change detected in {}. Reloading MCP Agents...",
-                    mcp_path_clone
-                );
-                last_mtime = current_mtime;
-
-                if let Ok(new_config) = sensei_lib::config::load_mcp_settings(&mcp_path_clone) {
-                    let new_keys: std::collections::HashSet<String> = new_config
-                        .mcp_servers
-                        .keys()
-                        .map(|k| k.to_uppercase())
-                        .collect();
-
-                    // 1. Remove deleted agents
-                    let to_remove: Vec<String> = current_known_servers
-                        .difference(&new_keys)
-                        .cloned()
-                        .collect();
-                    for name in to_remove {
-                        info!("   🗑️ Removing agent '{}'", name);
-                        orchestrator_clone
-                            .unregister(&AgentCategory::new(&name))
-                            .await;
-                        current_known_servers.remove(&name);
-                    }
-
-                    // 2. Add new agents
-                    for (name, conf) in new_config.mcp_servers {
-                        let name_upper = name.to_uppercase();
-                        if !current_known_servers.contains(&name_upper) {
-                            info!("   ✨ Adding new agent '{}'", name);
-
-                            let envs = conf.env;
-                            let args_str = conf.args;
-                            let args_slice: Vec<&str> =
-                                args_str.iter().map(|s| s.as_str()).collect();
-
-                            match sensei_lib::mcp_client::McpClient::new(
-                                &conf.command,
-                                &args_slice,
-                                envs,
-                            )
-                            .await
-                            {
-                                Ok(client) => {
-                                    let client_arc = Arc::new(client);
-                                    match sensei_lib::agents::mcp_agent::McpAgent::new(
-                                        client_arc,
-                                        fast_llm_clone.clone(),
-                                        &name,
-                                    )
-                                    .await
-                                    {
-                                        Ok(agent) => {
-                                            orchestrator_clone.register(Box::new(agent)).await;
-                                            current_known_servers.insert(name_upper);
-                                        }
-                                        Err(e) => warn!(
-                                            "   ❌ Failed to init MCP Agent '{}': {}",
-                                            name, e
-                                        ),
-                                    }
-                                }
-                                Err(e) => {
-                                    warn!("   ❌ Failed to spawn MCP Server '{}': {}", name, e)
-                                }
-                            }
-                        }
-                    }
-                } else {
-                    warn!("   ❌ Failed to reload MCP settings (JSON syntax error?)");
-                }
-            }
-        }
-    });
+    // 6.5 Hot Reloading: file changes, SIGHUP and POST /v1/admin/reload
+    if let Err(e) = reloader.watch() {
+        warn!("⚠️ {}. Reload with SIGHUP or the admin API instead.", e);
+    }
+    #[cfg(unix)]
+    reloader.reload_on_sighup()?;
 
     // 7. Start Server
     let app = app(state);
-    let listen_target = env::var("SENSEI_LISTEN_ADDR").unwrap_or("0.0.0.0:3000".to_string());
 
     if listen_target.starts_with("unix://") {
         #[cfg(unix)]
@@ -376,11 +225,26 @@ async fn main() -> anyhow::Result<()> {
             let listener =
                 tokio::net::UnixListener::bind(path).context("Failed to bind to Unix socket")?;
 
-            use std::os::unix::fs::PermissionsExt;
-            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o700))?;
+            // Every peer is checked with SO_PEERCRED against the allowlist.
+            use std::os::unix::fs::{MetadataExt, PermissionsExt};
+            let owner = std::fs::metadata(path)?.uid();
+            let policy = PeerPolicy::from_env(owner)?;
+
+            // Other allowed users need filesystem access to the socket too.
+            let mode = if policy.is_shared() { 0o777 } else { 0o700 };
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
 
             info!("🚀 Sensei Server listening on Unix Socket: {}", path);
-            axum::serve(listener, app).await.context("Server crashed")?;
+            info!(
+                "🔒 Allowed peers: uids {:?}, gids {:?}",
+                policy.uids, policy.gids
+            );
+            axum::serve(
+                PeerCredListener::new(listener, policy),
+                app.into_make_service_with_connect_info::<PeerIdentity>(),
+            )
+            .await
+            .context("Server crashed")?;
         }
         #[cfg(not(unix))]
         {
@@ -395,8 +259,8 @@ async fn main() -> anyhow::Result<()> {
             "🚀 Sensei Server running on http://{} (Swarm Mode)",
             listen_target
         );
-        info!("⚡ Fast Model: {}", MODEL_CHAT_FAST);
-        info!("🧠 Smart Model: {}", MODEL_CHAT_SMART);
+        info!("⚡ Fast Model: {}", config.llm.fast_model);
+        info!("🧠 Smart Model: {}", config.llm.smart_model);
 
         axum::serve(listener, app).await.context("Server crashed")?;
     }
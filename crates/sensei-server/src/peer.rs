@@ -0,0 +1,187 @@
+//! Peer credential checks for the Unix socket listener.
+//!
+//! Every accepted connection is identified with `SO_PEERCRED` (uid, gid, pid)
+//! and dropped unless its user or its primary group is on the allowlist.
+//! Supplementary groups are not consulted: they can only be read by pid, which the
+//! peer may have exited and handed to another process by the time it is looked up.
+//! The identity is available to handlers as `ConnectInfo<PeerIdentity>`.
+
+use axum::extract::connect_info::Connected;
+use axum::serve::{IncomingStream, Listener};
+use sensei_lib::auth::Caller;
+use sensei_lib::errors::SenseiError;
+use std::env;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{UnixListener, UnixStream, unix};
+
+/// Local user on the other end of a Unix socket connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerIdentity {
+    pub uid: u32,
+    pub gid: u32,
+    pub pid: Option<i32>,
+}
+
+impl PeerIdentity {
+    /// Read the peer credentials of a connected stream.
+    pub fn from_stream(stream: &UnixStream) -> io::Result<Self> {
+        let cred = stream.peer_cred()?;
+        Ok(Self {
+            uid: cred.uid(),
+            gid: cred.gid(),
+            pid: cred.pid(),
+        })
+    }
+
+    /// Identity recorded on the messages of this peer, e.g. `uid:1000`.
+    pub fn caller(&self) -> Caller {
+        Caller(format!("uid:{}", self.uid))
+    }
+}
+
+/// Users and groups allowed to connect.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PeerPolicy {
+    pub uids: Vec<u32>,
+    pub gids: Vec<u32>,
+}
+
+impl PeerPolicy {
+    /// Only the given user (usually the one running the server).
+    pub fn owner(uid: u32) -> Self {
+        Self {
+            uids: vec![uid],
+            gids: Vec::new(),
+        }
+    }
+
+    /// `owner` plus the comma separated `SENSEI_ALLOWED_UIDS` and `SENSEI_ALLOWED_GIDS`.
+    pub fn from_env(owner: u32) -> Result<Self, SenseiError> {
+        let mut policy = Self::owner(owner);
+        for uid in parse_ids("SENSEI_ALLOWED_UIDS")? {
+            if !policy.uids.contains(&uid) {
+                policy.uids.push(uid);
+            }
+        }
+        policy.gids = parse_ids("SENSEI_ALLOWED_GIDS")?;
+        Ok(policy)
+    }
+
+    pub fn allows(&self, peer: &PeerIdentity) -> bool {
+        self.uids.contains(&peer.uid) || self.gids.contains(&peer.gid)
+    }
+
+    /// Whether anyone besides a single user may connect.
+    pub fn is_shared(&self) -> bool {
+        self.uids.len() > 1 || !self.gids.is_empty()
+    }
+}
+
+fn parse_ids(var: &str) -> Result<Vec<u32>, SenseiError> {
+    let Ok(list) = env::var(var) else {
+        return Ok(Vec::new());
+    };
+    list.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse()
+                .map_err(|_| SenseiError::Config(format!("{}: '{}' is not a numeric id", var, id)))
+        })
+        .collect()
+}
+
+/// A `UnixListener` that only hands allowed peers to the server.
+pub struct PeerCredListener {
+    listener: UnixListener,
+    policy: PeerPolicy,
+}
+
+impl PeerCredListener {
+    pub fn new(listener: UnixListener, policy: PeerPolicy) -> Self {
+        Self { listener, policy }
+    }
+}
+
+impl Listener for PeerCredListener {
+    type Io = PeerStream;
+    type Addr = unix::SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        loop {
+            let (stream, addr) = match self.listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    eprintln!("Accept Error: {}", e);
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+
+            match PeerIdentity::from_stream(&stream) {
+                Ok(identity) if self.policy.allows(&identity) => {
+                    return (PeerStream { stream, identity }, addr);
+                }
+                Ok(identity) => eprintln!(
+                    "🚫 Rejected Unix socket peer uid={} gid={} pid={:?}",
+                    identity.uid, identity.gid, identity.pid
+                ),
+                Err(e) => eprintln!("🚫 Rejected Unix socket peer: no credentials ({})", e),
+            }
+            // Dropping the stream closes the connection.
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.listener.local_addr()
+    }
+}
+
+/// An accepted connection together with the identity checked at accept time.
+pub struct PeerStream {
+    stream: UnixStream,
+    identity: PeerIdentity,
+}
+
+impl PeerStream {
+    pub fn identity(&self) -> &PeerIdentity {
+        &self.identity
+    }
+}
+
+impl AsyncRead for PeerStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for PeerStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+impl Connected<IncomingStream<'_, PeerCredListener>> for PeerIdentity {
+    fn connect_info(stream: IncomingStream<'_, PeerCredListener>) -> Self {
+        stream.io().identity().clone()
+    }
+}
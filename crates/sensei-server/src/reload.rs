@@ -0,0 +1,47 @@
+use crate::AppState;
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde_json::json;
+
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/v1/admin/reload", post(reload_handler))
+        .route("/v1/admin/mcp", get(mcp_status_handler))
+}
+
+fn reload_disabled() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({ "error": "Hot reload is not enabled on this server" })),
+    )
+        .into_response()
+}
+
+/// `POST /v1/admin/reload` applies the configuration files now.
+async fn reload_handler(State(state): State<AppState>) -> Response {
+    let Some(reloader) = state.reloader else {
+        return reload_disabled();
+    };
+
+    match reloader.reload().await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({ "error": format!("Configuration rejected: {}", e) })),
+        )
+            .into_response(),
+    }
+}
+
+/// `GET /v1/admin/mcp` reports the health of each MCP server.
+async fn mcp_status_handler(State(state): State<AppState>) -> Response {
+    match state.reloader {
+        Some(reloader) => Json(json!({ "servers": reloader.mcp_status().await })).into_response(),
+        None => reload_disabled(),
+    }
+}
@@ -0,0 +1,328 @@
+use crate::AppState;
+use axum::{
+    Json, Router,
+    extract::{FromRequestParts, Path, Query, State},
+    http::{StatusCode, header, request::Parts},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use sensei_lib::auth::{ApiKey, Caller, Scope};
+use sensei_lib::memory::{Message, Session};
+use serde::Deserialize;
+use serde_json::json;
+use std::convert::Infallible;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 500;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/v1/sessions", get(list_sessions_handler))
+        .route(
+            "/v1/sessions/{id}",
+            get(get_session_handler)
+                .patch(rename_session_handler)
+                .delete(delete_session_handler),
+        )
+        .route("/v1/sessions/{id}/messages", get(list_messages_handler))
+        .route("/v1/sessions/{id}/export", get(export_session_handler))
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Pagination {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+impl Pagination {
+    pub(crate) fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+
+    pub(crate) fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0).max(0)
+    }
+}
+
+/// Sessions a request may touch: all of them without a caller identity or with an
+/// `admin` key, otherwise only the sessions opened by the caller.
+pub(crate) struct SessionScope(Option<String>);
+
+impl SessionScope {
+    /// Owner to filter on, `None` when every session is visible.
+    pub(crate) fn owner(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+
+    pub(crate) fn allows(&self, session: &Session) -> bool {
+        self.0.is_none() || session.owner == self.0
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for SessionScope {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let admin = parts
+            .extensions
+            .get::<ApiKey>()
+            .is_some_and(|key| key.allows(Scope::Admin));
+        let owner = parts
+            .extensions
+            .get::<Caller>()
+            .filter(|_| !admin)
+            .map(|caller| caller.0.clone());
+        Ok(Self(owner))
+    }
+}
+
+#[derive(Deserialize)]
+struct RenameRequest {
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    format: Option<String>,
+}
+
+fn not_found(id: &str) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": format!("Session '{}' not found", id) })),
+    )
+        .into_response()
+}
+
+fn storage_error(e: impl std::fmt::Display) -> Response {
+    eprintln!("Storage Error: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": "Failed to access session storage" })),
+    )
+        .into_response()
+}
+
+/// Resolve a session or produce the matching error response.
+/// Sessions of other callers are reported as missing, so their ids are not confirmed.
+async fn load_session(
+    state: &AppState,
+    scope: &SessionScope,
+    id: &str,
+) -> Result<Session, Response> {
+    match state.memory.find_session(id).await {
+        Ok(Some(session)) if scope.allows(&session) => Ok(session),
+        Ok(_) => Err(not_found(id)),
+        Err(e) => Err(storage_error(e)),
+    }
+}
+
+async fn list_sessions_handler(
+    State(state): State<AppState>,
+    scope: SessionScope,
+    Query(page): Query<Pagination>,
+) -> Response {
+    let sessions = match scope.owner() {
+        Some(owner) => {
+            state
+                .memory
+                .list_owned_sessions_page(owner, page.limit(), page.offset())
+                .await
+        }
+        None => {
+            state
+                .memory
+                .list_sessions_page(page.limit(), page.offset())
+                .await
+        }
+    };
+    match sessions {
+        Ok(sessions) => Json(json!({
+            "sessions": sessions,
+            "limit": page.limit(),
+            "offset": page.offset(),
+        }))
+        .into_response(),
+        Err(e) => storage_error(e),
+    }
+}
+
+async fn get_session_handler(
+    State(state): State<AppState>,
+    scope: SessionScope,
+    Path(id): Path<String>,
+) -> Response {
+    let session = match load_session(&state, &scope, &id).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+
+    match state.memory.count_messages(&id).await {
+        Ok(count) => Json(json!({
+            "id": session.id,
+            "title": session.title,
+            "created_at": session.created_at,
+            "updated_at": session.updated_at,
+            "message_count": count,
+        }))
+        .into_response(),
+        Err(e) => storage_error(e),
+    }
+}
+
+async fn rename_session_handler(
+    State(state): State<AppState>,
+    scope: SessionScope,
+    Path(id): Path<String>,
+    Json(payload): Json<RenameRequest>,
+) -> Response {
+    let title = payload.title.trim();
+    if title.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Title must not be empty" })),
+        )
+            .into_response();
+    }
+
+    if let Err(response) = load_session(&state, &scope, &id).await {
+        return response;
+    }
+
+    if let Err(e) = state.memory.update_session_title(&id, title).await {
+        return storage_error(e);
+    }
+
+    match load_session(&state, &scope, &id).await {
+        Ok(session) => Json(session).into_response(),
+        Err(response) => response,
+    }
+}
+
+async fn delete_session_handler(
+    State(state): State<AppState>,
+    scope: SessionScope,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = load_session(&state, &scope, &id).await {
+        return response;
+    }
+
+    match state.memory.delete_session(&id).await {
+        Ok(()) => {
+            state.orchestrator.forget_session(&id).await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => storage_error(e),
+    }
+}
+
+async fn list_messages_handler(
+    State(state): State<AppState>,
+    scope: SessionScope,
+    Path(id): Path<String>,
+    Query(page): Query<Pagination>,
+) -> Response {
+    if let Err(response) = load_session(&state, &scope, &id).await {
+        return response;
+    }
+
+    let total = match state.memory.count_messages(&id).await {
+        Ok(total) => total,
+        Err(e) => return storage_error(e),
+    };
+
+    match state
+        .memory
+        .get_messages_page(&id, page.limit(), page.offset())
+        .await
+    {
+        Ok(messages) => Json(json!({
+            "messages": messages,
+            "total": total,
+            "limit": page.limit(),
+            "offset": page.offset(),
+        }))
+        .into_response(),
+        Err(e) => storage_error(e),
+    }
+}
+
+async fn export_session_handler(
+    State(state): State<AppState>,
+    scope: SessionScope,
+    Path(id): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    let session = match load_session(&state, &scope, &id).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+
+    let messages = match state.memory.get_messages(&id).await {
+        Ok(messages) => messages,
+        Err(e) => return storage_error(e),
+    };
+
+    match query.format.as_deref().unwrap_or("markdown") {
+        "markdown" | "md" => (
+            [
+                (
+                    header::CONTENT_TYPE,
+                    "text/markdown; charset=utf-8".to_string(),
+                ),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"sensei-{}.md\"", session.id),
+                ),
+            ],
+            render_markdown(&session, &messages),
+        )
+            .into_response(),
+        "json" => (
+            [(
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"sensei-{}.json\"", session.id),
+            )],
+            Json(json!({
+                "session": session,
+                "messages": messages,
+            })),
+        )
+            .into_response(),
+        other => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!("Unsupported export format '{}' (use markdown or json)", other)
+            })),
+        )
+            .into_response(),
+    }
+}
+
+fn render_markdown(session: &Session, messages: &[Message]) -> String {
+    let mut out = format!(
+        "# {}\n\n_Session `{}` — started {}_\n",
+        session.title.as_deref().unwrap_or("Untitled session"),
+        session.id,
+        session.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+
+    for message in messages {
+        let speaker = match message.role.as_str() {
+            "user" => "🧑 User",
+            "assistant" => "🥋 Sensei",
+            other => other,
+        };
+        out.push_str(&format!(
+            "\n## {} · {}\n\n{}\n",
+            speaker,
+            message.created_at.format("%H:%M:%S"),
+            message.content.trim_end()
+        ));
+    }
+
+    out
+}
@@ -0,0 +1,424 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use sensei_common::Classification;
+use sensei_lib::agents::{Orchestrator, router::RouterAgent};
+use sensei_lib::auth::Scope;
+use sensei_lib::ingest::ChunkConfig;
+use sensei_lib::llm::GeminiClient;
+use sensei_lib::memory::{DocumentMetadata, MemoryStore};
+use sensei_server::{AppState, app};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+async fn setup() -> (Router, MemoryStore) {
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+
+    let llm = Arc::new(GeminiClient::new("dummy"));
+    let state = AppState {
+        orchestrator: Arc::new(Orchestrator::new()),
+        router: Arc::new(RouterAgent::new(llm.clone(), None, "Dummy Prompt")),
+        memory: memory.clone(),
+        llm,
+        auth_required: true,
+        chunking: ChunkConfig::default(),
+        reloader: None,
+    };
+    (app(state), memory)
+}
+
+async fn send(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    key: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder().uri(uri).method(method);
+    if let Some(key) = key {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", key));
+    }
+    let body = match body {
+        Some(json) => {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+            Body::from(json.to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+#[tokio::test]
+async fn requests_without_a_valid_key_are_rejected() {
+    let (app, _) = setup().await;
+    let ask = json!({ "prompt": "Hello Sensei" });
+
+    let (status, _) = send(&app, "POST", "/v1/ask", None, Some(ask.clone())).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = send(&app, "POST", "/v1/ask", Some("sensei_bogus"), Some(ask)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Health checks stay public.
+    let (status, _) = send(&app, "GET", "/health", None, None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn keys_are_limited_to_their_scopes() {
+    let (app, memory) = setup().await;
+    let (_, key) = memory.create_api_key("ci", &[Scope::Ask]).await.unwrap();
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/v1/knowledge/add",
+        Some(&key),
+        Some(json!({ "content": "doc" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(body["error"].as_str().unwrap().contains("ingest"));
+
+    let (status, _) = send(&app, "GET", "/v1/admin/keys", Some(&key), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send(&app, "GET", "/v1/sessions", Some(&key), None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn messages_record_the_calling_key() {
+    let (app, memory) = setup().await;
+    let (_, key) = memory
+        .create_api_key("laptop", &[Scope::Ask])
+        .await
+        .unwrap();
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/v1/ask",
+        Some(&key),
+        Some(json!({ "prompt": "Hello Sensei" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let sessions = memory.list_sessions().await.unwrap();
+    let messages = memory.get_messages(&sessions[0].id).await.unwrap();
+    assert!(!messages.is_empty());
+    assert!(
+        messages
+            .iter()
+            .all(|m| m.caller.as_deref() == Some("key:laptop"))
+    );
+
+    let keys = memory.list_api_keys().await.unwrap();
+    assert!(keys[0].last_used_at.is_some());
+}
+
+#[tokio::test]
+async fn admin_keys_manage_other_keys() {
+    let (app, memory) = setup().await;
+    let (_, admin) = memory
+        .create_api_key("root", &[Scope::Admin])
+        .await
+        .unwrap();
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/v1/admin/keys",
+        Some(&admin),
+        Some(json!({ "name": "ingest-bot", "scopes": ["ingest"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let key = body["key"].as_str().unwrap().to_string();
+    let id = body["api_key"]["id"].as_str().unwrap().to_string();
+    assert!(key.starts_with("sensei_"));
+
+    let (status, body) = send(&app, "GET", "/v1/admin/keys", Some(&admin), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["keys"].as_array().unwrap().len(), 2);
+    assert!(
+        !body.to_string().contains(&key),
+        "plaintext keys are never listed"
+    );
+
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &format!("/v1/admin/keys/{}", id),
+        Some(&admin),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/v1/knowledge/add",
+        Some(&key),
+        Some(json!({ "content": "doc" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Sessions belong to a key name, so names are not handed out twice.
+    for name in ["root", "ingest-bot"] {
+        let (status, body) = send(
+            &app,
+            "POST",
+            "/v1/admin/keys",
+            Some(&admin),
+            Some(json!({ "name": name, "scopes": ["ask"] })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT, "{}", name);
+        assert!(body["error"].as_str().unwrap().contains("already exists"));
+    }
+}
+
+#[tokio::test]
+async fn sessions_are_scoped_to_the_key_that_opened_them() {
+    let (app, memory) = setup().await;
+    let (_, alice) = memory.create_api_key("alice", &[Scope::Ask]).await.unwrap();
+    let (_, bob) = memory.create_api_key("bob", &[Scope::Ask]).await.unwrap();
+    let (_, admin) = memory
+        .create_api_key("root", &[Scope::Admin])
+        .await
+        .unwrap();
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/v1/ask",
+        Some(&alice),
+        Some(json!({ "prompt": "Hello Sensei" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let sessions = memory.list_sessions().await.unwrap();
+    assert_eq!(sessions[0].owner.as_deref(), Some("key:alice"));
+    let session = format!("/v1/sessions/{}", sessions[0].id);
+
+    let (_, body) = send(&app, "GET", "/v1/sessions", Some(&alice), None).await;
+    assert_eq!(body["sessions"].as_array().unwrap().len(), 1);
+    let (status, _) = send(&app, "GET", &session, Some(&alice), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Other keys neither see nor touch the session...
+    let (_, body) = send(&app, "GET", "/v1/sessions", Some(&bob), None).await;
+    assert!(body["sessions"].as_array().unwrap().is_empty());
+    for (method, uri) in [
+        ("GET", session.clone()),
+        ("GET", format!("{}/messages", session)),
+        ("GET", format!("{}/export", session)),
+        ("DELETE", session.clone()),
+    ] {
+        let (status, _) = send(&app, method, &uri, Some(&bob), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{} {}", method, uri);
+    }
+
+    // ...nor continue it.
+    let request = Request::builder()
+        .uri("/v1/ask")
+        .method("POST")
+        .header(header::AUTHORIZATION, format!("Bearer {}", bob))
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("x-session-id", sessions[0].id.as_str())
+        .body(Body::from(
+            json!({ "prompt": "What did Alice ask?" }).to_string(),
+        ))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(memory.count_messages(&sessions[0].id).await.unwrap(), 2);
+
+    // Streaming is refused up front, as are sessions that do not exist at all.
+    for (uri, session) in [
+        ("/v1/ask/stream", sessions[0].id.as_str()),
+        ("/v1/ask", "no-such-session"),
+        ("/v1/ask/stream", "no-such-session"),
+    ] {
+        let request = Request::builder()
+            .uri(uri)
+            .method("POST")
+            .header(header::AUTHORIZATION, format!("Bearer {}", bob))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("x-session-id", session)
+            .body(Body::from(json!({ "prompt": "Hello" }).to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(
+            response.status(),
+            StatusCode::NOT_FOUND,
+            "{} {}",
+            uri,
+            session
+        );
+    }
+    assert_eq!(memory.count_messages(&sessions[0].id).await.unwrap(), 2);
+    assert_eq!(memory.list_sessions().await.unwrap().len(), 1);
+
+    // Admin keys see every session.
+    let (_, body) = send(&app, "GET", "/v1/sessions", Some(&admin), None).await;
+    assert_eq!(body["sessions"].as_array().unwrap().len(), 1);
+    let (status, _) = send(&app, "DELETE", &session, Some(&admin), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn knowledge_reads_are_limited_to_the_key_clearance() {
+    let (app, memory) = setup().await;
+    let public = memory
+        .add_classified_document(
+            "Backups run nightly.",
+            vec![0.1; 3072],
+            Classification::Unclassified,
+        )
+        .await
+        .unwrap();
+    let secret = memory
+        .add_classified_document(
+            "The NAS password is hunter2.",
+            vec![0.1; 3072],
+            Classification::Secret,
+        )
+        .await
+        .unwrap();
+    let (_, ingest) = memory
+        .create_api_key("ingest-bot", &[Scope::Ingest])
+        .await
+        .unwrap();
+    let (_, cleared) = memory
+        .create_cleared_api_key("analyst", &[Scope::Ingest], Classification::Secret)
+        .await
+        .unwrap();
+    let (_, admin) = memory
+        .create_api_key("root", &[Scope::Admin])
+        .await
+        .unwrap();
+
+    let (status, body) = send(&app, "GET", "/v1/knowledge", Some(&ingest), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["documents"][0]["id"], public);
+
+    let uri = format!("/v1/knowledge/{}", secret);
+    for (method, body) in [
+        ("GET", None),
+        ("PATCH", Some(json!({ "tags": ["leak"] }))),
+        ("DELETE", None),
+    ] {
+        let (status, _) = send(&app, method, &uri, Some(&ingest), body).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{} {}", method, uri);
+    }
+
+    // Every withheld read is audited.
+    let denials = memory.list_access_denials(10).await.unwrap();
+    assert_eq!(denials.len(), 4);
+    assert!(denials.iter().all(|d| d.subject == "key:ingest-bot"
+        && d.document_id == secret
+        && d.classification == Classification::Secret));
+
+    for key in [&cleared, &admin] {
+        let (_, body) = send(&app, "GET", "/v1/knowledge", Some(key), None).await;
+        assert_eq!(body["total"], 2);
+        let (status, body) = send(&app, "GET", &uri, Some(key), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["content"], "The NAS password is hunter2.");
+    }
+}
+
+#[tokio::test]
+async fn sources_are_only_rewritten_by_keys_cleared_for_them() {
+    let (app, memory) = setup().await;
+    let (_, ingest) = memory
+        .create_api_key("ingest-bot", &[Scope::Ingest])
+        .await
+        .unwrap();
+    let (_, cleared) = memory
+        .create_cleared_api_key("analyst", &[Scope::Ingest], Classification::Secret)
+        .await
+        .unwrap();
+    // A secret source, stored directly: this server's LLM cannot embed.
+    let metadata = DocumentMetadata {
+        classification: Classification::Secret,
+        source: Some("vault.md".to_string()),
+        ..Default::default()
+    };
+    memory
+        .replace_source_documents(
+            Some("vault.md"),
+            vec![(
+                "The NAS password is hunter2.".to_string(),
+                metadata,
+                vec![0.1; 3072],
+            )],
+        )
+        .await
+        .unwrap();
+    let (chunks, _) = memory
+        .list_documents_page(
+            &Default::default(),
+            Classification::TopSecret,
+            "test",
+            10,
+            0,
+        )
+        .await
+        .unwrap();
+    let chunk = chunks[0].id;
+
+    for (method, uri, body) in [
+        ("DELETE", "/v1/knowledge?source=vault.md", None),
+        (
+            "POST",
+            "/v1/knowledge/add",
+            Some(json!({ "content": "New password", "source": "vault.md" })),
+        ),
+    ] {
+        let (status, body) = send(&app, method, uri, Some(&ingest), body).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{} {}", method, uri);
+        assert!(
+            body["error"]
+                .as_str()
+                .unwrap()
+                .contains("above your clearance")
+        );
+    }
+    assert!(memory.find_document(chunk).await.unwrap().is_some());
+    assert_eq!(memory.list_access_denials(10).await.unwrap().len(), 2);
+
+    let (status, body) = send(
+        &app,
+        "DELETE",
+        "/v1/knowledge?source=vault.md",
+        Some(&cleared),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["deleted"], 1);
+}
@@ -3,6 +3,7 @@ use axum::{
     http::{Request, StatusCode, header},
 };
 use sensei_lib::agents::{Orchestrator, router::RouterAgent};
+use sensei_lib::ingest::ChunkConfig;
 use sensei_lib::llm::GeminiClient;
 use sensei_lib::memory::MemoryStore;
 use sensei_server::{AppState, app};
@@ -24,6 +25,9 @@ async fn ask_endpoint_returns_response() {
         router,
         memory,
         llm,
+        auth_required: false,
+        chunking: ChunkConfig::default(),
+        reloader: None,
     };
     let app = app(state);
 
@@ -0,0 +1,74 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use sensei_lib::agents::{Orchestrator, router::RouterAgent};
+use sensei_lib::ingest::ChunkConfig;
+use sensei_lib::llm::GeminiClient;
+use sensei_lib::memory::MemoryStore;
+use sensei_server::{AppState, app};
+use serde_json::json;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn ask_stream_endpoint_emits_sse_frames() {
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+
+    let llm = Arc::new(GeminiClient::new("dummy"));
+    let orchestrator = Arc::new(Orchestrator::new());
+    let router = Arc::new(RouterAgent::new(llm.clone(), None, "Dummy Prompt"));
+
+    let state = AppState {
+        orchestrator,
+        router,
+        memory: memory.clone(),
+        llm,
+        auth_required: false,
+        chunking: ChunkConfig::default(),
+        reloader: None,
+    };
+    let app = app(state);
+
+    let request = Request::builder()
+        .uri("/v1/ask/stream")
+        .method("POST")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            json!({
+                "prompt": "Hello Sensei"
+            })
+            .to_string(),
+        ))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/event-stream")
+    );
+
+    let session_id = response.headers()["x-session-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    // The stream ends once the `done` frame has been sent.
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body = String::from_utf8(body_bytes.to_vec()).unwrap();
+
+    assert!(body.contains("event: routing"));
+    assert!(body.contains("event: rag"));
+    assert!(body.contains("event: done"));
+
+    // Both turns are persisted once the stream is over.
+    let messages = memory.get_messages(&session_id).await.unwrap();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[1].role, "assistant");
+}
@@ -3,6 +3,7 @@ use axum::{
     http::{Request, StatusCode, header},
 };
 use sensei_lib::agents::{Orchestrator, router::RouterAgent};
+use sensei_lib::ingest::ChunkConfig;
 use sensei_lib::llm::GeminiClient;
 use sensei_lib::memory::MemoryStore;
 use sensei_server::{AppState, app};
@@ -25,6 +26,9 @@ async fn bdd_memory_flow() {
         router,
         memory: memory.clone(),
         llm,
+        auth_required: false,
+        chunking: ChunkConfig::default(),
+        reloader: None,
     };
     let app_router = app(state);
 
@@ -0,0 +1,328 @@
+use async_trait::async_trait;
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use sensei_common::{AgentCategory, AskResponse, Classification};
+use sensei_lib::agents::specialists::SpecializedAgent;
+use sensei_lib::agents::{Orchestrator, router::RouterAgent};
+use sensei_lib::auth::Scope;
+use sensei_lib::context::ContextWindow;
+use sensei_lib::errors::SenseiError;
+use sensei_lib::ingest::ChunkConfig;
+use sensei_lib::llm::Llm;
+use sensei_lib::memory::MemoryStore;
+use sensei_server::{AppState, app};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+/// Routes questions about passwords to `red`, everything else to `casual`,
+/// and answers with the prompt it was given.
+struct EchoLlm;
+
+#[async_trait]
+impl Llm for EchoLlm {
+    async fn generate(&self, prompt: &str) -> Result<String, SenseiError> {
+        if prompt.starts_with("ROUTER") {
+            let category = if prompt.contains("password") {
+                "red"
+            } else {
+                "casual"
+            };
+            return Ok(format!(r#"{{"category": "{}"}}"#, category));
+        }
+        Ok(prompt.to_string())
+    }
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>, SenseiError> {
+        Ok(vec![0.5; 3072])
+    }
+}
+
+async fn setup() -> Router {
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+
+    let llm = Arc::new(EchoLlm);
+    let orchestrator = Orchestrator::new();
+    orchestrator
+        .register(Box::new(SpecializedAgent::new(
+            llm.clone(),
+            AgentCategory::new("casual"),
+            "SYSTEM",
+            None,
+        )))
+        .await;
+
+    app(AppState {
+        orchestrator: Arc::new(orchestrator),
+        router: Arc::new(RouterAgent::new(llm.clone(), None, "ROUTER")),
+        memory,
+        llm,
+        auth_required: false,
+        chunking: ChunkConfig::default(),
+        reloader: None,
+    })
+}
+
+async fn post(app: &Router, uri: &str, body: Value) -> (StatusCode, Vec<u8>) {
+    let request = Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, bytes.to_vec())
+}
+
+#[tokio::test]
+async fn answers_list_the_knowledge_they_were_given() {
+    let app = setup().await;
+    for (content, source, classification) in [
+        (
+            "Backups run nightly and are kept on the NAS.",
+            "runbook.md",
+            "unclassified",
+        ),
+        ("The NAS admin password is hunter2.", "vault.md", "secret"),
+    ] {
+        let (status, _) = post(
+            &app,
+            "/v1/knowledge/add",
+            json!({ "content": content, "source": source, "classification": classification }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (status, body) = post(
+        &app,
+        "/v1/ask",
+        json!({ "prompt": "Where are backups kept?" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let response: AskResponse = serde_json::from_slice(&body).unwrap();
+
+    // The casual agent is not cleared for the secret document.
+    assert_eq!(response.sources.len(), 1);
+    let citation = &response.sources[0];
+    assert_eq!(citation.index, 1);
+    assert!(citation.uri.starts_with("sensei://knowledge/"));
+    assert_eq!(citation.source.as_deref(), Some("runbook.md"));
+    assert!(citation.score > 0.0);
+    assert_eq!(
+        citation.snippet,
+        "Backups run nightly and are kept on the NAS."
+    );
+
+    // The agent was asked to cite the numbered knowledge.
+    assert!(response.content.contains("[1] (runbook.md)"));
+    assert!(response.content.contains("Cite the knowledge you use"));
+    assert!(!response.content.contains("hunter2"));
+}
+
+#[tokio::test]
+async fn answers_without_knowledge_have_no_sources() {
+    let app = setup().await;
+
+    let (status, body) = post(&app, "/v1/ask", json!({ "prompt": "Hello Sensei" })).await;
+    assert_eq!(status, StatusCode::OK);
+    let response: AskResponse = serde_json::from_slice(&body).unwrap();
+    assert!(response.sources.is_empty());
+    assert!(!response.content.contains("RELEVANT KNOWLEDGE"));
+}
+
+#[tokio::test]
+async fn streamed_rag_frames_carry_the_sources() {
+    let app = setup().await;
+    let (status, _) = post(
+        &app,
+        "/v1/knowledge/add",
+        json!({ "content": "Backups run nightly.", "source": "runbook.md" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = post(
+        &app,
+        "/v1/ask/stream",
+        json!({ "prompt": "When do backups run?" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let body = String::from_utf8(body).unwrap();
+    let rag = body
+        .lines()
+        .skip_while(|line| *line != "event: rag")
+        .nth(1)
+        .and_then(|line| line.strip_prefix("data: "))
+        .unwrap();
+    let rag: Value = serde_json::from_str(rag).unwrap();
+    assert_eq!(rag["documents"], 1);
+    assert_eq!(rag["sources"][0]["source"], "runbook.md");
+}
+
+#[tokio::test]
+async fn answers_built_on_secret_knowledge_are_not_replayed_to_uncleared_agents() {
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+
+    let llm = Arc::new(EchoLlm);
+    let orchestrator = Orchestrator::new().with_context(ContextWindow::new(memory.clone(), 4000));
+    for (category, clearance) in [
+        ("casual", Classification::Unclassified),
+        ("red", Classification::Secret),
+    ] {
+        orchestrator
+            .register(Box::new(
+                SpecializedAgent::new(llm.clone(), AgentCategory::new(category), "SYSTEM", None)
+                    .with_clearance(clearance),
+            ))
+            .await;
+    }
+    let app = app(AppState {
+        orchestrator: Arc::new(orchestrator),
+        router: Arc::new(RouterAgent::new(llm.clone(), None, "ROUTER")),
+        memory: memory.clone(),
+        llm,
+        auth_required: false,
+        chunking: ChunkConfig::default(),
+        reloader: None,
+    });
+    post(
+        &app,
+        "/v1/knowledge/add",
+        json!({ "content": "The NAS admin password is hunter2.", "classification": "secret" }),
+    )
+    .await;
+
+    let ask = |prompt: &str, session: Option<&str>| {
+        let mut request = Request::builder()
+            .uri("/v1/ask")
+            .method("POST")
+            .header(header::CONTENT_TYPE, "application/json");
+        if let Some(session) = session {
+            request = request.header("x-session-id", session);
+        }
+        request
+            .body(Body::from(json!({ "prompt": prompt }).to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(ask("What is the NAS admin password?", None))
+        .await
+        .unwrap();
+    let session_id = response.headers()["x-session-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let answer: AskResponse = serde_json::from_slice(&bytes).unwrap();
+    assert!(answer.content.contains("hunter2"));
+    assert_eq!(answer.sources[0].classification, Classification::Secret);
+
+    // The answer carries the label of the knowledge it was built from...
+    let messages = memory.get_messages(&session_id).await.unwrap();
+    assert_eq!(messages[0].classification, Classification::Unclassified);
+    assert_eq!(messages[1].classification, Classification::Secret);
+
+    // ...so the casual agent does not see it in the history of the next question.
+    let response = app
+        .clone()
+        .oneshot(ask("Recap our conversation", Some(&session_id)))
+        .await
+        .unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let recap: AskResponse = serde_json::from_slice(&bytes).unwrap();
+    assert!(recap.content.contains("What is the NAS admin password?"));
+    assert!(!recap.content.contains("hunter2"));
+}
+
+#[tokio::test]
+async fn answers_only_use_knowledge_the_key_is_cleared_for() {
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+    memory
+        .add_classified_document(
+            "The NAS admin password is hunter2.",
+            vec![0.5; 3072],
+            Classification::Secret,
+        )
+        .await
+        .unwrap();
+    let (_, analyst) = memory
+        .create_cleared_api_key("analyst", &[Scope::Ask], Classification::Unclassified)
+        .await
+        .unwrap();
+    let (_, officer) = memory
+        .create_cleared_api_key("officer", &[Scope::Ask], Classification::Secret)
+        .await
+        .unwrap();
+
+    let llm = Arc::new(EchoLlm);
+    let orchestrator = Orchestrator::new();
+    orchestrator
+        .register(Box::new(
+            SpecializedAgent::new(llm.clone(), AgentCategory::new("red"), "SYSTEM", None)
+                .with_clearance(Classification::Secret),
+        ))
+        .await;
+    let app = app(AppState {
+        orchestrator: Arc::new(orchestrator),
+        router: Arc::new(RouterAgent::new(llm.clone(), None, "ROUTER")),
+        memory,
+        llm,
+        auth_required: true,
+        chunking: ChunkConfig::default(),
+        reloader: None,
+    });
+
+    let ask = |uri: &str, key: &str| {
+        Request::builder()
+            .uri(uri)
+            .method("POST")
+            .header(header::AUTHORIZATION, format!("Bearer {}", key))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                json!({ "prompt": "What is the NAS admin password?" }).to_string(),
+            ))
+            .unwrap()
+    };
+    let body = |uri: &'static str, key: String| {
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(ask(uri, &key)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            String::from_utf8(bytes.to_vec()).unwrap()
+        }
+    };
+
+    // The red agent is cleared for the secret document, the analyst's key is not.
+    let answer: AskResponse =
+        serde_json::from_str(&body("/v1/ask", analyst.clone()).await).unwrap();
+    assert!(answer.sources.is_empty());
+    assert!(!answer.content.contains("hunter2"));
+    let stream = body("/v1/ask/stream", analyst).await;
+    assert!(stream.contains(r#""documents":0"#), "{}", stream);
+    assert!(!stream.contains("hunter2"));
+
+    let answer: AskResponse = serde_json::from_str(&body("/v1/ask", officer).await).unwrap();
+    assert_eq!(answer.sources[0].classification, Classification::Secret);
+    assert!(answer.content.contains("hunter2"));
+}
@@ -3,6 +3,7 @@ use axum::{
     http::{Request, StatusCode},
 };
 use sensei_lib::agents::{Orchestrator, router::RouterAgent};
+use sensei_lib::ingest::ChunkConfig;
 use sensei_lib::llm::GeminiClient;
 use sensei_lib::memory::MemoryStore;
 use sensei_server::{AppState, app};
@@ -19,6 +20,9 @@ async fn health_check_works() {
         router: Arc::new(RouterAgent::new(llm.clone(), None, "Dummy Prompt")),
         memory,
         llm,
+        auth_required: false,
+        chunking: ChunkConfig::default(),
+        reloader: None,
     };
     let app = app(state);
 
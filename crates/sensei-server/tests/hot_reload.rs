@@ -0,0 +1,374 @@
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use sensei_common::AgentCategory;
+use sensei_lib::agents::{Orchestrator, router::RouterAgent};
+use sensei_lib::errors::SenseiError;
+use sensei_lib::ingest::ChunkConfig;
+use sensei_lib::llm::Llm;
+use sensei_lib::mcp_client::McpState;
+use sensei_lib::memory::MemoryStore;
+use sensei_lib::reload::{Reloader, SwarmConfig};
+use sensei_lib::tools::ToolRegistry;
+use sensei_server::{AppState, app};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+/// Answers with the prompt it was given.
+struct EchoLlm;
+
+#[async_trait]
+impl Llm for EchoLlm {
+    async fn generate(&self, prompt: &str) -> Result<String, SenseiError> {
+        Ok(prompt.to_string())
+    }
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>, SenseiError> {
+        Ok(vec![0.5; 3072])
+    }
+}
+
+struct Swarm {
+    dir: TempDir,
+    orchestrator: Arc<Orchestrator>,
+    router: Arc<RouterAgent>,
+    reloader: Arc<Reloader>,
+}
+
+impl Swarm {
+    fn new(prompts: &str) -> Self {
+        Self::with_mcp_env(prompts, HashMap::new())
+    }
+
+    fn with_mcp_env(prompts: &str, mcp_env: HashMap<String, String>) -> Self {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("prompts.yaml"), prompts).unwrap();
+
+        let llm: Arc<dyn Llm> = Arc::new(EchoLlm);
+        let orchestrator = Arc::new(Orchestrator::new());
+        let router = Arc::new(RouterAgent::new(llm.clone(), None, ""));
+        let config = SwarmConfig {
+            prompts_path: dir.path().join("prompts.yaml"),
+            mcp_path: dir.path().join("mcp_settings.json"),
+            llm_for: {
+                let llm = llm.clone();
+                Arc::new(move |_| llm.clone())
+            },
+            mcp_llm: llm,
+            tools: ToolRegistry::builtin(),
+            react_steps: 3,
+            mcp_backoff: (Duration::from_millis(50), Duration::from_millis(200)),
+            knowledge: None,
+            mcp_env,
+        };
+        let reloader = Arc::new(Reloader::new(config, orchestrator.clone(), router.clone()));
+        Self {
+            dir,
+            orchestrator,
+            router,
+            reloader,
+        }
+    }
+
+    fn write(&self, prompts: &str) {
+        write_file(&self.dir.path().join("prompts.yaml"), prompts);
+    }
+
+    async fn ask(&self, category: &str) -> String {
+        self.orchestrator
+            .dispatch(AgentCategory::new(category), "hello")
+            .await
+    }
+}
+
+fn write_file(path: &Path, contents: &str) {
+    std::fs::write(path, contents).unwrap();
+}
+
+const FORENSICS: &str = r#"
+agents:
+  forensics:
+    prompt: "You are a Forensics Examiner."
+"#;
+
+#[tokio::test]
+async fn reload_swaps_changed_agents_and_the_router_prompt() {
+    let swarm = Swarm::new(FORENSICS);
+    let report = swarm.reloader.reload().await.unwrap();
+    assert_eq!(report.added.len(), 10);
+    assert!(report.router);
+    assert!(swarm.router.system_prompt().contains("FORENSICS"));
+    assert!(swarm.ask("forensics").await.contains("Forensics Examiner"));
+
+    // Nothing changed on disk: nothing is rebuilt.
+    assert!(swarm.reloader.reload().await.unwrap().is_empty());
+
+    swarm.write(
+        r#"
+agents:
+  casual:
+    prompt: "You are Relaxed."
+  dfir:
+    prompt: "You are an Incident Responder."
+"#,
+    );
+    let report = swarm.reloader.reload().await.unwrap();
+    assert_eq!(report.added, ["dfir"]);
+    assert_eq!(report.updated, ["casual"]);
+    assert_eq!(report.removed, ["forensics"]);
+
+    assert!(swarm.ask("casual").await.contains("You are Relaxed."));
+    assert!(swarm.ask("dfir").await.contains("Incident Responder"));
+    // Removed categories fall back to casual.
+    assert!(swarm.ask("forensics").await.contains("You are Relaxed."));
+    let router = swarm.router.system_prompt();
+    assert!(router.contains("DFIR") && !router.contains("FORENSICS"));
+}
+
+#[tokio::test]
+async fn invalid_configuration_keeps_the_running_swarm() {
+    let swarm = Swarm::new(FORENSICS);
+    swarm.reloader.reload().await.unwrap();
+    let router = swarm.router.system_prompt();
+
+    for broken in [
+        "agents:\n  forensics: [not, a, map]\n",
+        "agents:\n  forensics:\n    prompt: P\n    tools: [metasploit]\n",
+        "agents:\n  dfir:\n    tier: smart\n",
+    ] {
+        swarm.write(broken);
+        assert!(matches!(
+            swarm.reloader.reload().await,
+            Err(SenseiError::Config(_))
+        ));
+        assert!(swarm.ask("forensics").await.contains("Forensics Examiner"));
+        assert_eq!(swarm.router.system_prompt(), router);
+    }
+
+    write_file(&swarm.dir.path().join("mcp_settings.json"), "{ not json");
+    swarm.write(FORENSICS);
+    assert!(swarm.reloader.reload().await.is_err());
+
+    // MCP servers are routed by their lowercased name: one must not shadow an agent.
+    for servers in [
+        r#"{ "mcpServers": { "Forensics": { "command": "/nonexistent/mcp", "args": [] } } }"#,
+        r#"{ "mcpServers": { "fs": { "command": "/nonexistent/mcp", "args": [] },
+                             "FS": { "command": "/nonexistent/mcp", "args": [] } } }"#,
+    ] {
+        write_file(&swarm.dir.path().join("mcp_settings.json"), servers);
+        assert!(matches!(
+            swarm.reloader.reload().await,
+            Err(SenseiError::Config(_))
+        ));
+        assert!(swarm.ask("forensics").await.contains("Forensics Examiner"));
+        assert!(swarm.reloader.mcp_status().await.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn file_changes_are_applied_without_a_restart() {
+    let swarm = Swarm::new(FORENSICS);
+    swarm.reloader.reload().await.unwrap();
+    swarm.reloader.watch().unwrap();
+
+    swarm.write("agents:\n  dfir:\n    prompt: \"You are an Incident Responder.\"\n");
+    for _ in 0..100 {
+        if swarm.router.system_prompt().contains("DFIR") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert!(swarm.ask("dfir").await.contains("Incident Responder"));
+}
+
+#[tokio::test]
+async fn admin_endpoint_triggers_a_reload() {
+    let swarm = Swarm::new(FORENSICS);
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+    let state = |reloader| AppState {
+        orchestrator: swarm.orchestrator.clone(),
+        router: swarm.router.clone(),
+        memory: memory.clone(),
+        llm: Arc::new(EchoLlm),
+        auth_required: false,
+        chunking: ChunkConfig::default(),
+        reloader,
+    };
+
+    let reload = |state| async move {
+        let request = Request::builder()
+            .uri("/v1/admin/reload")
+            .method("POST")
+            .body(Body::empty())
+            .unwrap();
+        let response = app(state).oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice::<Value>(&bytes).unwrap())
+    };
+
+    let (status, report) = reload(state(Some(swarm.reloader.clone()))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        report["added"]
+            .as_array()
+            .unwrap()
+            .contains(&Value::from("forensics"))
+    );
+
+    swarm.write("agents:\n  dfir:\n    tier: smart\n");
+    let (status, body) = reload(state(Some(swarm.reloader.clone()))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["error"].as_str().unwrap().contains("dfir"));
+
+    let (status, _) = reload(state(None)).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+}
+
+const MOCK_MCP: &str = r#"
+import sys, json
+for line in sys.stdin:
+    req = json.loads(line)
+    result = {"tools": [{"name": "ping"}]} if req.get("method") == "tools/list" else {}
+    print(json.dumps({"jsonrpc": "2.0", "id": req.get("id"), "result": result}), flush=True)
+"#;
+
+fn mcp_settings(env: &str) -> String {
+    serde_json::json!({
+        "mcpServers": {
+            "mock": { "command": "python3", "args": ["-c", MOCK_MCP], "env": { "MODE": env } }
+        }
+    })
+    .to_string()
+}
+
+#[tokio::test]
+async fn mcp_servers_restart_when_their_config_changes() {
+    let swarm = Swarm::new(FORENSICS);
+    let settings = swarm.dir.path().join("mcp_settings.json");
+    write_file(&settings, &mcp_settings("a"));
+
+    let report = swarm.reloader.reload().await.unwrap();
+    assert!(report.added.contains(&"mock".to_string()));
+    assert!(
+        swarm
+            .router
+            .system_prompt()
+            .contains("ACTIVE EXTENSIONS: MOCK")
+    );
+    let status = swarm.reloader.mcp_status().await;
+    assert_eq!(status["mock"].state, McpState::Running);
+
+    // Same name, new environment: the server is restarted.
+    write_file(&settings, &mcp_settings("b"));
+    let report = swarm.reloader.reload().await.unwrap();
+    assert_eq!(report.updated, ["mock"]);
+    assert_eq!(
+        swarm.reloader.mcp_status().await["mock"].state,
+        McpState::Running
+    );
+
+    // A server that cannot start is reported, and the running one is removed with its entry.
+    write_file(
+        &settings,
+        r#"{ "mcpServers": { "broken": { "command": "/nonexistent/mcp", "args": [] } } }"#,
+    );
+    let report = swarm.reloader.reload().await.unwrap();
+    assert_eq!(report.removed, ["mock"]);
+    let status = swarm.reloader.mcp_status().await;
+    assert_eq!(status.len(), 1);
+    assert_eq!(status["broken"].state, McpState::Failed);
+    assert!(status["broken"].last_error.is_some());
+}
+
+#[tokio::test]
+async fn a_failed_restart_keeps_the_running_server() {
+    let swarm = Swarm::new(FORENSICS);
+    let settings = swarm.dir.path().join("mcp_settings.json");
+    write_file(&settings, &mcp_settings("a"));
+    swarm.reloader.reload().await.unwrap();
+
+    write_file(
+        &settings,
+        r#"{ "mcpServers": { "mock": { "command": "/nonexistent/mcp", "args": [] } } }"#,
+    );
+    let report = swarm.reloader.reload().await.unwrap();
+    assert!(report.is_empty(), "{:?}", report);
+
+    // The previous instance still serves, and that is what the status shows.
+    let status = swarm.reloader.mcp_status().await;
+    assert_eq!(status.len(), 1);
+    assert_eq!(status["mock"].state, McpState::Running);
+    assert!(
+        swarm
+            .router
+            .system_prompt()
+            .contains("ACTIVE EXTENSIONS: MOCK")
+    );
+}
+
+#[tokio::test]
+async fn status_does_not_wait_for_servers_being_started() {
+    let swarm = Arc::new(Swarm::new(FORENSICS));
+    swarm.reloader.reload().await.unwrap();
+    let slow = format!("import time\ntime.sleep(2)\n{}", MOCK_MCP);
+    let settings = serde_json::json!({
+        "mcpServers": { "slow": { "command": "python3", "args": ["-c", slow] } }
+    });
+    write_file(
+        &swarm.dir.path().join("mcp_settings.json"),
+        &settings.to_string(),
+    );
+
+    let reloading = {
+        let swarm = swarm.clone();
+        tokio::spawn(async move { swarm.reloader.reload().await.unwrap() })
+    };
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let status = tokio::time::timeout(Duration::from_millis(500), swarm.reloader.mcp_status())
+        .await
+        .expect("status is answered while the server starts");
+    assert!(status.is_empty());
+
+    let report = reloading.await.unwrap();
+    assert_eq!(report.added, ["slow"]);
+    assert_eq!(
+        swarm.reloader.mcp_status().await["slow"].state,
+        McpState::Running
+    );
+}
+
+#[tokio::test]
+async fn spawned_mcp_servers_get_the_swarm_environment() {
+    let env = HashMap::from([("SENSEI_MCP_NESTED".to_string(), "1".to_string())]);
+    let swarm = Swarm::with_mcp_env(FORENSICS, env);
+    let seen = swarm.dir.path().join("seen");
+    let script = format!(
+        "import os\nopen({:?}, 'w').write(os.environ.get('SENSEI_MCP_NESTED', '') + os.environ.get('MODE', ''))\n{}",
+        seen.to_string_lossy(),
+        MOCK_MCP
+    );
+    let settings = serde_json::json!({
+        "mcpServers": {
+            "mock": { "command": "python3", "args": ["-c", script], "env": { "MODE": "a" } }
+        }
+    });
+    write_file(
+        &swarm.dir.path().join("mcp_settings.json"),
+        &settings.to_string(),
+    );
+
+    swarm.reloader.reload().await.unwrap();
+    // Set on top of the server's own environment, not in this process.
+    assert_eq!(std::fs::read_to_string(&seen).unwrap(), "1a");
+    assert!(std::env::var_os("SENSEI_MCP_NESTED").is_none());
+}
@@ -0,0 +1,193 @@
+use async_trait::async_trait;
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use sensei_common::Classification;
+use sensei_lib::agents::{Orchestrator, router::RouterAgent};
+use sensei_lib::errors::SenseiError;
+use sensei_lib::ingest::ChunkConfig;
+use sensei_lib::llm::Llm;
+use sensei_lib::memory::MemoryStore;
+use sensei_server::{AppState, app};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tower::ServiceExt;
+
+/// Embeds every text as the same vector and counts the calls.
+#[derive(Default)]
+struct FakeEmbedder {
+    embeds: AtomicUsize,
+}
+
+#[async_trait]
+impl Llm for FakeEmbedder {
+    async fn generate(&self, _prompt: &str) -> Result<String, SenseiError> {
+        Ok(String::new())
+    }
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>, SenseiError> {
+        self.embeds.fetch_add(1, Ordering::SeqCst);
+        Ok(vec![0.5; 3072])
+    }
+}
+
+async fn setup() -> (Router, MemoryStore, Arc<FakeEmbedder>) {
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+
+    let llm = Arc::new(FakeEmbedder::default());
+    let state = AppState {
+        orchestrator: Arc::new(Orchestrator::new()),
+        router: Arc::new(RouterAgent::new(llm.clone(), None, "Dummy Prompt")),
+        memory: memory.clone(),
+        llm: llm.clone(),
+        auth_required: false,
+        chunking: ChunkConfig::default(),
+        reloader: None,
+    };
+    (app(state), memory, llm)
+}
+
+async fn send(app: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let mut request = Request::builder().uri(uri).method(method);
+    let body = match body {
+        Some(json) => {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+            Body::from(json.to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+async fn add(app: &Router, source: &str, content: &str, tags: &[&str]) {
+    let (status, _) = send(
+        app,
+        "POST",
+        "/v1/knowledge/add",
+        Some(json!({ "content": content, "source": source, "tags": tags })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn documents_can_be_listed_and_filtered() {
+    let (app, _, _) = setup().await;
+    add(
+        &app,
+        "runbook.md",
+        "# Backup\n\nNightly.\n\n# Restore\n\nFrom NAS.",
+        &["ops"],
+    )
+    .await;
+    add(&app, "notes.md", "Team lunch on friday.", &[]).await;
+
+    let (status, page) = send(&app, "GET", "/v1/knowledge", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["total"], 3);
+
+    let (_, page) = send(&app, "GET", "/v1/knowledge?tag=ops&limit=1", None).await;
+    assert_eq!(page["total"], 2);
+    assert_eq!(page["documents"].as_array().unwrap().len(), 1);
+    assert_eq!(page["documents"][0]["metadata"]["source"], "runbook.md");
+
+    let (_, page) = send(&app, "GET", "/v1/knowledge?source=notes.md", None).await;
+    assert_eq!(page["total"], 1);
+    let id = page["documents"][0]["id"].as_i64().unwrap();
+
+    let (status, doc) = send(&app, "GET", &format!("/v1/knowledge/{}", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(doc["content"], "Team lunch on friday.");
+
+    let (status, _) = send(&app, "GET", "/v1/knowledge/9999", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn updates_reembed_changed_content_only() {
+    let (app, memory, llm) = setup().await;
+    add(&app, "notes.md", "Team lunch on friday.", &[]).await;
+    let id = memory.list_documents().await.unwrap()[0].0;
+    let embeds = llm.embeds.load(Ordering::SeqCst);
+
+    let (status, doc) = send(
+        &app,
+        "PATCH",
+        &format!("/v1/knowledge/{}", id),
+        Some(json!({ "classification": "secret", "tags": ["hr"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(doc["metadata"]["classification"], "secret");
+    assert_eq!(doc["metadata"]["tags"], json!(["hr"]));
+    assert_eq!(llm.embeds.load(Ordering::SeqCst), embeds);
+
+    let (status, doc) = send(
+        &app,
+        "PATCH",
+        &format!("/v1/knowledge/{}", id),
+        Some(json!({ "content": "Team lunch moved to thursday." })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(doc["content"], "Team lunch moved to thursday.");
+    assert_eq!(llm.embeds.load(Ordering::SeqCst), embeds + 1);
+
+    // The edited chunk no longer matches its source, so re-adding the file is not skipped.
+    assert!(memory.source_hash("notes.md").await.unwrap().is_none());
+
+    // The label change is enforced on retrieval.
+    let results = memory
+        .search_documents("", vec![0.5; 3072], 5, Classification::Unclassified, "test")
+        .await
+        .unwrap();
+    assert!(results.is_empty());
+}
+
+#[tokio::test]
+async fn deletes_remove_documents_and_vectors() {
+    let (app, memory, _) = setup().await;
+    add(
+        &app,
+        "runbook.md",
+        "# Backup\n\nNightly.\n\n# Restore\n\nFrom NAS.",
+        &[],
+    )
+    .await;
+    add(&app, "notes.md", "Team lunch on friday.", &[]).await;
+
+    let (_, page) = send(&app, "GET", "/v1/knowledge?source=notes.md", None).await;
+    let id = page["documents"][0]["id"].as_i64().unwrap();
+
+    let (status, _) = send(&app, "DELETE", &format!("/v1/knowledge/{}", id), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&app, "DELETE", &format!("/v1/knowledge/{}", id), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, result) = send(&app, "DELETE", "/v1/knowledge?source=runbook.md", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["deleted"], 2);
+
+    assert!(memory.list_documents().await.unwrap().is_empty());
+    let results = memory
+        .search_documents("", vec![0.5; 3072], 5, Classification::TopSecret, "test")
+        .await
+        .unwrap();
+    assert!(results.is_empty(), "no orphaned vectors remain");
+}
@@ -0,0 +1,150 @@
+#![cfg(target_os = "linux")]
+
+use axum::Router;
+use sensei_lib::agents::{Orchestrator, router::RouterAgent};
+use sensei_lib::ingest::ChunkConfig;
+use sensei_lib::llm::GeminiClient;
+use sensei_lib::memory::MemoryStore;
+use sensei_server::peer::{PeerCredListener, PeerIdentity, PeerPolicy};
+use sensei_server::{AppState, app};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+
+fn own_ids() -> (u32, u32) {
+    let meta = std::fs::metadata("/proc/self").unwrap();
+    (meta.uid(), meta.gid())
+}
+
+async fn setup() -> (Router, MemoryStore) {
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+
+    let llm = Arc::new(GeminiClient::new("dummy"));
+    let state = AppState {
+        orchestrator: Arc::new(Orchestrator::new()),
+        router: Arc::new(RouterAgent::new(llm.clone(), None, "Dummy Prompt")),
+        memory: memory.clone(),
+        llm,
+        auth_required: false,
+        chunking: ChunkConfig::default(),
+        reloader: None,
+    };
+    (app(state), memory)
+}
+
+fn serve(path: &Path, app: Router, policy: PeerPolicy) {
+    let listener = UnixListener::bind(path).unwrap();
+    tokio::spawn(async move {
+        axum::serve(
+            PeerCredListener::new(listener, policy),
+            app.into_make_service_with_connect_info::<PeerIdentity>(),
+        )
+        .await
+        .unwrap();
+    });
+}
+
+/// Send a raw HTTP/1.1 request and return everything the server wrote back.
+async fn request(path: &Path, raw: &str) -> String {
+    let mut stream = UnixStream::connect(path).await.unwrap();
+    // A rejected peer may already be disconnected.
+    let _ = stream.write_all(raw.as_bytes()).await;
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response).await;
+    String::from_utf8_lossy(&response).to_string()
+}
+
+#[tokio::test]
+async fn socketpair_peer_is_identified() {
+    let (uid, gid) = own_ids();
+    let (a, _b) = UnixStream::pair().unwrap();
+
+    let peer = PeerIdentity::from_stream(&a).unwrap();
+    assert_eq!(peer.uid, uid);
+    assert_eq!(peer.gid, gid);
+    assert_eq!(peer.pid, Some(std::process::id() as i32));
+    assert_eq!(peer.caller().as_str(), format!("uid:{}", uid));
+}
+
+#[tokio::test]
+async fn policy_matches_users_and_groups() {
+    let (uid, gid) = own_ids();
+    let (a, _b) = UnixStream::pair().unwrap();
+    let peer = PeerIdentity::from_stream(&a).unwrap();
+
+    assert!(PeerPolicy::owner(uid).allows(&peer));
+    assert!(!PeerPolicy::owner(uid + 1).allows(&peer));
+
+    let by_group = PeerPolicy {
+        uids: vec![uid + 1],
+        gids: vec![gid],
+    };
+    assert!(by_group.allows(&peer));
+    assert!(by_group.is_shared());
+
+    // Only the primary group of the credentials counts.
+    let other_group = PeerIdentity {
+        uid: uid + 2,
+        gid: gid + 1,
+        pid: peer.pid,
+    };
+    assert!(!by_group.allows(&other_group));
+    assert!(!PeerPolicy::owner(uid).is_shared());
+}
+
+#[tokio::test]
+async fn allowed_peers_are_served_and_attributed() {
+    let (uid, _) = own_ids();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("sensei.sock");
+    let (app, memory) = setup().await;
+    serve(&path, app, PeerPolicy::owner(uid));
+
+    let health = request(
+        &path,
+        "GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    )
+    .await;
+    assert!(health.starts_with("HTTP/1.1 200"), "{}", health);
+
+    let body = r#"{"prompt":"Hello Sensei"}"#;
+    let ask = request(
+        &path,
+        &format!(
+            "POST /v1/ask HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+             Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        ),
+    )
+    .await;
+    assert!(ask.starts_with("HTTP/1.1 200"), "{}", ask);
+
+    let sessions = memory.list_sessions().await.unwrap();
+    let messages = memory.get_messages(&sessions[0].id).await.unwrap();
+    let expected = format!("uid:{}", uid);
+    assert!(
+        messages
+            .iter()
+            .all(|m| m.caller.as_deref() == Some(expected.as_str()))
+    );
+}
+
+#[tokio::test]
+async fn peers_outside_the_allowlist_are_dropped() {
+    let (uid, _) = own_ids();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("sensei.sock");
+    let (app, _) = setup().await;
+    serve(&path, app, PeerPolicy::owner(uid + 1));
+
+    let response = request(
+        &path,
+        "GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    )
+    .await;
+    assert!(response.is_empty(), "{}", response);
+}
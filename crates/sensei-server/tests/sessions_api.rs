@@ -0,0 +1,160 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use sensei_lib::agents::{Orchestrator, router::RouterAgent};
+use sensei_lib::ingest::ChunkConfig;
+use sensei_lib::llm::GeminiClient;
+use sensei_lib::memory::MemoryStore;
+use sensei_server::{AppState, app};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+async fn setup() -> (Router, MemoryStore) {
+    let memory = MemoryStore::new("sqlite::memory:").await.unwrap();
+    memory.migrate().await.unwrap();
+
+    let llm = Arc::new(GeminiClient::new("dummy"));
+    let state = AppState {
+        orchestrator: Arc::new(Orchestrator::new()),
+        router: Arc::new(RouterAgent::new(llm.clone(), None, "Dummy Prompt")),
+        memory: memory.clone(),
+        llm,
+        auth_required: false,
+        chunking: ChunkConfig::default(),
+        reloader: None,
+    };
+    (app(state), memory)
+}
+
+async fn send(app: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, String) {
+    let mut request = Request::builder().uri(uri).method(method);
+    let body = match body {
+        Some(json) => {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+            Body::from(json.to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn sessions_can_be_listed_renamed_and_deleted() {
+    let (app, memory) = setup().await;
+    let first = memory.create_session(Some("First")).await.unwrap();
+    let second = memory.create_session(None).await.unwrap();
+
+    let (status, body) = send(&app, "GET", "/v1/sessions?limit=1", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let page: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(page["sessions"].as_array().unwrap().len(), 1);
+    assert_eq!(page["sessions"][0]["id"], second.as_str());
+
+    let (status, body) = send(
+        &app,
+        "PATCH",
+        &format!("/v1/sessions/{}", second),
+        Some(json!({ "title": "Recon of 10.0.0.0/24" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let renamed: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(renamed["title"], "Recon of 10.0.0.0/24");
+
+    let (status, _) = send(&app, "DELETE", &format!("/v1/sessions/{}", first), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = send(&app, "GET", &format!("/v1/sessions/{}", first), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn unknown_sessions_return_404() {
+    let (app, _) = setup().await;
+
+    for (method, uri, body) in [
+        ("GET", "/v1/sessions/missing", None),
+        ("GET", "/v1/sessions/missing/messages", None),
+        ("GET", "/v1/sessions/missing/export", None),
+        ("DELETE", "/v1/sessions/missing", None),
+        (
+            "PATCH",
+            "/v1/sessions/missing",
+            Some(json!({ "title": "Nope" })),
+        ),
+    ] {
+        let (status, _) = send(&app, method, uri, body).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{} {}", method, uri);
+    }
+}
+
+#[tokio::test]
+async fn messages_are_paginated_and_exported() {
+    let (app, memory) = setup().await;
+    let id = memory.create_session(Some("Export me")).await.unwrap();
+    for i in 0..5 {
+        let role = if i % 2 == 0 { "user" } else { "assistant" };
+        memory
+            .add_message(&id, role, &format!("message {}", i))
+            .await
+            .unwrap();
+    }
+
+    let (status, body) = send(
+        &app,
+        "GET",
+        &format!("/v1/sessions/{}/messages?limit=2&offset=2", id),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let page: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(page["total"], 5);
+    assert_eq!(page["messages"][0]["content"], "message 2");
+    assert_eq!(page["messages"][1]["content"], "message 3");
+
+    let (status, body) = send(&app, "GET", &format!("/v1/sessions/{}", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let session: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(session["message_count"], 5);
+
+    let (status, markdown) = send(&app, "GET", &format!("/v1/sessions/{}/export", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(markdown.starts_with("# Export me"));
+    assert!(markdown.contains("message 0"));
+    assert!(markdown.find("message 0").unwrap() < markdown.find("message 4").unwrap());
+
+    let (status, body) = send(
+        &app,
+        "GET",
+        &format!("/v1/sessions/{}/export?format=json", id),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let export: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(export["session"]["title"], "Export me");
+    assert_eq!(export["messages"].as_array().unwrap().len(), 5);
+
+    let (status, _) = send(
+        &app,
+        "GET",
+        &format!("/v1/sessions/{}/export?format=pdf", id),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}